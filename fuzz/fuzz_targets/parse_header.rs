@@ -0,0 +1,34 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use ppot_rs::ptau::PtauHeader;
+
+// Build a file around an arbitrary header so that the fuzzer spends its time
+// past the magic string and section table checks
+fuzz_target!(|input: (PtauHeader, Vec<u8>)| {
+    let (header, payload) = input;
+    let mut payload = payload.as_slice();
+
+    let mut data = b"ptau".to_vec();
+    data.extend_from_slice(&1u32.to_le_bytes());
+    data.extend_from_slice(&(header.sections.len() as u32).to_le_bytes());
+    for section in header.sections.iter() {
+        let contents = if section.id == 1 {
+            let mut contents = header.n8.to_le_bytes().to_vec();
+            contents.extend_from_slice(&header.q);
+            contents.extend_from_slice(&header.power.to_le_bytes());
+            contents.extend_from_slice(&header.ceremony_power.to_le_bytes());
+            contents
+        } else {
+            let len = (section.size as usize).min(payload.len());
+            let (contents, rest) = payload.split_at(len);
+            payload = rest;
+            contents.to_vec()
+        };
+        data.extend_from_slice(&section.id.to_le_bytes());
+        data.extend_from_slice(&(contents.len() as u64).to_le_bytes());
+        data.extend_from_slice(&contents);
+    }
+
+    let _ = ppot_rs::ptau::parse(&data);
+});
@@ -0,0 +1,157 @@
+//! An opt-in, append-only audit trail of the operations performed on ptau
+//! files, written as one JSON object per line.
+
+use crate::checksum::to_hex;
+use crate::ptau::{io_error, Error};
+use blake2::{Blake2b512, Digest};
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+struct AuditLog {
+    file: File,
+    hash_inputs: bool,
+}
+
+fn audit_log() -> &'static Mutex<Option<AuditLog>> {
+    static AUDIT_LOG: OnceLock<Mutex<Option<AuditLog>>> = OnceLock::new();
+    AUDIT_LOG.get_or_init(|| Mutex::new(None))
+}
+
+/// Start appending a record of every operation on a ptau file to `path`:
+/// the operation and its parameters, the input file, the outcome and the
+/// duration.
+///
+/// With `hash_inputs`, the BLAKE2b-512 hash of each input file is recorded
+/// as well, which takes an extra pass over the file.
+pub fn enable<P: AsRef<Path>>(path: P, hash_inputs: bool) -> Result<(), Error> {
+    let file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(io_error)?;
+    *audit_log().lock().unwrap() = Some(AuditLog { file, hash_inputs });
+    Ok(())
+}
+
+pub fn disable() {
+    *audit_log().lock().unwrap() = None;
+}
+
+// Run `f`, an operation on `ptau_file`, recording it if auditing is enabled
+pub(crate) fn record<T>(
+    operation: &str,
+    ptau_file: &str,
+    params: &[(&str, u64)],
+    f: impl FnOnce() -> Result<T, Error>,
+) -> Result<T, Error> {
+    // Operations may be nested, so don't hold the lock while running `f`
+    let hash_inputs = audit_log()
+        .lock()
+        .unwrap()
+        .as_ref()
+        .map(|log| log.hash_inputs);
+    let hash_inputs = match hash_inputs {
+        Some(hash_inputs) => hash_inputs,
+        None => return f(),
+    };
+    let hash = if hash_inputs {
+        Some(hash_file(ptau_file))
+    } else {
+        None
+    };
+
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    let start = Instant::now();
+    let r = f();
+    let duration = start.elapsed();
+
+    let mut line = format!(
+        "{{\"timestamp\":{},\"operation\":{},\"file\":{}",
+        timestamp,
+        json_string(operation),
+        json_string(ptau_file)
+    );
+    match hash {
+        Some(Ok(hash)) => line += &format!(",\"blake2b\":\"{}\"", hash),
+        Some(Err(e)) => line += &format!(",\"blake2b_error\":{}", json_string(&format!("{:?}", e))),
+        None => {}
+    }
+    for (name, value) in params {
+        line += &format!(",{}:{}", json_string(name), value);
+    }
+    match &r {
+        Ok(_) => line += ",\"outcome\":\"ok\"",
+        Err(e) => line += &format!(",\"outcome\":{}", json_string(&format!("{:?}", e))),
+    }
+    line += &format!(",\"duration_ms\":{}}}\n", duration.as_millis());
+
+    // The log may have been disabled in the meantime
+    if let Some(log) = audit_log().lock().unwrap().as_mut() {
+        if let Err(e) = log.file.write_all(line.as_bytes()) {
+            log::error!("failed to write to the audit log: {}", e);
+        }
+    }
+    r
+}
+
+fn hash_file(path: &str) -> Result<String, Error> {
+    let mut f = File::open(path).map_err(io_error)?;
+    let mut hasher = Blake2b512::new();
+    std::io::copy(&mut f, &mut hasher).map_err(io_error)?;
+    Ok(to_hex(&hasher.finalize()))
+}
+
+/// `s` as a quoted JSON string, as the audit log writes file names.
+pub fn json_string(s: &str) -> String {
+    let mut out = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => out += "\\\"",
+            '\\' => out += "\\\\",
+            c if (c as u32) < 0x20 => out += &format!("\\u{:04x}", c as u32),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ptau::{read, Error};
+
+    #[test]
+    pub fn test_audit_log() {
+        let path = std::env::temp_dir().join("ppot-rs-audit.jsonl");
+        let _ = std::fs::remove_file(&path);
+        super::enable(&path, true).unwrap();
+        read("8.ptau", 2, 1).unwrap();
+        assert_eq!(read("8.ptau", 512, 1), Err(Error::InvalidNumG1Points));
+        super::disable();
+        read("8.ptau", 2, 1).unwrap();
+
+        // Other tests may read files while the log is enabled
+        let log = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = log
+            .lines()
+            .filter(|l| l.contains("\"file\":\"8.ptau\"") && l.contains("\"num_g2_points\":1,"))
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].contains("\"operation\":\"read\""));
+        assert!(lines[0].contains("\"num_g1_points\":2,"));
+        assert!(lines[0].contains("\"blake2b\":\""));
+        assert!(lines[0].contains("\"outcome\":\"ok\""));
+        assert!(lines[1].contains("\"num_g1_points\":512,"));
+        assert!(lines[1].contains("\"outcome\":\"InvalidNumG1Points\""));
+    }
+
+    #[test]
+    pub fn test_json_string() {
+        assert_eq!(super::json_string("a\"b\\c\n"), "\"a\\\"b\\\\c\\u000a\"");
+    }
+}
@@ -0,0 +1,218 @@
+//! Selection of how points are read and decoded, so that reads are fast
+//! without tuning `ReadOptions`.
+
+use crate::ptau::{Error, ReadOptions};
+
+// Reads smaller than this are decoded on the calling thread, as handing
+// chunks to the rayon pool costs more than it saves
+#[cfg(feature = "parallel")]
+const PARALLEL_MIN_BYTES: u64 = 1 << 20;
+
+// Reads smaller than this are not worth setting up a memory map for
+#[cfg(feature = "mmap")]
+const MMAP_MIN_BYTES: u64 = 1 << 26;
+
+/// How points are read from a file and decoded, set with
+/// `ReadOptions::backend`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Backend {
+    /// Pick one of the others from the number of bytes to read, the size of
+    /// the file, the memory available, the threads of the rayon pool and
+    /// the throttle (the default). With the `mmap` feature, large reads
+    /// from a path may map the file, which must then not be modified while
+    /// it is read.
+    #[default]
+    Auto,
+    /// Read through a buffer, decoding points one at a time on the calling
+    /// thread.
+    Buffered,
+    /// Read through a buffer a chunk at a time, decoding the points of each
+    /// chunk on the rayon pool. Requires the `parallel` feature.
+    #[cfg(feature = "parallel")]
+    Parallel,
+    /// Map the file into memory and decode points on the rayon pool, as
+    /// `mmap::read_mmap` does. Requires the `mmap` feature. Only reads of
+    /// tau powers from a path, other than `read_recover`, map the file;
+    /// other reads fall back to `Auto` without mapping.
+    #[cfg(feature = "mmap")]
+    Mmap,
+}
+
+// What the choice of backend is made from. Without the `parallel` and
+// `mmap` features, there is little to choose from.
+#[cfg_attr(not(all(feature = "parallel", feature = "mmap")), allow(dead_code))]
+pub(crate) struct Facts {
+    requested_bytes: u64,
+    // Only known for reads from a path, which are the only ones that can be
+    // mapped
+    file_size: Option<u64>,
+    available_memory: Option<u64>,
+    threads: usize,
+    throttled: bool,
+}
+
+impl Facts {
+    pub(crate) fn new(requested_bytes: u64, file_size: Option<u64>, options: &ReadOptions) -> Self {
+        Self {
+            requested_bytes,
+            file_size,
+            available_memory: file_size.and_then(|_| available_memory()),
+            threads: options.num_threads(),
+            throttled: options.bytes_per_second().is_some(),
+        }
+    }
+}
+
+/// The backend `ptau::read_with_options` would use to read the first
+/// `num_g1_points` and `num_g2_points` tau powers of a file.
+pub fn select(
+    ptau_file: &str,
+    num_g1_points: usize,
+    num_g2_points: usize,
+    options: &ReadOptions,
+) -> Result<Backend, Error> {
+    let file_size = std::fs::metadata(ptau_file).map_err(Error::Io)?.len();
+    let requested_bytes = num_g1_points as u64 * 64 + num_g2_points as u64 * 128;
+    Ok(choose(
+        options.backend_choice(),
+        &Facts::new(requested_bytes, Some(file_size), options),
+    ))
+}
+
+// Resolve `requested` to a backend other than `Auto`
+pub(crate) fn choose(requested: Backend, facts: &Facts) -> Backend {
+    match requested {
+        Backend::Auto => choose_auto(facts),
+        #[cfg(feature = "mmap")]
+        Backend::Mmap if facts.file_size.is_none() => choose_buffered(facts),
+        backend => backend,
+    }
+}
+
+fn choose_auto(facts: &Facts) -> Backend {
+    // Large files can't be mapped in a 32-bit address space. The throttle
+    // can't apply to a mapped file, and mapping more than fits in memory
+    // thrashes the page cache.
+    #[cfg(all(feature = "mmap", target_pointer_width = "64"))]
+    if facts.file_size.is_some()
+        && !facts.throttled
+        && facts.requested_bytes >= MMAP_MIN_BYTES
+        && facts
+            .available_memory
+            .is_none_or(|memory| facts.requested_bytes <= memory)
+    {
+        return Backend::Mmap;
+    }
+    choose_buffered(facts)
+}
+
+#[cfg_attr(not(feature = "parallel"), allow(unused_variables))]
+fn choose_buffered(facts: &Facts) -> Backend {
+    #[cfg(feature = "parallel")]
+    if facts.threads > 1 && facts.requested_bytes >= PARALLEL_MIN_BYTES {
+        return Backend::Parallel;
+    }
+    Backend::Buffered
+}
+
+// MemAvailable from /proc/meminfo, on systems that have it
+fn available_memory() -> Option<u64> {
+    let meminfo = std::fs::read_to_string("/proc/meminfo").ok()?;
+    let line = meminfo
+        .lines()
+        .find(|line| line.starts_with("MemAvailable:"))?;
+    let kib: u64 = line.split_whitespace().nth(1)?.parse().ok()?;
+    Some(kib * 1024)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{choose, select, Backend, Facts};
+    use crate::ptau::{read, read_with_options, ReadOptions};
+
+    fn facts(requested_bytes: u64, file_size: Option<u64>) -> Facts {
+        Facts {
+            requested_bytes,
+            file_size,
+            available_memory: Some(1 << 32),
+            threads: 8,
+            throttled: false,
+        }
+    }
+
+    #[test]
+    pub fn test_choose() {
+        assert_eq!(
+            choose(Backend::Buffered, &facts(1 << 30, None)),
+            Backend::Buffered
+        );
+        assert_eq!(
+            choose(Backend::Auto, &facts(1 << 10, Some(1 << 30))),
+            Backend::Buffered
+        );
+        let single_thread = Facts {
+            threads: 1,
+            ..facts(1 << 24, None)
+        };
+        assert_eq!(choose(Backend::Auto, &single_thread), Backend::Buffered);
+
+        #[cfg(feature = "parallel")]
+        {
+            assert_eq!(
+                choose(Backend::Auto, &facts(1 << 24, None)),
+                Backend::Parallel
+            );
+            assert_eq!(
+                choose(Backend::Parallel, &facts(1, None)),
+                Backend::Parallel
+            );
+        }
+        #[cfg(all(feature = "mmap", target_pointer_width = "64"))]
+        {
+            assert_eq!(
+                choose(Backend::Auto, &facts(1 << 30, Some(1 << 31))),
+                Backend::Mmap
+            );
+            // Not for readers that aren't files, throttled reads or reads
+            // that don't fit in memory
+            assert_ne!(choose(Backend::Auto, &facts(1 << 30, None)), Backend::Mmap);
+            assert_ne!(choose(Backend::Mmap, &facts(1 << 30, None)), Backend::Mmap);
+            let throttled = Facts {
+                throttled: true,
+                ..facts(1 << 30, Some(1 << 31))
+            };
+            assert_ne!(choose(Backend::Auto, &throttled), Backend::Mmap);
+            let too_large = Facts {
+                available_memory: Some(1 << 29),
+                ..facts(1 << 30, Some(1 << 31))
+            };
+            assert_ne!(choose(Backend::Auto, &too_large), Backend::Mmap);
+        }
+    }
+
+    #[test]
+    pub fn test_backends() {
+        let options = ReadOptions::default();
+        assert_eq!(
+            select("8.ptau", 511, 256, &options).unwrap(),
+            Backend::Buffered
+        );
+
+        let expected = read("8.ptau", 511, 256).unwrap();
+        let backends = [
+            Backend::Auto,
+            Backend::Buffered,
+            #[cfg(feature = "parallel")]
+            Backend::Parallel,
+            #[cfg(feature = "mmap")]
+            Backend::Mmap,
+        ];
+        for backend in backends {
+            let options = ReadOptions::default().backend(backend);
+            assert_eq!(
+                read_with_options("8.ptau", 511, 256, &options).unwrap(),
+                expected
+            );
+        }
+    }
+}
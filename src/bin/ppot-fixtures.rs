@@ -0,0 +1,127 @@
+//! Writes a standard set of small ptau files and the values they are
+//! expected to give into a directory, for other crates' test suites.
+//! Requires the `fixtures` feature.
+//!
+//! ```text
+//! ppot-fixtures <dir>
+//! ```
+//!
+//! The files are generated from fixed secrets, so every run writes the same
+//! bytes. For each power `p` from 1 to 4, `power_p.ptau` is a prepared file
+//! with all the sections snarkjs writes, `power_p.ptau.sums` its
+//! checksum manifest and `power_p.json` its secrets, header values and
+//! verifier key digest. `mutations/` holds corrupted copies of
+//! `power_2.ptau`, listed in `mutations.json` with the error reading each
+//! one gives. zkey files are not generated, as this crate does not read
+//! them.
+
+use ark_bn254::Fr;
+use ark_ff::{BigInteger, PrimeField};
+use ppot_rs::checksum::write_manifest;
+use ppot_rs::ptau::{parse, read_header, ReadOptions};
+use ppot_rs::testing::{generate_from_secrets, Mutation};
+use ppot_rs::vk::KzgVerifierKey;
+use std::path::Path;
+use std::process::ExitCode;
+
+// Power 0 files have no [tau]_2 for the verifier key
+const MAX_POWER: u32 = 4;
+const MUTATED_POWER: u32 = 2;
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let [dir] = &args[..] else {
+        eprintln!("usage: ppot-fixtures <dir>");
+        return ExitCode::from(2);
+    };
+    match write_fixtures(Path::new(dir)) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("{}: {}", dir, e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn write_fixtures(dir: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    let (tau, alpha, beta) = secrets();
+    std::fs::create_dir_all(dir.join("mutations"))?;
+    for power in 1..=MAX_POWER {
+        let data = generate_from_secrets(tau, alpha, beta, power);
+        let ptau_file = dir.join(format!("power_{}.ptau", power));
+        std::fs::write(&ptau_file, &data)?;
+        let ptau_file = ptau_file.to_str().ok_or("non UTF-8 path")?;
+        write_manifest(ptau_file)?;
+
+        let header = read_header(ptau_file)?;
+        let (num_g1_points, num_g2_points) = header.num_points()?;
+        let vk = KzgVerifierKey::read(ptau_file, &ReadOptions::default())?;
+        let json = format!(
+            "{{\"file\":\"power_{}.ptau\",\"tau\":\"{}\",\"alpha\":\"{}\",\"beta\":\"{}\",\
+             \"power\":{},\"ceremony_power\":{},\"num_g1_points\":{},\"num_g2_points\":{},\
+             \"vk_keccak\":\"{}\"}}\n",
+            power,
+            fr_hex(&tau),
+            fr_hex(&alpha),
+            fr_hex(&beta),
+            header.power,
+            header.ceremony_power,
+            num_g1_points,
+            num_g2_points,
+            hex(&vk.keccak_digest()),
+        );
+        std::fs::write(dir.join(format!("power_{}.json", power)), json)?;
+    }
+
+    let data = generate_from_secrets(tau, alpha, beta, MUTATED_POWER);
+    let mutations = [
+        ("magic_string", Mutation::MagicString),
+        ("version", Mutation::Version),
+        ("num_sections", Mutation::NumSections),
+        ("modulus", Mutation::Modulus),
+        ("g1_point", Mutation::G1Point(1)),
+        ("g2_point", Mutation::G2Point(1)),
+        ("non_canonical_g1", Mutation::NonCanonicalG1(1)),
+        ("truncate", Mutation::Truncate),
+    ];
+    let mut entries = Vec::new();
+    for (name, mutation) in mutations {
+        let mutated = mutation.apply(&data);
+        let error = parse(&mutated)
+            .err()
+            .ok_or("mutation left the file valid")?;
+        std::fs::write(
+            dir.join("mutations").join(format!("{}.ptau", name)),
+            mutated,
+        )?;
+        entries.push(format!(
+            "{{\"file\":\"mutations/{}.ptau\",\"error\":\"{}\"}}",
+            name,
+            format!("{:?}", error)
+                .replace('\\', "\\\\")
+                .replace('"', "\\\"")
+        ));
+    }
+    std::fs::write(
+        dir.join("mutations.json"),
+        format!("[{}]\n", entries.join(",")),
+    )?;
+    Ok(())
+}
+
+// Arbitrary but fixed secrets
+fn secrets() -> (Fr, Fr, Fr) {
+    (
+        Fr::from(1_000_003u64),
+        Fr::from(1_000_033u64),
+        Fr::from(1_000_037u64),
+    )
+}
+
+fn fr_hex(fr: &Fr) -> String {
+    hex(&fr.into_repr().to_bytes_be())
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
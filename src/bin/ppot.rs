@@ -0,0 +1,303 @@
+//! The ppot command line tool. Requires the `cli` feature.
+//!
+//! ```text
+//! ppot [<read options>] verify-contributions [--json] [--first-challenge <hex>] <file.ptau>
+//! ppot [<read options>] truncate <input.ptau> <output.ptau> <power>
+//! ppot [<read options>] prepare-phase2 <input.ptau> <output.ptau>
+//! ppot [<read options>] verify-dir <dir>
+//! ```
+
+use ppot_rs::audit::json_string;
+use ppot_rs::checksum::{from_hex, to_hex};
+use ppot_rs::contributions::{key_statuses, read_contributions, Contribution};
+use ppot_rs::prepare::prepare_phase2;
+use ppot_rs::ptau::ReadOptions;
+use ppot_rs::throttle::Throttle;
+use ppot_rs::truncate::truncate;
+use ppot_rs::verify::{verify_dir, FileOutcome};
+use std::process::ExitCode;
+
+const USAGE: &str = "usage: ppot [<read options>] verify-contributions [--json] \
+                     [--first-challenge <hex>] <file.ptau>
+       ppot [<read options>] truncate <input.ptau> <output.ptau> <power>
+       ppot [<read options>] prepare-phase2 <input.ptau> <output.ptau>
+       ppot [<read options>] verify-dir <dir>
+
+read options, which throttle the reads of every command:
+  --bytes-per-second <n>  read at most n bytes per second
+  --reads-per-second <n>  make at most n read calls per second
+
+verify-contributions lists the contributions of a ptau file with their
+response hashes, and checks the proof of knowledge of each contribution
+key. The first key is only checked if the challenge it was made to is
+given with --first-challenge, as computing it from the ceremony power can
+take minutes.
+
+truncate writes the ptau file of the given power made of the first tau
+powers of the input, reading only those from it.
+
+prepare-phase2 writes the input with the Lagrange-basis sections snarkjs
+needs for phase 2.
+
+verify-dir checks the header of each .ptau file under the directory and
+decodes every point, in parallel, and lists the files with their outcome.
+Points are checked to be on the curve, but not to be powers of the same
+tau. .zkey files are listed as unsupported without failing the command, and
+links to directories are not followed. The exit code is that of the first
+invalid file.
+
+The exit code is 1 if a contribution key is invalid, 2 for usage errors,
+and 3 to 7 for I/O, format, validation, verification and resource limit
+errors.";
+
+enum Command {
+    VerifyContributions(Args),
+    Truncate {
+        input: String,
+        output: String,
+        power: u32,
+    },
+    PreparePhase2 {
+        input: String,
+        output: String,
+    },
+    VerifyDir(String),
+}
+
+struct Args {
+    json: bool,
+    first_challenge: Option<[u8; 64]>,
+    ptau_file: String,
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let parsed =
+        parse_options(&args).and_then(|(options, rest)| Ok((options, parse_command(rest)?)));
+    let (options, command) = match parsed {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+    match command {
+        Command::VerifyContributions(args) => verify_contributions(args, &options),
+        Command::Truncate {
+            input,
+            output,
+            power,
+        } => report(&input, truncate(&input, &output, power, &options)),
+        Command::PreparePhase2 { input, output } => {
+            report(&input, prepare_phase2(&input, &output, &options))
+        }
+        Command::VerifyDir(dir) => verify_dir_command(&dir, &options),
+    }
+}
+
+fn report(input: &str, result: Result<(), ppot_rs::ptau::Error>) -> ExitCode {
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("{}: {}", input, e);
+            ExitCode::from(e.kind().exit_code())
+        }
+    }
+}
+
+fn verify_contributions(args: Args, options: &ReadOptions) -> ExitCode {
+    let contributions = match read_contributions(&args.ptau_file, options) {
+        Ok(contributions) => contributions,
+        Err(e) => {
+            eprintln!("{}: {}", args.ptau_file, e);
+            return ExitCode::from(e.kind().exit_code());
+        }
+    };
+    let statuses = key_statuses(&contributions, args.first_challenge.as_ref());
+    if args.json {
+        println!("{}", to_json(&contributions, &statuses));
+    } else {
+        print_table(&contributions, &statuses);
+    }
+    if statuses.contains(&Some(false)) {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
+
+// The read options before the command, and the arguments after them
+fn parse_options(mut args: &[String]) -> Result<(ReadOptions, &[String]), String> {
+    let mut throttle = Throttle::new();
+    while let Some((arg, tail)) = args.split_first() {
+        let set: fn(Throttle, u64) -> Throttle = match arg.as_str() {
+            "--bytes-per-second" => Throttle::bytes_per_second,
+            "--reads-per-second" => Throttle::reads_per_second,
+            _ if arg.starts_with("--") => return Err(format!("unknown option {}", arg)),
+            _ => break,
+        };
+        let (value, tail) = tail
+            .split_first()
+            .ok_or_else(|| format!("missing value for {}", arg))?;
+        let rate = value
+            .parse()
+            .map_err(|_| format!("invalid rate {} for {}", value, arg))?;
+        throttle = set(throttle, rate);
+        args = tail;
+    }
+    Ok((ReadOptions::default().throttle(throttle), args))
+}
+
+fn verify_dir_command(dir: &str, options: &ReadOptions) -> ExitCode {
+    let report = match verify_dir(dir, options) {
+        Ok(report) => report,
+        Err(e) => {
+            eprintln!("{}: {}", dir, e);
+            return ExitCode::from(e.kind().exit_code());
+        }
+    };
+    let mut exit_code = ExitCode::SUCCESS;
+    for (path, outcome) in &report.files {
+        match outcome {
+            FileOutcome::Decoded => println!("decoded      {}", path.display()),
+            FileOutcome::Invalid(e) => {
+                println!("INVALID      {}: {}", path.display(), e);
+                if exit_code == ExitCode::SUCCESS {
+                    exit_code = ExitCode::from(e.kind().exit_code());
+                }
+            }
+            FileOutcome::Unsupported => println!("unsupported  {}", path.display()),
+        }
+    }
+    exit_code
+}
+
+fn parse_command(args: &[String]) -> Result<Command, String> {
+    let (command, rest) = args.split_first().ok_or("missing command")?;
+    match command.as_str() {
+        "verify-contributions" => parse_args(rest).map(Command::VerifyContributions),
+        "truncate" => match rest {
+            [input, output, power] => Ok(Command::Truncate {
+                input: input.clone(),
+                output: output.clone(),
+                power: power
+                    .parse()
+                    .map_err(|_| format!("invalid power {}", power))?,
+            }),
+            _ => Err("expected an input file, an output file and a power".to_string()),
+        },
+        "prepare-phase2" => match rest {
+            [input, output] => Ok(Command::PreparePhase2 {
+                input: input.clone(),
+                output: output.clone(),
+            }),
+            _ => Err("expected an input file and an output file".to_string()),
+        },
+        "verify-dir" => match rest {
+            [dir] => Ok(Command::VerifyDir(dir.clone())),
+            _ => Err("expected a directory".to_string()),
+        },
+        _ => Err(format!("unknown command {}", command)),
+    }
+}
+
+fn parse_args(mut rest: &[String]) -> Result<Args, String> {
+    let mut json = false;
+    let mut first_challenge = None;
+    let mut ptau_file = None;
+    while let Some((arg, tail)) = rest.split_first() {
+        rest = tail;
+        match arg.as_str() {
+            "--json" => json = true,
+            "--first-challenge" => {
+                let (value, tail) = rest.split_first().ok_or("missing first challenge")?;
+                rest = tail;
+                first_challenge = Some(parse_hash(value)?);
+            }
+            _ if arg.starts_with("--") => return Err(format!("unknown option {}", arg)),
+            _ if ptau_file.is_none() => ptau_file = Some(arg.clone()),
+            _ => return Err(format!("unexpected argument {}", arg)),
+        }
+    }
+    Ok(Args {
+        json,
+        first_challenge,
+        ptau_file: ptau_file.ok_or("missing ptau file")?,
+    })
+}
+
+fn parse_hash(s: &str) -> Result<[u8; 64], String> {
+    from_hex(s).ok_or_else(|| format!("invalid challenge {}: expected 128 hex digits", s))
+}
+
+fn status(status: Option<bool>) -> &'static str {
+    match status {
+        Some(true) => "valid",
+        Some(false) => "INVALID",
+        None => "unchecked",
+    }
+}
+
+fn kind(contribution: &Contribution) -> &'static str {
+    if contribution.beacon.is_some() {
+        "beacon"
+    } else {
+        "contribution"
+    }
+}
+
+fn print_table(contributions: &[Contribution], statuses: &[Option<bool>]) {
+    let name_width = contributions
+        .iter()
+        .filter_map(|c| c.name.as_ref().map(|name| name.chars().count()))
+        .max()
+        .unwrap_or(0)
+        .max(4);
+    println!(
+        "{:>5}  {:<12}  {:<name_width$}  {:<9}  response hash",
+        "index", "type", "name", "key"
+    );
+    for (i, (contribution, key_status)) in contributions.iter().zip(statuses).enumerate() {
+        println!(
+            "{:>5}  {:<12}  {:<name_width$}  {:<9}  {}",
+            i,
+            kind(contribution),
+            contribution.name.as_deref().unwrap_or("-"),
+            status(*key_status),
+            to_hex(&contribution.response_hash),
+        );
+    }
+}
+
+fn to_json(contributions: &[Contribution], statuses: &[Option<bool>]) -> String {
+    let entries: Vec<String> = contributions
+        .iter()
+        .zip(statuses)
+        .enumerate()
+        .map(|(i, (contribution, key_status))| {
+            let name = match &contribution.name {
+                Some(name) => json_string(name),
+                None => "null".to_string(),
+            };
+            let beacon = match &contribution.beacon {
+                Some(beacon) => format!(
+                    "{{\"num_iterations_exp\":{},\"hash\":\"{}\"}}",
+                    beacon.num_iterations_exp,
+                    to_hex(&beacon.hash)
+                ),
+                None => "null".to_string(),
+            };
+            format!(
+                "{{\"index\":{},\"type\":\"{}\",\"name\":{},\"beacon\":{},\
+                 \"response_hash\":\"{}\",\"key\":\"{}\"}}",
+                i,
+                kind(contribution),
+                name,
+                beacon,
+                to_hex(&contribution.response_hash),
+                status(*key_status).to_lowercase(),
+            )
+        })
+        .collect();
+    format!("[{}]", entries.join(","))
+}
@@ -0,0 +1,194 @@
+//! A BLAKE2b-512 hasher that can resume from the partial state snarkjs
+//! stores in contribution records, which the blake2 crate cannot import.
+
+const IV: [u64; 8] = [
+    0x6a09e667f3bcc908,
+    0xbb67ae8584caa73b,
+    0x3c6ef372fe94f82b,
+    0xa54ff53a5f1d36f1,
+    0x510e527fade682d1,
+    0x9b05688c2b3e6c1f,
+    0x1f83d9abfb41bd6b,
+    0x5be0cd19137e2179,
+];
+
+const SIGMA: [[usize; 16]; 12] = [
+    [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15],
+    [14, 10, 4, 8, 9, 15, 13, 6, 1, 12, 0, 2, 11, 7, 5, 3],
+    [11, 8, 12, 0, 5, 2, 15, 13, 10, 14, 3, 6, 7, 1, 9, 4],
+    [7, 9, 3, 1, 13, 12, 11, 14, 2, 6, 5, 10, 4, 0, 15, 8],
+    [9, 0, 5, 7, 2, 4, 10, 15, 14, 1, 11, 12, 6, 8, 3, 13],
+    [2, 12, 6, 10, 0, 11, 8, 3, 4, 13, 7, 5, 15, 14, 1, 9],
+    [12, 5, 1, 15, 14, 13, 4, 10, 0, 7, 6, 3, 9, 2, 8, 11],
+    [13, 11, 7, 14, 12, 1, 3, 9, 5, 0, 15, 4, 8, 6, 2, 10],
+    [6, 15, 14, 9, 11, 3, 0, 8, 12, 2, 13, 7, 1, 4, 10, 5],
+    [10, 2, 8, 4, 7, 6, 1, 5, 15, 11, 9, 14, 3, 12, 13, 0],
+    [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15],
+    [14, 10, 4, 8, 9, 15, 13, 6, 1, 12, 0, 2, 11, 7, 5, 3],
+];
+
+pub(crate) struct Blake2b {
+    h: [u64; 8],
+    // The number of bytes compressed so far
+    t: u128,
+    buf: [u8; 128],
+    buf_len: usize,
+}
+
+impl Blake2b {
+    /// An unkeyed BLAKE2b-512 hasher.
+    pub(crate) fn new() -> Self {
+        let mut h = IV;
+        h[0] ^= 0x01010040;
+        Self {
+            h,
+            t: 0,
+            buf: [0u8; 128],
+            buf_len: 0,
+        }
+    }
+
+    /// Resume from a snarkjs partial hash: the pending block, the chained
+    /// state, the byte counter and the number of bytes in the block, as
+    /// laid out by blake2b-wasm. Returns `None` if the block length is out
+    /// of range.
+    pub(crate) fn from_partial(state: &[u8; 216]) -> Option<Self> {
+        let word =
+            |offset: usize| u64::from_le_bytes(state[offset..offset + 8].try_into().unwrap());
+        let buf_len = word(200);
+        if buf_len > 128 {
+            return None;
+        }
+        let mut h = [0u64; 8];
+        for (i, h) in h.iter_mut().enumerate() {
+            *h = word(128 + 8 * i);
+        }
+        Some(Self {
+            h,
+            t: word(192) as u128,
+            buf: state[..128].try_into().unwrap(),
+            buf_len: buf_len as usize,
+        })
+    }
+
+    /// The partial hash snarkjs stores, in the layout read by
+    /// `from_partial`.
+    pub(crate) fn to_partial(&self) -> [u8; 216] {
+        let mut state = [0u8; 216];
+        state[..128].copy_from_slice(&self.buf);
+        for (i, h) in self.h.iter().enumerate() {
+            state[128 + 8 * i..136 + 8 * i].copy_from_slice(&h.to_le_bytes());
+        }
+        state[192..200].copy_from_slice(&(self.t as u64).to_le_bytes());
+        state[200..208].copy_from_slice(&(self.buf_len as u64).to_le_bytes());
+        state
+    }
+
+    pub(crate) fn update(&mut self, mut data: &[u8]) {
+        while !data.is_empty() {
+            // The last block is only compressed once it is known to be the
+            // last, so flush a full buffer only when more data arrives
+            if self.buf_len == 128 {
+                self.t += 128;
+                let block = self.buf;
+                self.compress(&block, false);
+                self.buf_len = 0;
+            }
+            let n = data.len().min(128 - self.buf_len);
+            self.buf[self.buf_len..self.buf_len + n].copy_from_slice(&data[..n]);
+            self.buf_len += n;
+            data = &data[n..];
+        }
+    }
+
+    pub(crate) fn finalize(mut self) -> [u8; 64] {
+        self.t += self.buf_len as u128;
+        self.buf[self.buf_len..].fill(0);
+        let block = self.buf;
+        self.compress(&block, true);
+        let mut out = [0u8; 64];
+        for (chunk, h) in out.chunks_exact_mut(8).zip(self.h) {
+            chunk.copy_from_slice(&h.to_le_bytes());
+        }
+        out
+    }
+
+    fn compress(&mut self, block: &[u8; 128], last: bool) {
+        let mut m = [0u64; 16];
+        for (i, m) in m.iter_mut().enumerate() {
+            *m = u64::from_le_bytes(block[8 * i..8 * i + 8].try_into().unwrap());
+        }
+        let mut v = [0u64; 16];
+        v[..8].copy_from_slice(&self.h);
+        v[8..].copy_from_slice(&IV);
+        v[12] ^= self.t as u64;
+        v[13] ^= (self.t >> 64) as u64;
+        if last {
+            v[14] = !v[14];
+        }
+        for s in SIGMA {
+            g(&mut v, 0, 4, 8, 12, m[s[0]], m[s[1]]);
+            g(&mut v, 1, 5, 9, 13, m[s[2]], m[s[3]]);
+            g(&mut v, 2, 6, 10, 14, m[s[4]], m[s[5]]);
+            g(&mut v, 3, 7, 11, 15, m[s[6]], m[s[7]]);
+            g(&mut v, 0, 5, 10, 15, m[s[8]], m[s[9]]);
+            g(&mut v, 1, 6, 11, 12, m[s[10]], m[s[11]]);
+            g(&mut v, 2, 7, 8, 13, m[s[12]], m[s[13]]);
+            g(&mut v, 3, 4, 9, 14, m[s[14]], m[s[15]]);
+        }
+        for i in 0..8 {
+            self.h[i] ^= v[i] ^ v[i + 8];
+        }
+    }
+}
+
+fn g(v: &mut [u64; 16], a: usize, b: usize, c: usize, d: usize, x: u64, y: u64) {
+    v[a] = v[a].wrapping_add(v[b]).wrapping_add(x);
+    v[d] = (v[d] ^ v[a]).rotate_right(32);
+    v[c] = v[c].wrapping_add(v[d]);
+    v[b] = (v[b] ^ v[c]).rotate_right(24);
+    v[a] = v[a].wrapping_add(v[b]).wrapping_add(y);
+    v[d] = (v[d] ^ v[a]).rotate_right(16);
+    v[c] = v[c].wrapping_add(v[d]);
+    v[b] = (v[b] ^ v[c]).rotate_right(63);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Blake2b;
+    use blake2::{Blake2b512, Digest};
+
+    // The partial state of an unkeyed BLAKE2b-512 hash after `data`
+    fn partial(data: &[u8]) -> [u8; 216] {
+        let mut hasher = Blake2b::new();
+        hasher.update(data);
+        hasher.to_partial()
+    }
+
+    #[test]
+    pub fn test_resume() {
+        let data: Vec<u8> = (0..1000u32).map(|i| (i * 7) as u8).collect();
+        for (split, len) in [
+            (0, 0),
+            (0, 1),
+            (96, 200),
+            (128, 128),
+            (128, 129),
+            (300, 1000),
+        ] {
+            let mut hasher = Blake2b::from_partial(&partial(&data[..split])).unwrap();
+            hasher.update(&data[split..len]);
+            assert_eq!(
+                hasher.finalize(),
+                <[u8; 64]>::from(Blake2b512::digest(&data[..len])),
+                "split {} of {} bytes",
+                split,
+                len
+            );
+        }
+
+        let mut state = partial(&data[..10]);
+        state[200] = 129;
+        assert!(Blake2b::from_partial(&state).is_none());
+    }
+}
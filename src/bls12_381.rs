@@ -0,0 +1,213 @@
+//! Reading ptau files over BLS12-381, as produced by snarkjs for that
+//! curve. Base field elements take 48 bytes, so G1 points take 96 bytes and
+//! G2 points 192. Requires the `bls12-381` feature.
+
+use crate::ptau::{read_curve, read_curve_header, Error, PtauHeader, ReadOptions};
+use ark_bls12_381::{Bls12_381, G1Affine, G2Affine};
+
+/// Like `ptau::read`, for a file over BLS12-381.
+pub fn read(
+    ptau_file: &str,
+    num_g1_points: usize,
+    num_g2_points: usize,
+) -> Result<(Vec<G1Affine>, Vec<G2Affine>), Error> {
+    read_with_options(
+        ptau_file,
+        num_g1_points,
+        num_g2_points,
+        &ReadOptions::default(),
+    )
+}
+
+/// Like `ptau::read_with_options`, for a file over BLS12-381. Files whose
+/// header records another curve are rejected with
+/// `Error::InvalidPrimeOrder`.
+pub fn read_with_options(
+    ptau_file: &str,
+    num_g1_points: usize,
+    num_g2_points: usize,
+    options: &ReadOptions,
+) -> Result<(Vec<G1Affine>, Vec<G2Affine>), Error> {
+    read_curve::<Bls12_381>(ptau_file, num_g1_points, num_g2_points, options)
+}
+
+/// Like `ptau::read_header`, for a file over BLS12-381.
+pub fn read_header(ptau_file: &str) -> Result<PtauHeader, Error> {
+    read_header_with_options(ptau_file, &ReadOptions::default())
+}
+
+pub fn read_header_with_options(
+    ptau_file: &str,
+    options: &ReadOptions,
+) -> Result<PtauHeader, Error> {
+    read_curve_header::<Bls12_381>(ptau_file, options)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{read, read_header, read_with_options};
+    use crate::curve::{detect_curve, CurveId};
+    use crate::export::export_raw;
+    use crate::index::PtauIndex;
+    use crate::ptau::{self, Error, ReadOptions};
+    use ark_bls12_381::{Fq, Fr, G1Affine, G2Affine};
+    use ark_ec::{AffineCurve, ProjectiveCurve};
+    use ark_ff::{FpParameters, One, PrimeField};
+
+    fn write_fq(out: &mut Vec<u8>, x: &Fq) {
+        for limb in (x.0).0.iter() {
+            out.extend_from_slice(&limb.to_le_bytes());
+        }
+    }
+
+    // A ptau file of the given power with sections 1 to 3 only, holding the
+    // powers of `tau`
+    fn generate(tau: Fr, power: u32) -> (Vec<u8>, Vec<G1Affine>, Vec<G2Affine>) {
+        let num_g2_points = 1usize << power;
+        let num_g1_points = num_g2_points * 2 - 1;
+        let mut g1_points = Vec::new();
+        let mut g2_points = Vec::new();
+        let mut cur = Fr::one();
+        for i in 0..num_g1_points {
+            g1_points.push(G1Affine::prime_subgroup_generator().mul(cur).into_affine());
+            if i < num_g2_points {
+                g2_points.push(G2Affine::prime_subgroup_generator().mul(cur).into_affine());
+            }
+            cur *= tau;
+        }
+
+        let mut header = 48u32.to_le_bytes().to_vec();
+        for limb in <Fq as PrimeField>::Params::MODULUS.0.iter() {
+            header.extend_from_slice(&limb.to_le_bytes());
+        }
+        header.extend_from_slice(&power.to_le_bytes());
+        header.extend_from_slice(&power.to_le_bytes());
+        let mut g1_section = Vec::new();
+        for p in g1_points.iter() {
+            write_fq(&mut g1_section, &p.x);
+            write_fq(&mut g1_section, &p.y);
+        }
+        let mut g2_section = Vec::new();
+        for p in g2_points.iter() {
+            for x in [p.x.c0, p.x.c1, p.y.c0, p.y.c1] {
+                write_fq(&mut g2_section, &x);
+            }
+        }
+
+        let mut data = b"ptau".to_vec();
+        data.extend_from_slice(&1u32.to_le_bytes());
+        data.extend_from_slice(&3u32.to_le_bytes());
+        for (id, contents) in [(1u32, header), (2, g1_section), (3, g2_section)] {
+            data.extend_from_slice(&id.to_le_bytes());
+            data.extend_from_slice(&(contents.len() as u64).to_le_bytes());
+            data.extend_from_slice(&contents);
+        }
+        (data, g1_points, g2_points)
+    }
+
+    fn write_temp(name: &str, data: &[u8]) -> String {
+        let path = std::env::temp_dir().join(format!("ppot-rs-{}.ptau", name));
+        std::fs::write(&path, data).unwrap();
+        path.to_str().unwrap().to_string()
+    }
+
+    #[test]
+    pub fn test_read_bls12_381() {
+        let (data, g1_points, g2_points) = generate(Fr::from(1234567u64), 3);
+        let path = write_temp("bls12-381", &data);
+
+        assert_eq!(detect_curve(&path).unwrap(), CurveId::Bls12_381);
+        let header = read_header(&path).unwrap();
+        assert_eq!(header.n8, 48);
+        assert_eq!(header.power, 3);
+        let (g1, g2) = read(&path, 15, 8).unwrap();
+        assert_eq!(g1, g1_points);
+        assert_eq!(g2, g2_points);
+        assert!(matches!(read(&path, 16, 8), Err(Error::InvalidNumG1Points)));
+
+        // The BN254 reader rejects the file, and the BLS12-381 reader BN254
+        // files
+        assert!(matches!(
+            ptau::read(&path, 1, 1),
+            Err(Error::InvalidPrimeOrder)
+        ));
+        assert!(matches!(
+            read("8.ptau", 1, 1),
+            Err(Error::InvalidPrimeOrder)
+        ));
+
+        // Corrupt the second G1 point, which starts after the file and
+        // section headers, the header section and the first point
+        let point = 12 + 12 + 60 + 12 + 96;
+        let mut corrupted = data.clone();
+        corrupted[point + 48] ^= 1;
+        let corrupted_path = write_temp("bls12-381-corrupted", &corrupted);
+        assert!(matches!(
+            read(&corrupted_path, 2, 1),
+            Err(Error::InvalidG1Point)
+        ));
+        // A coordinate set to the modulus is not canonical
+        for (i, limb) in <Fq as PrimeField>::Params::MODULUS.0.iter().enumerate() {
+            corrupted[point + 8 * i..point + 8 * i + 8].copy_from_slice(&limb.to_le_bytes());
+        }
+        std::fs::write(&corrupted_path, &corrupted).unwrap();
+        assert!(matches!(
+            read(&corrupted_path, 2, 1),
+            Err(Error::NonCanonicalEncoding)
+        ));
+        let options = ReadOptions::default().lenient(true).check_on_curve(false);
+        assert!(read_with_options(&corrupted_path, 2, 1, &options).is_ok());
+
+        std::fs::remove_file(path).unwrap();
+        std::fs::remove_file(corrupted_path).unwrap();
+    }
+
+    #[test]
+    pub fn test_point_sizes_bls12_381() {
+        // Points take 96 bytes in G1 and 192 in G2, from byte 96 on
+        let (data, _, _) = generate(Fr::from(7u64), 3);
+        let path = write_temp("bls12-381-sizes", &data);
+
+        let mut out = Vec::new();
+        let descriptor = export_raw(&path, 2, 15, &mut out).unwrap();
+        assert_eq!(out, data[96..96 + 15 * 96]);
+        assert_eq!(
+            (descriptor.point_size, descriptor.field_element_size),
+            (96, 48)
+        );
+        assert!(descriptor
+            .to_json()
+            .starts_with("{\"curve\":\"bls12-381\",\"section\":2,\"num_points\":15,\"point_size\":96,\"field_element_size\":48,"));
+        let g2_start = 96 + 15 * 96 + 12;
+        let mut out = Vec::new();
+        export_raw(&path, 3, 8, &mut out).unwrap();
+        assert_eq!(out, data[g2_start..g2_start + 8 * 192]);
+        assert_eq!(
+            export_raw(&path, 3, 9, &mut Vec::new()),
+            Err(Error::InvalidNumG2Points)
+        );
+
+        let index = PtauIndex::generate(&path, 4, &ReadOptions::default()).unwrap();
+        assert_eq!(index.section_blocks(2).unwrap().blake2b.len(), 4);
+        assert_eq!(index.block_range(2, 3), Some((96 + 12 * 96, 3 * 96)));
+        assert_eq!(
+            index.block_range(3, 1),
+            Some((g2_start as u64 + 4 * 192, 4 * 192))
+        );
+
+        // The point a truncated section breaks off at
+        let truncated = write_temp("bls12-381-truncated", &data[..96 + 5 * 96 + 10]);
+        assert_eq!(
+            read_header(&truncated),
+            Err(Error::UnexpectedEof {
+                section: 2,
+                point_index: Some(5),
+                expected_bytes: 15 * 96,
+                got_bytes: 5 * 96 + 10,
+            })
+        );
+
+        std::fs::remove_file(path).unwrap();
+        std::fs::remove_file(truncated).unwrap();
+    }
+}
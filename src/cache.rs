@@ -0,0 +1,92 @@
+//! An opt-in, process-wide cache of SRS reads, for services that construct
+//! many proving contexts from the same ptau file.
+
+use crate::ptau::{io_error, read_with_options, Error, ReadOptions};
+use ark_bn254::{G1Affine, G2Affine};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::SystemTime;
+
+/// G1 and G2 points shared between all readers of the same cache entry.
+pub type SharedSrs = (Arc<[G1Affine]>, Arc<[G2Affine]>);
+
+#[derive(PartialEq, Eq, Hash)]
+struct Key {
+    path: PathBuf,
+    // The file length and modification time, so that a file replaced on
+    // disk is read again
+    len: u64,
+    modified: Option<SystemTime>,
+    num_g1_points: usize,
+    num_g2_points: usize,
+    options: ReadOptions,
+}
+
+fn cache() -> &'static Mutex<HashMap<Key, SharedSrs>> {
+    static CACHE: OnceLock<Mutex<HashMap<Key, SharedSrs>>> = OnceLock::new();
+    CACHE.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Like `ptau::read_with_options`, but returns the points of an earlier
+/// call with the same file, point counts and options instead of reading
+/// and validating them again. Errors are not cached.
+pub fn read_cached(
+    ptau_file: &str,
+    num_g1_points: usize,
+    num_g2_points: usize,
+    options: &ReadOptions,
+) -> Result<SharedSrs, Error> {
+    let path = std::fs::canonicalize(ptau_file).map_err(io_error)?;
+    let metadata = std::fs::metadata(&path).map_err(io_error)?;
+    let key = Key {
+        path,
+        len: metadata.len(),
+        modified: metadata.modified().ok(),
+        num_g1_points,
+        num_g2_points,
+        options: options.clone(),
+    };
+    if let Some(srs) = cache().lock().unwrap().get(&key) {
+        return Ok(srs.clone());
+    }
+
+    // Read without holding the lock, so that reads of other files are not
+    // blocked
+    let (g1_points, g2_points) =
+        read_with_options(ptau_file, num_g1_points, num_g2_points, options)?;
+    let srs: SharedSrs = (g1_points.into(), g2_points.into());
+    Ok(cache().lock().unwrap().entry(key).or_insert(srs).clone())
+}
+
+/// Drop all cached points. Points still held by callers stay alive until
+/// they are dropped.
+pub fn clear() {
+    cache().lock().unwrap().clear();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::read_cached;
+    use crate::ptau::{read, ReadOptions};
+    use std::sync::Arc;
+
+    #[test]
+    pub fn test_read_cached() {
+        let options = ReadOptions::default();
+        let (g1_a, g2_a) = read_cached("8.ptau", 511, 256, &options).unwrap();
+        let (g1_b, g2_b) = read_cached("./8.ptau", 511, 256, &options).unwrap();
+        assert!(Arc::ptr_eq(&g1_a, &g1_b));
+        assert!(Arc::ptr_eq(&g2_a, &g2_b));
+
+        let (g1_points, g2_points) = read("8.ptau", 511, 256).unwrap();
+        assert_eq!(*g1_a, g1_points[..]);
+        assert_eq!(*g2_a, g2_points[..]);
+
+        // Different point counts or options are separate entries
+        let (g1_c, _) = read_cached("8.ptau", 10, 256, &options).unwrap();
+        assert_eq!(*g1_c, g1_points[..10]);
+        let (g1_d, _) = read_cached("8.ptau", 511, 256, &options.clone().lenient(true)).unwrap();
+        assert!(!Arc::ptr_eq(&g1_a, &g1_d));
+    }
+}
@@ -0,0 +1,97 @@
+//! The ChaCha20 stream cipher as a deterministic random number generator.
+
+use ark_std::rand::RngCore;
+
+// The ChaCha20 generator, with a zero nonce, used by snarkjs and the
+// original Rust ceremony code to derive points from hashes
+pub(crate) struct ChaCha {
+    state: [u32; 16],
+    buf: [u32; 16],
+    index: usize,
+}
+
+impl ChaCha {
+    pub(crate) fn new(seed: [u32; 8]) -> Self {
+        let mut state = [0u32; 16];
+        state[..4].copy_from_slice(&[0x61707865, 0x3320646e, 0x79622d32, 0x6b206574]);
+        state[4..12].copy_from_slice(&seed);
+        Self {
+            state,
+            buf: [0; 16],
+            index: 16,
+        }
+    }
+
+    pub(crate) fn next_u32(&mut self) -> u32 {
+        if self.index == 16 {
+            self.refill();
+        }
+        self.index += 1;
+        self.buf[self.index - 1]
+    }
+
+    pub(crate) fn next_u64(&mut self) -> u64 {
+        let hi = self.next_u32() as u64;
+        (hi << 32) | self.next_u32() as u64
+    }
+
+    fn refill(&mut self) {
+        let mut x = self.state;
+        for _ in 0..10 {
+            quarter_round(&mut x, 0, 4, 8, 12);
+            quarter_round(&mut x, 1, 5, 9, 13);
+            quarter_round(&mut x, 2, 6, 10, 14);
+            quarter_round(&mut x, 3, 7, 11, 15);
+            quarter_round(&mut x, 0, 5, 10, 15);
+            quarter_round(&mut x, 1, 6, 11, 12);
+            quarter_round(&mut x, 2, 7, 8, 13);
+            quarter_round(&mut x, 3, 4, 9, 14);
+        }
+        for (b, (x, s)) in self.buf.iter_mut().zip(x.iter().zip(self.state.iter())) {
+            *b = x.wrapping_add(*s);
+        }
+        self.index = 0;
+        // Increment the 128-bit block counter
+        for word in &mut self.state[12..] {
+            *word = word.wrapping_add(1);
+            if *word != 0 {
+                break;
+            }
+        }
+    }
+}
+
+fn quarter_round(x: &mut [u32; 16], a: usize, b: usize, c: usize, d: usize) {
+    x[a] = x[a].wrapping_add(x[b]);
+    x[d] = (x[d] ^ x[a]).rotate_left(16);
+    x[c] = x[c].wrapping_add(x[d]);
+    x[b] = (x[b] ^ x[c]).rotate_left(12);
+    x[a] = x[a].wrapping_add(x[b]);
+    x[d] = (x[d] ^ x[a]).rotate_left(8);
+    x[c] = x[c].wrapping_add(x[d]);
+    x[b] = (x[b] ^ x[c]).rotate_left(7);
+}
+
+// Bytes are taken from the little-endian words, for reproducible
+// challenges derived from a recorded seed
+impl RngCore for ChaCha {
+    fn next_u32(&mut self) -> u32 {
+        ChaCha::next_u32(self)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        ChaCha::next_u64(self)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        for chunk in dest.chunks_mut(4) {
+            let word = ChaCha::next_u32(self).to_le_bytes();
+            chunk.copy_from_slice(&word[..chunk.len()]);
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), ark_std::rand::Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
@@ -0,0 +1,545 @@
+//! Per-section integrity digests, computed while the points are read.
+
+use crate::audit;
+use crate::ptau::{
+    io_error, open, read_bytes, read_header_from, read_points_from, Error, ReadOptions, Section,
+};
+use ark_bn254::{G1Affine, G2Affine};
+use blake2::{Blake2b512, Digest};
+use std::fmt;
+use std::fs::File;
+use std::io::{BufReader, Read, Seek, SeekFrom};
+
+/// The BLAKE2b-512 digest of the bytes of a section that were read, which
+/// are the first `hashed_bytes` bytes of the section.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SectionDigest {
+    pub section: u32,
+    pub hashed_bytes: u64,
+    /// Whether the whole section was hashed.
+    pub complete: bool,
+    pub blake2b: [u8; 64],
+}
+
+/// Points read by `read_with_digests`, with the digests of the sections
+/// they were read from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DigestedSrs {
+    pub g1_points: Vec<G1Affine>,
+    pub g2_points: Vec<G2Affine>,
+    /// Digests of the header (section 1) and of the G1 and G2 sections.
+    pub digests: Vec<SectionDigest>,
+}
+
+/// Like `ptau::read_with_options`, also hashing the sections as they are
+/// read. Sections 2 and 3 are only hashed as far as the requested points.
+pub fn read_with_digests(
+    ptau_file: &str,
+    num_g1_points: usize,
+    num_g2_points: usize,
+    options: &ReadOptions,
+) -> Result<DigestedSrs, Error> {
+    audit::record(
+        "read_with_digests",
+        ptau_file,
+        &[
+            ("num_g1_points", num_g1_points as u64),
+            ("num_g2_points", num_g2_points as u64),
+        ],
+        || {
+            let mut f = BufReader::new(open(ptau_file, options)?);
+            let header = read_header_from(&mut f, options)?;
+            let mut reader = DigestReader::new(f, &header.sections);
+
+            // The header section was read before the reader knew where the
+            // sections are, so read it again
+            let header_section = header.section(1).ok_or(Error::MissingSection(1))?;
+            reader
+                .seek(SeekFrom::Start(header_section.position))
+                .map_err(io_error)?;
+            let mut buf = vec![0u8; header_section.size as usize];
+            read_bytes(&mut reader, &mut buf, 1)?;
+
+            let srs = read_points_from(
+                &mut reader,
+                &header,
+                num_g1_points,
+                num_g2_points,
+                options,
+                false,
+            )?;
+            Ok(DigestedSrs {
+                g1_points: srs.g1_points,
+                g2_points: srs.g2_points,
+                digests: reader.finish(),
+            })
+        },
+    )
+}
+
+/// Read points without checking that they are on the curve or in the
+/// subgroup, provided the whole file hashes to `blake2b`, a BLAKE2b-512
+/// digest pinned by the caller (such as the `file_blake2b` of a trusted
+/// `Manifest`). Fails with `ChecksumMismatch` otherwise, without returning
+/// any points.
+///
+/// The digest is computed over the same bytes the points are decoded
+/// from, in a single pass over the file. The checks of `options` are
+/// overridden; its limits still apply.
+pub fn read_pinned(
+    ptau_file: &str,
+    num_g1_points: usize,
+    num_g2_points: usize,
+    blake2b: &[u8; 64],
+    options: &ReadOptions,
+) -> Result<(Vec<G1Affine>, Vec<G2Affine>), Error> {
+    audit::record(
+        "read_pinned",
+        ptau_file,
+        &[
+            ("num_g1_points", num_g1_points as u64),
+            ("num_g2_points", num_g2_points as u64),
+        ],
+        || {
+            let options = options
+                .clone()
+                .check_on_curve(false)
+                .check_subgroup(false)
+                .check_g1_subgroup(false);
+            let mut f = open(ptau_file, &options)?;
+            let header = read_header_from(&mut f, &options)?;
+            f.seek(SeekFrom::Start(0)).map_err(io_error)?;
+
+            let mut reader = FileHasher::new(BufReader::new(f));
+            let srs = read_points_from(
+                &mut reader,
+                &header,
+                num_g1_points,
+                num_g2_points,
+                &options,
+                false,
+            )?;
+            let (_, digest) = reader.finish().map_err(io_error)?;
+            if digest != *blake2b {
+                return Err(Error::ChecksumMismatch { section: None });
+            }
+            Ok((srs.g1_points, srs.g2_points))
+        },
+    )
+}
+
+// Hashes a whole file as it is read. Seeking forward reads and hashes the
+// bytes skipped over, and seeking backward is not supported.
+pub(crate) struct FileHasher<R> {
+    inner: R,
+    position: u64,
+    hasher: Blake2b512,
+}
+
+impl<R: Read> FileHasher<R> {
+    pub(crate) fn new(inner: R) -> Self {
+        Self {
+            inner,
+            position: 0,
+            hasher: Blake2b512::new(),
+        }
+    }
+
+    // Read and hash the rest of the file, returning its size and
+    // BLAKE2b-512 hash
+    pub(crate) fn finish(mut self) -> std::io::Result<(u64, [u8; 64])> {
+        std::io::copy(&mut self, &mut std::io::sink())?;
+        Ok((self.position, self.hasher.finalize().into()))
+    }
+}
+
+impl<R: Read> Read for FileHasher<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.hasher.update(&buf[..n]);
+        self.position += n as u64;
+        Ok(n)
+    }
+}
+
+impl<R: Read> Seek for FileHasher<R> {
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        let target = match pos {
+            SeekFrom::Start(target) => Some(target),
+            SeekFrom::Current(offset) => self.position.checked_add_signed(offset),
+            SeekFrom::End(_) => None,
+        };
+        let skip = target
+            .and_then(|target| target.checked_sub(self.position))
+            .ok_or_else(|| {
+                std::io::Error::new(
+                    std::io::ErrorKind::Unsupported,
+                    "the file can only be read in order when pinned",
+                )
+            })?;
+        let skipped = std::io::copy(&mut self.by_ref().take(skip), &mut std::io::sink())?;
+        if skipped < skip {
+            return Err(std::io::ErrorKind::UnexpectedEof.into());
+        }
+        Ok(self.position)
+    }
+}
+
+/// The sizes and BLAKE2b-512 digests of a ptau file and of each of its
+/// sections, stored next to the file as `<file>.sums` to check its
+/// integrity before use.
+///
+/// The text format has a version line, a line for the whole file, then one
+/// line per section:
+///
+/// ```text
+/// ppot-rs-sums 1
+/// file <size> <blake2b hex>
+/// section <id> <size> <blake2b hex>
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Manifest {
+    pub file_size: u64,
+    pub file_blake2b: [u8; 64],
+    pub sections: Vec<SectionDigest>,
+}
+
+const MANIFEST_VERSION_LINE: &str = "ppot-rs-sums 1";
+
+impl Manifest {
+    /// Hash a ptau file and all of its sections, in one pass.
+    pub fn generate(ptau_file: &str) -> Result<Self, Error> {
+        let options = ReadOptions::new().max_power(u32::MAX).lenient(true);
+        let mut f = File::open(ptau_file).map_err(io_error)?;
+        let header = read_header_from(&mut f, &options)?;
+        f.seek(SeekFrom::Start(0)).map_err(io_error)?;
+
+        let mut reader = DigestReader::new(BufReader::new(f), &header.sections);
+        let mut file_hasher = Blake2b512::new();
+        let mut file_size = 0;
+        let mut buf = vec![0u8; 1 << 16];
+        loop {
+            let n = reader.read(&mut buf).map_err(io_error)?;
+            if n == 0 {
+                break;
+            }
+            file_hasher.update(&buf[..n]);
+            file_size += n as u64;
+        }
+        Ok(Self {
+            file_size,
+            file_blake2b: file_hasher.finalize().into(),
+            sections: reader.finish(),
+        })
+    }
+
+    /// Check that a ptau file matches this manifest. A file of the wrong
+    /// size is rejected without being read.
+    pub fn verify(&self, ptau_file: &str) -> Result<(), Error> {
+        let file_size = std::fs::metadata(ptau_file).map_err(io_error)?.len();
+        if file_size != self.file_size {
+            return Err(Error::ChecksumMismatch { section: None });
+        }
+        let actual = Self::generate(ptau_file)?;
+        for expected in &self.sections {
+            match actual
+                .sections
+                .iter()
+                .find(|s| s.section == expected.section)
+            {
+                Some(s) if s == expected => {}
+                _ => {
+                    return Err(Error::ChecksumMismatch {
+                        section: Some(expected.section),
+                    })
+                }
+            }
+        }
+        if actual.file_blake2b != self.file_blake2b {
+            return Err(Error::ChecksumMismatch { section: None });
+        }
+        Ok(())
+    }
+
+    pub fn parse(s: &str) -> Result<Self, Error> {
+        let mut lines = s.lines();
+        if lines.next() != Some(MANIFEST_VERSION_LINE) {
+            return Err(Error::InvalidManifest);
+        }
+        let file_line: Vec<&str> = lines
+            .next()
+            .ok_or(Error::InvalidManifest)?
+            .split(' ')
+            .collect();
+        let (file_size, file_blake2b) = match file_line[..] {
+            ["file", size, hash] => (
+                parse_u64(size)?,
+                from_hex(hash).ok_or(Error::InvalidManifest)?,
+            ),
+            _ => return Err(Error::InvalidManifest),
+        };
+        let mut sections = Vec::new();
+        for line in lines {
+            let fields: Vec<&str> = line.split(' ').collect();
+            match fields[..] {
+                ["section", id, size, hash] => {
+                    let size = parse_u64(size)?;
+                    sections.push(SectionDigest {
+                        section: id.parse().map_err(|_| Error::InvalidManifest)?,
+                        hashed_bytes: size,
+                        complete: true,
+                        blake2b: from_hex(hash).ok_or(Error::InvalidManifest)?,
+                    })
+                }
+                _ => return Err(Error::InvalidManifest),
+            }
+        }
+        Ok(Self {
+            file_size,
+            file_blake2b,
+            sections,
+        })
+    }
+}
+
+impl fmt::Display for Manifest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}", MANIFEST_VERSION_LINE)?;
+        writeln!(f, "file {} {}", self.file_size, to_hex(&self.file_blake2b))?;
+        for s in &self.sections {
+            writeln!(
+                f,
+                "section {} {} {}",
+                s.section,
+                s.hashed_bytes,
+                to_hex(&s.blake2b)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// The path of the manifest of a ptau file.
+pub fn manifest_path(ptau_file: &str) -> String {
+    format!("{}.sums", ptau_file)
+}
+
+/// Generate the manifest of a ptau file and write it next to the file.
+pub fn write_manifest(ptau_file: &str) -> Result<Manifest, Error> {
+    let manifest = Manifest::generate(ptau_file)?;
+    std::fs::write(manifest_path(ptau_file), manifest.to_string()).map_err(io_error)?;
+    Ok(manifest)
+}
+
+/// Check a ptau file against the manifest stored next to it.
+pub fn verify_manifest(ptau_file: &str) -> Result<(), Error> {
+    let manifest = std::fs::read_to_string(manifest_path(ptau_file)).map_err(io_error)?;
+    audit::record("verify_manifest", ptau_file, &[], || {
+        Manifest::parse(&manifest)?.verify(ptau_file)
+    })
+}
+
+/// `bytes` as lowercase hex, as hashes are written in manifests.
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Parse a hash written by `to_hex`.
+pub fn from_hex(s: &str) -> Option<[u8; 64]> {
+    let mut out = [0u8; 64];
+    if s.len() != 128 || !s.is_ascii() {
+        return None;
+    }
+    for (i, byte) in out.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&s[2 * i..2 * i + 2], 16).ok()?;
+    }
+    Some(out)
+}
+
+fn parse_u64(s: &str) -> Result<u64, Error> {
+    s.parse().map_err(|_| Error::InvalidManifest)
+}
+
+struct SectionHasher {
+    section: Section,
+    hasher: Blake2b512,
+    hashed_bytes: u64,
+}
+
+/// A reader that hashes the bytes read from each section. Only bytes that
+/// extend the hashed prefix of a section are hashed: re-reads are ignored,
+/// and a section stops being hashed at the first gap.
+pub struct DigestReader<R> {
+    inner: R,
+    position: u64,
+    sections: Vec<SectionHasher>,
+}
+
+impl<R> DigestReader<R> {
+    pub fn new(inner: R, sections: &[Section]) -> Self {
+        Self {
+            inner,
+            position: 0,
+            sections: sections
+                .iter()
+                .map(|section| SectionHasher {
+                    section: section.clone(),
+                    hasher: Blake2b512::new(),
+                    hashed_bytes: 0,
+                })
+                .collect(),
+        }
+    }
+
+    /// The digests of the sections that were read from.
+    pub fn finish(self) -> Vec<SectionDigest> {
+        self.sections
+            .into_iter()
+            .filter(|s| s.hashed_bytes > 0)
+            .map(|s| SectionDigest {
+                section: s.section.id,
+                hashed_bytes: s.hashed_bytes,
+                complete: s.hashed_bytes == s.section.size,
+                blake2b: s.hasher.finalize().into(),
+            })
+            .collect()
+    }
+}
+
+impl<R: Read> Read for DigestReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        let (start, end) = (self.position, self.position + n as u64);
+        for s in self.sections.iter_mut() {
+            let section_start = s.section.position;
+            let section_end = section_start + s.section.size;
+            // The next byte of the section to hash, if it was just read
+            let next = section_start + s.hashed_bytes;
+            if next < start || next >= end.min(section_end) {
+                continue;
+            }
+            let to = end.min(section_end);
+            s.hasher
+                .update(&buf[(next - start) as usize..(to - start) as usize]);
+            s.hashed_bytes += to - next;
+        }
+        self.position = end;
+        Ok(n)
+    }
+}
+
+impl<R: Seek> Seek for DigestReader<R> {
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        self.position = self.inner.seek(pos)?;
+        Ok(self.position)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{read_pinned, read_with_digests, verify_manifest, write_manifest, Manifest};
+    use crate::ptau::{read, Error, ReadOptions};
+    use blake2::{Blake2b512, Digest};
+
+    fn blake2b(data: &[u8]) -> [u8; 64] {
+        Blake2b512::digest(data).into()
+    }
+
+    #[test]
+    pub fn test_read_with_digests() {
+        let data = std::fs::read("8.ptau").unwrap();
+        let srs = read_with_digests("8.ptau", 511, 10, &ReadOptions::default()).unwrap();
+        assert_eq!(
+            (srs.g1_points.clone(), srs.g2_points.clone()),
+            read("8.ptau", 511, 10).unwrap()
+        );
+
+        let digests: Vec<_> = srs
+            .digests
+            .iter()
+            .map(|d| (d.section, d.hashed_bytes, d.complete, d.blake2b))
+            .collect();
+        assert_eq!(
+            digests,
+            vec![
+                (1, 44, true, blake2b(&data[24..68])),
+                (2, 511 * 64, true, blake2b(&data[80..80 + 511 * 64])),
+                (3, 10 * 128, false, blake2b(&data[32796..32796 + 10 * 128])),
+            ]
+        );
+    }
+
+    #[test]
+    pub fn test_manifest() {
+        let data = std::fs::read("8.ptau").unwrap();
+        let ptau_file = std::env::temp_dir().join("ppot-rs-manifest.ptau");
+        let ptau_file = ptau_file.to_str().unwrap();
+        std::fs::write(ptau_file, &data).unwrap();
+
+        let manifest = write_manifest(ptau_file).unwrap();
+        assert_eq!(manifest.file_size, data.len() as u64);
+        assert_eq!(manifest.file_blake2b, blake2b(&data));
+        assert_eq!(manifest.sections.len(), 11);
+        assert!(manifest.sections.iter().all(|s| s.complete));
+        assert_eq!(manifest.sections[1].blake2b, blake2b(&data[80..32784]));
+        assert_eq!(Manifest::parse(&manifest.to_string()), Ok(manifest));
+        assert_eq!(verify_manifest(ptau_file), Ok(()));
+
+        // A changed G2 point
+        let mut tampered = data.clone();
+        tampered[32796 + 5] ^= 1;
+        std::fs::write(ptau_file, &tampered).unwrap();
+        assert_eq!(
+            verify_manifest(ptau_file),
+            Err(Error::ChecksumMismatch { section: Some(3) })
+        );
+
+        // Trailing bytes outside any section
+        let mut extended = data;
+        extended.push(0);
+        std::fs::write(ptau_file, &extended).unwrap();
+        assert_eq!(
+            verify_manifest(ptau_file),
+            Err(Error::ChecksumMismatch { section: None })
+        );
+
+        assert_eq!(
+            Manifest::parse("ppot-rs-sums 2\n"),
+            Err(Error::InvalidManifest)
+        );
+    }
+
+    #[test]
+    pub fn test_read_pinned() {
+        let data = std::fs::read("8.ptau").unwrap();
+        let options = ReadOptions::default();
+        let pinned = blake2b(&data);
+        assert_eq!(
+            read_pinned("8.ptau", 511, 256, &pinned, &options),
+            Ok(read("8.ptau", 511, 256).unwrap())
+        );
+
+        // A G1 point off the curve is only caught by the digest
+        let mut tampered = data.clone();
+        tampered[80 + 3 * 64] ^= 1;
+        let ptau_file = std::env::temp_dir().join("ppot-rs-pinned.ptau");
+        let ptau_file = ptau_file.to_str().unwrap();
+        std::fs::write(ptau_file, &tampered).unwrap();
+        assert_eq!(
+            read_pinned(ptau_file, 511, 256, &pinned, &options),
+            Err(Error::ChecksumMismatch { section: None })
+        );
+        let (g1_points, _) = read_pinned(ptau_file, 10, 1, &blake2b(&tampered), &options).unwrap();
+        assert!(!g1_points[3].is_on_curve());
+
+        // Bytes past the points that were read are hashed too
+        let mut extended = data;
+        extended.push(0);
+        std::fs::write(ptau_file, &extended).unwrap();
+        assert_eq!(
+            read_pinned(ptau_file, 1, 1, &pinned, &options),
+            Err(Error::ChecksumMismatch { section: None })
+        );
+        std::fs::remove_file(ptau_file).unwrap();
+    }
+}
@@ -0,0 +1,263 @@
+//! An in-memory SRS that keeps points in compressed form (the x coordinate
+//! plus a flag) and decompresses them on access, for workloads that only
+//! touch a few points of a large SRS.
+
+use crate::ptau::Error;
+use ark_bn254::{G1Affine, G2Affine};
+use ark_ec::models::short_weierstrass_jacobian::GroupAffine;
+use ark_ec::SWModelParameters;
+use ark_ff::Zero;
+use std::collections::VecDeque;
+use std::ops::Range;
+use std::sync::{Arc, Mutex};
+
+const DEFAULT_CHUNK_SIZE: usize = 1024;
+const DEFAULT_MAX_CHUNKS: usize = 8;
+
+// Point flags. A point is recovered from its x coordinate by picking the
+// greater or lesser of the two possible y coordinates.
+const FLAG_LESSER_Y: u8 = 0;
+const FLAG_GREATER_Y: u8 = 1;
+const FLAG_INFINITY: u8 = 2;
+
+/// G1 and G2 points stored compressed, with a small cache of recently
+/// decompressed chunks of points.
+///
+/// Points are checked to be on the curve when the SRS is built, so that
+/// they can be decompressed, but not to be in the prime order subgroup.
+pub struct CompressedSrs {
+    g1: CompressedPoints<ark_bn254::g1::Parameters>,
+    g2: CompressedPoints<ark_bn254::g2::Parameters>,
+}
+
+impl CompressedSrs {
+    /// Fails with `Error::InvalidG1Point` or `Error::InvalidG2Point` if a
+    /// point is not on the curve, as points read with `check_on_curve(false)`
+    /// may not be.
+    pub fn new(g1_points: &[G1Affine], g2_points: &[G2Affine]) -> Result<Self, Error> {
+        Self::with_cache(g1_points, g2_points, DEFAULT_CHUNK_SIZE, DEFAULT_MAX_CHUNKS)
+    }
+
+    /// Decompress points `chunk_size` at a time, keeping up to `max_chunks`
+    /// decompressed chunks per group in memory. Fails with
+    /// `Error::InvalidChunkSize` if `chunk_size` is 0, and otherwise as
+    /// `new` does.
+    pub fn with_cache(
+        g1_points: &[G1Affine],
+        g2_points: &[G2Affine],
+        chunk_size: usize,
+        max_chunks: usize,
+    ) -> Result<Self, Error> {
+        if chunk_size == 0 {
+            return Err(Error::InvalidChunkSize);
+        }
+        Ok(Self {
+            g1: CompressedPoints::new(g1_points, chunk_size, max_chunks)
+                .ok_or(Error::InvalidG1Point)?,
+            g2: CompressedPoints::new(g2_points, chunk_size, max_chunks)
+                .ok_or(Error::InvalidG2Point)?,
+        })
+    }
+
+    pub fn num_g1_points(&self) -> usize {
+        self.g1.len()
+    }
+
+    pub fn num_g2_points(&self) -> usize {
+        self.g2.len()
+    }
+
+    /// Panics if `index` is out of bounds.
+    pub fn g1(&self, index: usize) -> G1Affine {
+        self.g1.get(index)
+    }
+
+    /// Panics if `range` is out of bounds.
+    pub fn g1_range(&self, range: Range<usize>) -> Vec<G1Affine> {
+        self.g1.range(range)
+    }
+
+    /// Panics if `index` is out of bounds.
+    pub fn g2(&self, index: usize) -> G2Affine {
+        self.g2.get(index)
+    }
+
+    /// Panics if `range` is out of bounds.
+    pub fn g2_range(&self, range: Range<usize>) -> Vec<G2Affine> {
+        self.g2.range(range)
+    }
+}
+
+type Chunk<P> = Arc<Vec<GroupAffine<P>>>;
+
+struct CompressedPoints<P: SWModelParameters> {
+    xs: Vec<P::BaseField>,
+    flags: Vec<u8>,
+    chunk_size: usize,
+    max_chunks: usize,
+    // Most recently used chunks last
+    cache: Mutex<VecDeque<(usize, Chunk<P>)>>,
+}
+
+impl<P: SWModelParameters> CompressedPoints<P> {
+    // The compressed points, or `None` if one is not on the curve
+    fn new(points: &[GroupAffine<P>], chunk_size: usize, max_chunks: usize) -> Option<Self> {
+        let mut xs = Vec::with_capacity(points.len());
+        let mut flags = Vec::with_capacity(points.len());
+        for point in points {
+            if point.is_zero() {
+                xs.push(P::BaseField::zero());
+                flags.push(FLAG_INFINITY);
+            } else if !point.is_on_curve() {
+                return None;
+            } else {
+                xs.push(point.x);
+                flags.push(if point.y > -point.y {
+                    FLAG_GREATER_Y
+                } else {
+                    FLAG_LESSER_Y
+                });
+            }
+        }
+        Some(Self {
+            xs,
+            flags,
+            chunk_size,
+            max_chunks,
+            cache: Mutex::new(VecDeque::new()),
+        })
+    }
+
+    fn len(&self) -> usize {
+        self.xs.len()
+    }
+
+    fn get(&self, index: usize) -> GroupAffine<P> {
+        assert!(index < self.len(), "point index out of bounds");
+        let chunk = self.chunk(index / self.chunk_size);
+        chunk[index % self.chunk_size]
+    }
+
+    fn range(&self, range: Range<usize>) -> Vec<GroupAffine<P>> {
+        assert!(
+            range.start <= range.end && range.end <= self.len(),
+            "point range out of bounds"
+        );
+        let mut points = Vec::with_capacity(range.end - range.start);
+        let mut index = range.start;
+        while index < range.end {
+            let chunk_index = index / self.chunk_size;
+            let chunk = self.chunk(chunk_index);
+            let chunk_start = chunk_index * self.chunk_size;
+            let end = range.end.min(chunk_start + chunk.len());
+            points.extend_from_slice(&chunk[index - chunk_start..end - chunk_start]);
+            index = end;
+        }
+        points
+    }
+
+    // Fetch a decompressed chunk from the cache, or decompress it without
+    // holding the lock so that other threads can use the cache meanwhile
+    fn chunk(&self, chunk_index: usize) -> Chunk<P> {
+        if let Some(chunk) = Self::cached(&mut self.cache.lock().unwrap(), chunk_index) {
+            return chunk;
+        }
+
+        let start = chunk_index * self.chunk_size;
+        let end = self.len().min(start + self.chunk_size);
+        let chunk = Arc::new((start..end).map(|i| self.decompress(i)).collect::<Vec<_>>());
+        let mut cache = self.cache.lock().unwrap();
+        // Another thread may have decompressed the same chunk in the meantime
+        if let Some(chunk) = Self::cached(&mut cache, chunk_index) {
+            return chunk;
+        }
+        if self.max_chunks > 0 {
+            if cache.len() == self.max_chunks {
+                cache.pop_front();
+            }
+            cache.push_back((chunk_index, chunk.clone()));
+        }
+        chunk
+    }
+
+    // Look up a chunk in the cache, marking it as the most recently used
+    fn cached(cache: &mut VecDeque<(usize, Chunk<P>)>, chunk_index: usize) -> Option<Chunk<P>> {
+        let i = cache.iter().position(|(j, _)| *j == chunk_index)?;
+        let entry = cache.remove(i).unwrap();
+        let chunk = entry.1.clone();
+        cache.push_back(entry);
+        Some(chunk)
+    }
+
+    fn decompress(&self, index: usize) -> GroupAffine<P> {
+        match self.flags[index] {
+            FLAG_INFINITY => GroupAffine::zero(),
+            // Points on the curve have a y for their x
+            flag => GroupAffine::get_point_from_x(self.xs[index], flag == FLAG_GREATER_Y).unwrap(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CompressedSrs;
+    use crate::ptau::Error;
+    use ark_bn254::{G1Affine, G2Affine};
+    use ark_ec::AffineCurve;
+    use ark_ff::{Field, Zero};
+
+    #[test]
+    pub fn test_compressed_srs() {
+        let (g1_points, g2_points) = crate::ptau::read("8.ptau", 511, 256).unwrap();
+
+        // Use small chunks so that ranges span several chunks and the cache
+        // has to evict
+        let srs = CompressedSrs::with_cache(&g1_points, &g2_points, 16, 2).unwrap();
+        assert_eq!(srs.num_g1_points(), 511);
+        assert_eq!(srs.num_g2_points(), 256);
+        for (i, point) in g1_points.iter().enumerate() {
+            assert_eq!(srs.g1(i), *point);
+        }
+        for (i, point) in g2_points.iter().enumerate().rev() {
+            assert_eq!(srs.g2(i), *point);
+        }
+        assert_eq!(srs.g1_range(5..100), g1_points[5..100]);
+        assert_eq!(srs.g1_range(490..511), g1_points[490..511]);
+        assert_eq!(srs.g2_range(0..256), g2_points);
+        assert!(srs.g2_range(7..7).is_empty());
+    }
+
+    #[test]
+    pub fn test_compressed_srs_infinity() {
+        let g1_points = vec![G1Affine::zero(), G1Affine::prime_subgroup_generator()];
+        let g2_points = vec![G2Affine::prime_subgroup_generator(), G2Affine::zero()];
+        let srs = CompressedSrs::new(&g1_points, &g2_points).unwrap();
+        assert_eq!(srs.g1_range(0..2), g1_points);
+        assert_eq!(srs.g2_range(0..2), g2_points);
+    }
+
+    #[test]
+    pub fn test_compressed_srs_invalid() {
+        let g1_points = vec![G1Affine::prime_subgroup_generator()];
+        let g2_points = vec![G2Affine::prime_subgroup_generator()];
+        assert!(matches!(
+            CompressedSrs::with_cache(&g1_points, &g2_points, 0, 1),
+            Err(Error::InvalidChunkSize)
+        ));
+
+        // Points off the curve are rejected rather than failing to
+        // decompress
+        let mut off_curve = G1Affine::prime_subgroup_generator();
+        off_curve.y.double_in_place();
+        assert!(matches!(
+            CompressedSrs::new(&[off_curve], &g2_points),
+            Err(Error::InvalidG1Point)
+        ));
+        let mut off_curve = G2Affine::prime_subgroup_generator();
+        off_curve.x.double_in_place();
+        assert!(matches!(
+            CompressedSrs::new(&g1_points, &[off_curve]),
+            Err(Error::InvalidG2Point)
+        ));
+    }
+}
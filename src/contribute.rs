@@ -0,0 +1,664 @@
+//! Contributions to a ptau file, made as `snarkjs powersoftau contribute`
+//! makes them, for ceremony tooling written in Rust.
+
+use crate::audit;
+use crate::blake2b::Blake2b;
+use crate::chacha::ChaCha;
+use crate::contributions::{
+    encode_params, first_challenge, g2_sp, key_bytes, random_g1, read_contributions, recorded_name,
+    uncompressed_g1, uncompressed_g2, Beacon, Contribution, ContributionKey, KeyPair,
+};
+use crate::ptau::{
+    encode_g1, encode_g2, io_error, open, read_header_from, read_section_range_from, Error,
+    PointFormat, PtauHeader, ReadOptions, Section, G1_FORMAT, G2_FORMAT,
+};
+use crate::truncate::{copy_section, write_section_header};
+use crate::writer::write_output;
+use ark_bn254::{Fr, FrParameters};
+use ark_ec::{AffineCurve, ProjectiveCurve};
+use ark_ff::biginteger::BigInteger256;
+use ark_ff::{FpParameters, One, PrimeField};
+use ark_std::rand::{CryptoRng, RngCore};
+use blake2::{Blake2b512, Digest};
+use rayon::prelude::*;
+use sha2::Sha256;
+use std::fs::File;
+use std::io::{BufReader, Read, Seek, SeekFrom, Write};
+use zeroize::Zeroize;
+
+// The number of points updated at a time
+const CHUNK_POINTS: usize = 1 << 16;
+
+// The secrets of a contribution. Anyone who learns the secrets of every
+// contribution to a file can forge proofs, so they are cleared when dropped.
+pub(crate) struct Secrets {
+    pub(crate) tau: Fr,
+    pub(crate) alpha: Fr,
+    pub(crate) beta: Fr,
+}
+
+impl Drop for Secrets {
+    fn drop(&mut self) {
+        self.tau.zeroize();
+        self.alpha.zeroize();
+        self.beta.zeroize();
+    }
+}
+
+// What a contribution records besides its points and public key
+pub(crate) struct RecordParams<'a> {
+    pub(crate) contribution_type: u32,
+    pub(crate) name: Option<&'a str>,
+    pub(crate) beacon: Option<&'a Beacon>,
+}
+
+/// Contribute to `input` and write the updated file to `output`, as
+/// `snarkjs powersoftau contribute` does. Secrets tau, alpha and beta are
+/// drawn from `rng`, the points of sections 2 to 6 are multiplied by the
+/// matching products of them, and a record of the contribution with a
+/// public key proving knowledge of the secrets is added to section 7.
+///
+/// Returns the record, whose `response_hash` the contributor publishes to
+/// attest to the contribution. `name`, if given, is recorded up to its
+/// first 64 characters.
+///
+/// The secrets are only as good as `rng`, which should be seeded from the
+/// operating system. They are not returned.
+///
+/// `input` must be at its ceremony power. Its Lagrange sections are not
+/// carried over, so the output must be prepared again with
+/// `prepare::prepare_phase2`. If `input` has no contributions, the
+/// challenge of the first one is computed from the power (see
+/// `contributions::first_challenge` for its cost). The output is written
+/// to a temporary file renamed to `output` once complete, so `output` may
+/// be `input`.
+pub fn contribute<R: RngCore + CryptoRng>(
+    input: &str,
+    output: &str,
+    name: Option<&str>,
+    rng: &mut R,
+    options: &ReadOptions,
+) -> Result<Contribution, Error> {
+    audit::record("contribute", input, &[], || {
+        let params = RecordParams {
+            contribution_type: 0,
+            name,
+            beacon: None,
+        };
+        apply(input, output, &params, options, |challenge| {
+            generate_key(challenge, rng)
+        })
+    })
+}
+
+/// Apply a random beacon to `input` and write the updated file to `output`,
+/// as `snarkjs powersoftau beacon` does. The secrets are drawn from a
+/// ChaCha generator seeded with `beacon_hash` hashed with SHA-256
+/// `2^num_iterations_exp` times, so anyone can recompute them from the
+/// beacon parameters recorded with the contribution.
+///
+/// Ceremonies end with a beacon, whose hash is a public value nobody could
+/// predict before the last contribution, such as a future block hash.
+/// `beacon_hash` must be 1 to 255 bytes long and `num_iterations_exp` from
+/// 10 to 63. As for `contribute`, `input` must be at its ceremony power.
+pub fn apply_beacon(
+    input: &str,
+    output: &str,
+    name: Option<&str>,
+    beacon_hash: &[u8],
+    num_iterations_exp: u8,
+    options: &ReadOptions,
+) -> Result<Contribution, Error> {
+    audit::record("apply_beacon", input, &[], || {
+        if beacon_hash.is_empty()
+            || beacon_hash.len() > 255
+            || !(10..=63).contains(&num_iterations_exp)
+        {
+            return Err(Error::InvalidBeacon);
+        }
+        let beacon = Beacon {
+            num_iterations_exp,
+            hash: beacon_hash.to_vec(),
+        };
+        let params = RecordParams {
+            contribution_type: 1,
+            name,
+            beacon: Some(&beacon),
+        };
+        apply(input, output, &params, options, |challenge| {
+            generate_key(challenge, &mut beacon_rng(&beacon))
+        })
+    })
+}
+
+// The generator snarkjs draws the secrets of a beacon contribution from
+pub(crate) fn beacon_rng(beacon: &Beacon) -> ChaCha {
+    let mut hash = Sha256::digest(&beacon.hash);
+    for _ in 1..1u64 << beacon.num_iterations_exp {
+        hash = Sha256::digest(hash);
+    }
+    let mut seed = [0u32; 8];
+    for (i, word) in seed.iter_mut().enumerate() {
+        *word = u32::from_be_bytes(hash[4 * i..4 * i + 4].try_into().unwrap());
+    }
+    ChaCha::new(seed)
+}
+
+// Apply to `input` the contribution whose secrets and key `key` derives
+// from the challenge it is made to, writing the result to `output`
+pub(crate) fn apply<F>(
+    input: &str,
+    output: &str,
+    params: &RecordParams<'_>,
+    options: &ReadOptions,
+    key: F,
+) -> Result<Contribution, Error>
+where
+    F: FnOnce(&[u8; 64]) -> (Secrets, ContributionKey),
+{
+    let mut f = BufReader::new(open(input, options)?);
+    let header = read_header_from(&mut f, options)?;
+    if header.power < header.ceremony_power {
+        return Err(Error::BelowCeremonyPower {
+            power: header.power,
+            ceremony_power: header.ceremony_power,
+        });
+    }
+    // The record holds [tau]_1 and [tau]_2, which files of power 0 lack
+    if header.power == 0 {
+        return Err(Error::ZeroPower);
+    }
+    for id in 1..=7 {
+        header.section(id).ok_or(Error::MissingSection(id))?;
+    }
+    let challenge = match read_contributions(input, options)?.last() {
+        Some(last) => last.next_challenge,
+        None => first_challenge(header.power),
+    };
+    let (secrets, key) = key(&challenge);
+
+    write_output(output, |out, temp| {
+        let mut update = Update {
+            reader: &mut f,
+            header: &header,
+            out: &mut *out,
+            options,
+            // The magic string, version and number of sections
+            position: 12,
+            sections: Vec::new(),
+        };
+        update.write_header()?;
+
+        // The response is the challenge hash followed by the updated points and
+        // the public key
+        let mut response = Blake2b::new();
+        response.update(&challenge);
+        let (num_g1_points, num_g2_points) = header.num_points()?;
+        let one = Fr::one();
+        let tau = secrets.tau;
+        let tau_g1 = update.update_section(2, num_g1_points, one, tau, G1, &mut response)?;
+        let tau_g2 = update.update_section(3, num_g2_points, one, tau, G2, &mut response)?;
+        let alpha_g1 =
+            update.update_section(4, num_g2_points, secrets.alpha, tau, G1, &mut response)?;
+        let beta_g1 =
+            update.update_section(5, num_g2_points, secrets.beta, tau, G1, &mut response)?;
+        let beta_g2 = update.update_section(6, 1, secrets.beta, tau, G2, &mut response)?;
+        let sections = std::mem::take(&mut update.sections);
+
+        let partial_hash = response.to_partial();
+        response.update(&key_bytes(&key));
+        let response_hash = response.finalize();
+        out.flush().map_err(io_error)?;
+        let next_challenge = next_challenge(temp, &sections, &response_hash, options)?;
+
+        let contribution = Contribution {
+            tau_g1: tau_g1[1],
+            tau_g2: tau_g2[1],
+            alpha_g1: alpha_g1[0],
+            beta_g1: beta_g1[0],
+            beta_g2: beta_g2[0],
+            key,
+            partial_hash,
+            next_challenge,
+            contribution_type: params.contribution_type,
+            name: recorded_name(params.name).map(str::to_string),
+            beacon: params.beacon.cloned(),
+            response_hash,
+        };
+        let record = record_bytes(&contribution, &encode_params(params.name, params.beacon));
+        write_contributions(&mut f, &header, out, &record)?;
+        Ok(contribution)
+    })
+}
+
+// Draw the secrets and public key of a contribution made to `challenge`, as
+// snarkjs does
+pub(crate) fn generate_key<R: RngCore>(
+    challenge: &[u8; 64],
+    rng: &mut R,
+) -> (Secrets, ContributionKey) {
+    let secrets = Secrets {
+        tau: random_fr(rng),
+        alpha: random_fr(rng),
+        beta: random_fr(rng),
+    };
+    let key = ContributionKey {
+        tau: key_pair(0, secrets.tau, challenge, rng),
+        alpha: key_pair(1, secrets.alpha, challenge, rng),
+        beta: key_pair(2, secrets.beta, challenge, rng),
+    };
+    (secrets, key)
+}
+
+fn key_pair<R: RngCore>(personalization: u8, x: Fr, challenge: &[u8; 64], rng: &mut R) -> KeyPair {
+    let g1_s = random_g1(rng);
+    let g1_sx = g1_s.mul(x.into_repr()).into_affine();
+    let g2_sp = g2_sp(personalization, challenge, &g1_s, &g1_sx);
+    KeyPair {
+        g1_s,
+        g1_sx,
+        g2_spx: g2_sp.mul(x.into_repr()).into_affine(),
+    }
+}
+
+// A random scalar whose Montgomery representation is uniform below the
+// modulus
+fn random_fr<R: RngCore>(rng: &mut R) -> Fr {
+    loop {
+        let mut limbs = [0u64; 4];
+        for limb in limbs.iter_mut() {
+            *limb = rng.next_u64();
+        }
+        limbs[3] &= u64::MAX >> 2;
+        let repr = BigInteger256(limbs);
+        if repr < FrParameters::MODULUS {
+            return Fr::new(repr);
+        }
+    }
+}
+
+// How the points of a group are stored in the file and hashed
+struct Group<A> {
+    format: PointFormat<A>,
+    encode: fn(&A, &mut Vec<u8>),
+    uncompressed: fn(&A, &mut Vec<u8>),
+}
+
+const G1: Group<ark_bn254::G1Affine> = Group {
+    format: G1_FORMAT,
+    encode: encode_g1,
+    uncompressed: uncompressed_g1,
+};
+
+const G2: Group<ark_bn254::G2Affine> = Group {
+    format: G2_FORMAT,
+    encode: encode_g2,
+    uncompressed: uncompressed_g2,
+};
+
+struct Update<'a, R, W> {
+    reader: &'a mut R,
+    header: &'a PtauHeader,
+    out: &'a mut W,
+    options: &'a ReadOptions,
+    // Where the next section starts in the output
+    position: u64,
+    // The id, position and number of points of the sections written
+    sections: Vec<(u32, u64, usize)>,
+}
+
+impl<R: Read + Seek, W: Write> Update<'_, R, W> {
+    fn write_header(&mut self) -> Result<(), Error> {
+        self.out.write_all(b"ptau").map_err(io_error)?;
+        self.out.write_all(&1u32.to_le_bytes()).map_err(io_error)?;
+        self.out.write_all(&7u32.to_le_bytes()).map_err(io_error)?;
+        let section = self.header.section(1).unwrap();
+        write_section_header(self.out, 1, section.size).map_err(io_error)?;
+        self.reader
+            .seek(SeekFrom::Start(section.position))
+            .map_err(io_error)?;
+        std::io::copy(&mut self.reader.by_ref().take(section.size), self.out).map_err(io_error)?;
+        self.position += 12 + section.size;
+        Ok(())
+    }
+
+    // Write section `id` with its first `count` points multiplied by
+    // `first * tau^i`, hashing them into `response`. Returns the first two
+    // updated points.
+    fn update_section<A: AffineCurve<ScalarField = Fr>>(
+        &mut self,
+        id: u32,
+        count: usize,
+        first: Fr,
+        tau: Fr,
+        group: Group<A>,
+        response: &mut Blake2b,
+    ) -> Result<Vec<A>, Error> {
+        let size = count as u64 * group.format.size as u64;
+        write_section_header(self.out, id, size).map_err(io_error)?;
+        self.sections.push((id, self.position + 12, count));
+        self.position += 12 + size;
+
+        let mut factor = first;
+        let mut head = Vec::new();
+        let mut buf = Vec::new();
+        let mut hashed = Vec::new();
+        for start in (0..count).step_by(CHUNK_POINTS) {
+            let end = (start + CHUNK_POINTS).min(count);
+            let points = read_section_range_from(
+                self.reader,
+                self.header,
+                id,
+                start,
+                end,
+                self.options,
+                group.format,
+                A::zero(),
+            )?;
+            let factors: Vec<Fr> = (start..end)
+                .map(|_| {
+                    let current = factor;
+                    factor *= tau;
+                    current
+                })
+                .collect();
+            let updated: Vec<A::Projective> = self.options.install(|| {
+                points
+                    .par_iter()
+                    .zip(factors.par_iter())
+                    .map(|(point, factor)| point.mul(factor.into_repr()))
+                    .collect()
+            })?;
+            let updated = A::Projective::batch_normalization_into_affine(&updated);
+
+            buf.clear();
+            hashed.clear();
+            for point in &updated {
+                (group.encode)(point, &mut buf);
+                (group.uncompressed)(point, &mut hashed);
+            }
+            self.out.write_all(&buf).map_err(io_error)?;
+            response.update(&hashed);
+            if head.is_empty() {
+                head = updated.iter().take(2).copied().collect();
+            }
+        }
+        Ok(head)
+    }
+}
+
+// The challenge of the next contribution: the hash of the response hash
+// followed by the updated points, read back from the output as snarkjs does
+fn next_challenge(
+    output: &str,
+    sections: &[(u32, u64, usize)],
+    response_hash: &[u8; 64],
+    options: &ReadOptions,
+) -> Result<[u8; 64], Error> {
+    // The points were just computed, so are only decoded
+    let options = options
+        .clone()
+        .check_on_curve(false)
+        .check_subgroup(false)
+        .check_g1_subgroup(false);
+    let mut f = BufReader::new(File::open(output).map_err(io_error)?);
+    let mut hasher = Blake2b512::new();
+    hasher.update(response_hash);
+    for &(id, position, count) in sections {
+        f.seek(SeekFrom::Start(position)).map_err(io_error)?;
+        if id == 3 || id == 6 {
+            hash_points(&mut f, count, G2, &mut hasher, &options)?;
+        } else {
+            hash_points(&mut f, count, G1, &mut hasher, &options)?;
+        }
+    }
+    Ok(hasher.finalize().into())
+}
+
+fn hash_points<R: Read, A>(
+    reader: &mut R,
+    count: usize,
+    group: Group<A>,
+    hasher: &mut Blake2b512,
+    options: &ReadOptions,
+) -> Result<(), Error> {
+    let mut buf = vec![0u8; group.format.size];
+    let mut hashed = Vec::new();
+    for _ in 0..count {
+        reader.read_exact(&mut buf).map_err(io_error)?;
+        let point = (group.format.decode)(&buf, options)?;
+        hashed.clear();
+        (group.uncompressed)(&point, &mut hashed);
+        hasher.update(&hashed);
+    }
+    Ok(())
+}
+
+// Write section 7: the records of `input` followed by `record`
+fn write_contributions<R: Read + Seek, W: Write>(
+    reader: &mut R,
+    header: &PtauHeader,
+    out: &mut W,
+    record: &[u8],
+) -> Result<(), Error> {
+    let section = header.section(7).unwrap();
+    if section.size < 4 {
+        return Err(Error::InvalidContributions);
+    }
+    reader
+        .seek(SeekFrom::Start(section.position))
+        .map_err(io_error)?;
+    let mut count = [0u8; 4];
+    reader.read_exact(&mut count).map_err(io_error)?;
+    let count = u32::from_le_bytes(count) + 1;
+
+    write_section_header(out, 7, section.size + record.len() as u64).map_err(io_error)?;
+    out.write_all(&count.to_le_bytes()).map_err(io_error)?;
+    // The records after the count
+    let records = Section {
+        id: 7,
+        position: section.position + 4,
+        size: section.size - 4,
+    };
+    copy_section(reader, &records, records.size, out)?;
+    out.write_all(record).map_err(io_error)
+}
+
+// A contribution record as snarkjs writes it, with points in the format of
+// the file
+fn record_bytes(contribution: &Contribution, params: &[u8]) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    encode_g1(&contribution.tau_g1, &mut bytes);
+    encode_g2(&contribution.tau_g2, &mut bytes);
+    encode_g1(&contribution.alpha_g1, &mut bytes);
+    encode_g1(&contribution.beta_g1, &mut bytes);
+    encode_g2(&contribution.beta_g2, &mut bytes);
+    let key = &contribution.key;
+    for pair in [&key.tau, &key.alpha, &key.beta] {
+        encode_g1(&pair.g1_s, &mut bytes);
+        encode_g1(&pair.g1_sx, &mut bytes);
+    }
+    for pair in [&key.tau, &key.alpha, &key.beta] {
+        encode_g2(&pair.g2_spx, &mut bytes);
+    }
+    bytes.extend_from_slice(&contribution.partial_hash);
+    bytes.extend_from_slice(&contribution.next_challenge);
+    bytes.extend_from_slice(&contribution.contribution_type.to_le_bytes());
+    bytes.extend_from_slice(&(params.len() as u32).to_le_bytes());
+    bytes.extend_from_slice(params);
+    bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{apply_beacon, beacon_rng, contribute, generate_key};
+    use crate::contributions::{first_challenge, read_contributions};
+    use crate::dev::generate_with_alpha_beta;
+    use crate::diff::diff;
+    use crate::export::export_challenge;
+    use crate::ptau::{beta_g2, read, read_alpha_tau_g1, read_beta_tau_g1, Error, ReadOptions};
+    use crate::verify::verify_file;
+    use ark_bn254::Fr;
+    use ark_std::rand::rngs::StdRng;
+    use ark_std::rand::SeedableRng;
+
+    #[test]
+    pub fn test_contribute() {
+        let options = ReadOptions::default();
+        let input = std::env::temp_dir().join("ppot-rs-contribute-input.ptau");
+        let input = input.to_str().unwrap();
+        let output = std::env::temp_dir().join("ppot-rs-contribute-output.ptau");
+        let output = output.to_str().unwrap();
+        let (tau, alpha, beta) = (Fr::from(7u64), Fr::from(11u64), Fr::from(13u64));
+        generate_with_alpha_beta(tau, alpha, beta, 3)
+            .write_file(input)
+            .unwrap();
+
+        let contribution = contribute(
+            input,
+            output,
+            Some("alice"),
+            &mut StdRng::seed_from_u64(1),
+            &options,
+        )
+        .unwrap();
+        assert_eq!(verify_file(output, &options), Ok(()));
+        assert_eq!(
+            read_contributions(output, &options).unwrap(),
+            std::slice::from_ref(&contribution)
+        );
+        assert_eq!(contribution.name.as_deref(), Some("alice"));
+        let challenge = first_challenge(3);
+        assert!(diff(input, output, Some(&challenge), &options)
+            .unwrap()
+            .all_valid());
+        // The next challenge is the hash of the challenge file of the output
+        assert_eq!(
+            export_challenge(output, &options, &mut std::io::sink()).unwrap(),
+            contribution.next_challenge
+        );
+
+        // The points are those of the products of the secrets
+        let (secrets, _) = generate_key(&challenge, &mut StdRng::seed_from_u64(1));
+        let expected = generate_with_alpha_beta(
+            tau * secrets.tau,
+            alpha * secrets.alpha,
+            beta * secrets.beta,
+            3,
+        );
+        assert_eq!(
+            read(output, 15, 8).unwrap(),
+            (expected.tau_g1.clone(), expected.tau_g2.clone())
+        );
+        let alpha_beta = expected.alpha_beta.unwrap();
+        assert_eq!(
+            read_alpha_tau_g1(output, 8, &options).unwrap(),
+            alpha_beta.alpha_tau_g1
+        );
+        assert_eq!(
+            read_beta_tau_g1(output, 8, &options).unwrap(),
+            alpha_beta.beta_tau_g1
+        );
+        assert_eq!(beta_g2(output, &options).unwrap(), alpha_beta.beta_g2);
+
+        // A second contribution, made to the next challenge
+        contribute(output, input, None, &mut StdRng::seed_from_u64(2), &options).unwrap();
+        let contributions = read_contributions(input, &options).unwrap();
+        assert_eq!(contributions.len(), 2);
+        assert_eq!(contributions[1].name, None);
+        assert!(diff(output, input, None, &options).unwrap().all_valid());
+
+        // A third, written over its input
+        std::fs::copy(input, output).unwrap();
+        contribute(
+            output,
+            output,
+            None,
+            &mut StdRng::seed_from_u64(3),
+            &options,
+        )
+        .unwrap();
+        assert_eq!(verify_file(output, &options), Ok(()));
+        assert_eq!(read_contributions(output, &options).unwrap().len(), 3);
+        assert!(diff(input, output, None, &options).unwrap().all_valid());
+
+        generate_with_alpha_beta(tau, alpha, beta, 0)
+            .write_file(input)
+            .unwrap();
+        assert_eq!(
+            contribute(input, output, None, &mut StdRng::seed_from_u64(3), &options),
+            Err(Error::ZeroPower)
+        );
+
+        assert_eq!(
+            contribute(
+                "8.ptau",
+                output,
+                None,
+                &mut StdRng::seed_from_u64(3),
+                &options
+            ),
+            Err(Error::BelowCeremonyPower {
+                power: 8,
+                ceremony_power: 28
+            })
+        );
+        std::fs::remove_file(input).unwrap();
+        std::fs::remove_file(output).unwrap();
+    }
+
+    #[test]
+    pub fn test_apply_beacon() {
+        // The key of the beacon that ends 8.ptau is the one its parameters
+        // give
+        let options = ReadOptions::default();
+        let contributions = read_contributions("8.ptau", &options).unwrap();
+        let last = &contributions[54];
+        let mut rng = beacon_rng(last.beacon.as_ref().unwrap());
+        let (_, key) = generate_key(&contributions[53].next_challenge, &mut rng);
+        assert_eq!(key, last.key);
+
+        let input = std::env::temp_dir().join("ppot-rs-beacon-input.ptau");
+        let input = input.to_str().unwrap();
+        let output = std::env::temp_dir().join("ppot-rs-beacon-output.ptau");
+        let output = output.to_str().unwrap();
+        let (tau, alpha, beta) = (Fr::from(7u64), Fr::from(11u64), Fr::from(13u64));
+        generate_with_alpha_beta(tau, alpha, beta, 3)
+            .write_file(input)
+            .unwrap();
+        let beacon_hash = [0xab; 32];
+        let contribution =
+            apply_beacon(input, output, Some("beacon"), &beacon_hash, 10, &options).unwrap();
+        assert_eq!(verify_file(output, &options), Ok(()));
+        assert_eq!(
+            read_contributions(output, &options).unwrap(),
+            std::slice::from_ref(&contribution)
+        );
+        assert_eq!(contribution.contribution_type, 1);
+        let beacon = contribution.beacon.as_ref().unwrap();
+        assert_eq!(
+            (beacon.num_iterations_exp, &beacon.hash[..]),
+            (10, &beacon_hash[..])
+        );
+        assert!(diff(input, output, Some(&first_challenge(3)), &options)
+            .unwrap()
+            .all_valid());
+
+        // Anyone can recompute the points from the beacon
+        let (secrets, key) = generate_key(&first_challenge(3), &mut beacon_rng(beacon));
+        assert_eq!(key, contribution.key);
+        let expected = generate_with_alpha_beta(tau * secrets.tau, alpha, beta, 3);
+        assert_eq!(read(output, 15, 8).unwrap().0, expected.tau_g1);
+
+        for (hash, num_iterations_exp) in
+            [(&[][..], 10), (&[0; 256][..], 10), (&beacon_hash[..], 9)]
+        {
+            assert_eq!(
+                apply_beacon(input, output, None, hash, num_iterations_exp, &options),
+                Err(Error::InvalidBeacon)
+            );
+        }
+        std::fs::remove_file(input).unwrap();
+        std::fs::remove_file(output).unwrap();
+    }
+}
@@ -0,0 +1,539 @@
+//! Typed access to the contribution records of section 7, and verification
+//! of the proofs of knowledge in their public keys.
+
+use crate::audit;
+use crate::blake2b::Blake2b;
+use crate::chacha::ChaCha;
+use crate::ptau::{
+    decode_g1, decode_g2, io_error, open, read_bytes, read_header_from, Error, ReadOptions,
+    FQ_MODULUS,
+};
+use ark_bn254::{Bn254, Fq, Fq2, G1Affine, G2Affine};
+use ark_ec::{AffineCurve, PairingEngine};
+use ark_ff::biginteger::{BigInteger, BigInteger256};
+use ark_ff::{PrimeField, Zero};
+use ark_std::rand::RngCore;
+use blake2::{Blake2b512, Digest};
+use std::io::{BufReader, Read, Seek, SeekFrom};
+
+// The size of a record with no parameters: 5 points, the public key (6 G1
+// and 3 G2 points), the partial response hash, the next challenge, the type
+// and the parameter length
+const MIN_RECORD_SIZE: u64 = 3 * 64 + 2 * 128 + 6 * 64 + 3 * 128 + 216 + 64 + 4 + 4;
+
+/// A proof of knowledge of one of the secrets `x` of a contribution:
+/// `g1_sx = x * g1_s`, and `g2_spx = x * g2_sp` for a point `g2_sp`
+/// derived from the challenge and `g1_s` and `g1_sx`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyPair {
+    pub g1_s: G1Affine,
+    pub g1_sx: G1Affine,
+    pub g2_spx: G2Affine,
+}
+
+/// The public key of a contribution, proving knowledge of its tau, alpha
+/// and beta.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContributionKey {
+    pub tau: KeyPair,
+    pub alpha: KeyPair,
+    pub beta: KeyPair,
+}
+
+/// A contribution record: `tau * G1`, `tau * G2`, `alpha * G1`,
+/// `beta * G1` and `beta * G2` after the contribution was applied, and its
+/// public key.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Contribution {
+    pub tau_g1: G1Affine,
+    pub tau_g2: G2Affine,
+    pub alpha_g1: G1Affine,
+    pub beta_g1: G1Affine,
+    pub beta_g2: G2Affine,
+    pub key: ContributionKey,
+    /// The BLAKE2b state after hashing the response up to these points.
+    pub partial_hash: [u8; 216],
+    /// The challenge the next contribution was made to.
+    pub next_challenge: [u8; 64],
+    /// 0 for a regular contribution, 1 for a random beacon.
+    pub contribution_type: u32,
+    /// The name the contributor gave, if any.
+    pub name: Option<String>,
+    /// The beacon parameters, for a beacon contribution.
+    pub beacon: Option<Beacon>,
+    /// The hash of the response file of the contribution, which
+    /// contributors publish to attest to it.
+    pub response_hash: [u8; 64],
+}
+
+/// The parameters of a random beacon contribution: the beacon value, hashed
+/// `2^num_iterations_exp` times to derive the secrets.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Beacon {
+    pub num_iterations_exp: u8,
+    pub hash: Vec<u8>,
+}
+
+impl ContributionKey {
+    /// Check the proofs of knowledge of the key, for a contribution made to
+    /// `challenge`: the `next_challenge` of the previous contribution, or
+    /// `first_challenge` for the first one.
+    pub fn verify(&self, challenge: &[u8; 64]) -> bool {
+        [&self.tau, &self.alpha, &self.beta]
+            .iter()
+            .enumerate()
+            .all(|(personalization, pair)| pair.verify(personalization as u8, challenge))
+    }
+}
+
+impl KeyPair {
+    fn verify(&self, personalization: u8, challenge: &[u8; 64]) -> bool {
+        if self.g1_s.is_zero() || self.g1_sx.is_zero() {
+            return false;
+        }
+        let g2_sp = g2_sp(personalization, challenge, &self.g1_s, &self.g1_sx);
+        Bn254::pairing(self.g1_s, self.g2_spx) == Bn254::pairing(self.g1_sx, g2_sp)
+    }
+}
+
+/// Read the contribution records of section 7.
+pub fn read_contributions(
+    ptau_file: &str,
+    options: &ReadOptions,
+) -> Result<Vec<Contribution>, Error> {
+    audit::record("read_contributions", ptau_file, &[], || {
+        let mut f = BufReader::new(open(ptau_file, options)?);
+        let header = read_header_from(&mut f, options)?;
+        let section = header.section(7).ok_or(Error::MissingSection(7))?;
+        f.seek(SeekFrom::Start(section.position))
+            .map_err(io_error)?;
+        let mut f = f.take(section.size);
+
+        let num_contributions = read_u32(&mut f)?;
+        if num_contributions as u64 * MIN_RECORD_SIZE > section.size {
+            return Err(Error::InvalidContributions);
+        }
+        (0..num_contributions)
+            .map(|_| read_contribution(&mut f, options))
+            .collect()
+    })
+}
+
+/// Check the proofs of knowledge of every contribution key, the first
+/// contribution having been made to `first_challenge`.
+pub fn verify_keys(
+    contributions: &[Contribution],
+    first_challenge: &[u8; 64],
+) -> Result<(), Error> {
+    let mut challenge = first_challenge;
+    for (index, contribution) in contributions.iter().enumerate() {
+        if !contribution.key.verify(challenge) {
+            return Err(Error::InvalidContributionKey { index });
+        }
+        challenge = &contribution.next_challenge;
+    }
+    Ok(())
+}
+
+/// Check the proof of knowledge of each contribution key against the
+/// challenge it was made to, giving `None` for the first contribution if
+/// `first_challenge` is not known. Unlike `verify_keys`, this reports every
+/// contribution instead of stopping at the first invalid one.
+pub fn key_statuses(
+    contributions: &[Contribution],
+    first_challenge: Option<&[u8; 64]>,
+) -> Vec<Option<bool>> {
+    let challenges = std::iter::once(first_challenge)
+        .chain(contributions.iter().map(|c| Some(&c.next_challenge)));
+    contributions
+        .iter()
+        .zip(challenges)
+        .map(|(contribution, challenge)| challenge.map(|c| contribution.key.verify(c)))
+        .collect()
+}
+
+/// The challenge the first contribution of a ceremony of power
+/// `ceremony_power` is made to: the hash of a file whose points are all
+/// generators.
+///
+/// This hashes about `3 * 2^(ceremony_power + 5)` bytes of G1 and G2
+/// points, which takes minutes for the largest ceremonies.
+pub fn first_challenge(ceremony_power: u32) -> [u8; 64] {
+    let mut g1 = Vec::new();
+    uncompressed_g1(&G1Affine::prime_subgroup_generator(), &mut g1);
+    let mut g2 = Vec::new();
+    uncompressed_g2(&G2Affine::prime_subgroup_generator(), &mut g2);
+
+    let num_g2_points = 1u64 << ceremony_power;
+    let mut hasher = Blake2b512::new();
+    hasher.update(Blake2b512::digest([]));
+    hash_repeated(&mut hasher, &g1, 2 * num_g2_points - 1);
+    hash_repeated(&mut hasher, &g2, num_g2_points);
+    hash_repeated(&mut hasher, &g1, num_g2_points);
+    hash_repeated(&mut hasher, &g1, num_g2_points);
+    hasher.update(&g2);
+    hasher.finalize().into()
+}
+
+fn hash_repeated(hasher: &mut Blake2b512, bytes: &[u8], count: u64) {
+    const BLOCK_POINTS: u64 = 1 << 12;
+    let block = bytes.repeat(BLOCK_POINTS as usize);
+    let mut remaining = count;
+    while remaining > 0 {
+        let n = remaining.min(BLOCK_POINTS);
+        hasher.update(&block[..n as usize * bytes.len()]);
+        remaining -= n;
+    }
+}
+
+fn read_contribution<R: Read + Seek>(
+    reader: &mut R,
+    options: &ReadOptions,
+) -> Result<Contribution, Error> {
+    let tau_g1 = read_g1(reader, options)?;
+    let tau_g2 = read_g2(reader, options)?;
+    let alpha_g1 = read_g1(reader, options)?;
+    let beta_g1 = read_g1(reader, options)?;
+    let beta_g2 = read_g2(reader, options)?;
+
+    let mut g1 = [G1Affine::zero(); 6];
+    for point in g1.iter_mut() {
+        *point = read_g1(reader, options)?;
+    }
+    let mut g2 = [G2Affine::zero(); 3];
+    for point in g2.iter_mut() {
+        *point = read_g2(reader, options)?;
+    }
+    let key = ContributionKey {
+        tau: KeyPair {
+            g1_s: g1[0],
+            g1_sx: g1[1],
+            g2_spx: g2[0],
+        },
+        alpha: KeyPair {
+            g1_s: g1[2],
+            g1_sx: g1[3],
+            g2_spx: g2[1],
+        },
+        beta: KeyPair {
+            g1_s: g1[4],
+            g1_sx: g1[5],
+            g2_spx: g2[2],
+        },
+    };
+
+    let mut partial_hash = [0u8; 216];
+    read_bytes(reader, &mut partial_hash, 7)?;
+    let mut next_challenge = [0u8; 64];
+    read_bytes(reader, &mut next_challenge, 7)?;
+    let contribution_type = read_u32(reader)?;
+    let params_len = read_u32(reader)?;
+    let mut params = Vec::new();
+    reader
+        .take(params_len as u64)
+        .read_to_end(&mut params)
+        .map_err(io_error)?;
+    if params.len() != params_len as usize {
+        return Err(Error::InvalidContributions);
+    }
+    let (name, beacon) = parse_params(&params)?;
+
+    // The response is hashed up to the public key, which is hashed last
+    let mut hasher = Blake2b::from_partial(&partial_hash).ok_or(Error::InvalidContributions)?;
+    hasher.update(&key_bytes(&key));
+    let response_hash = hasher.finalize();
+
+    Ok(Contribution {
+        tau_g1,
+        tau_g2,
+        alpha_g1,
+        beta_g1,
+        beta_g2,
+        key,
+        partial_hash,
+        next_challenge,
+        contribution_type,
+        name,
+        beacon,
+        response_hash,
+    })
+}
+
+// The public key as hashed into the response: the G1 points of each pair,
+// then the G2 points, uncompressed
+pub(crate) fn key_bytes(key: &ContributionKey) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(6 * 64 + 3 * 128);
+    for pair in [&key.tau, &key.alpha, &key.beta] {
+        uncompressed_g1(&pair.g1_s, &mut bytes);
+        uncompressed_g1(&pair.g1_sx, &mut bytes);
+    }
+    for pair in [&key.tau, &key.alpha, &key.beta] {
+        uncompressed_g2(&pair.g2_spx, &mut bytes);
+    }
+    bytes
+}
+
+// The name snarkjs records for a contribution: none if empty, and
+// otherwise its first 64 characters, within the 255 bytes a length byte
+// allows
+pub(crate) fn recorded_name(name: Option<&str>) -> Option<&str> {
+    let name = name.filter(|name| !name.is_empty())?;
+    let end = name
+        .char_indices()
+        .nth(64)
+        .map_or(name.len(), |(index, _)| index);
+    let mut name = &name[..end];
+    while name.len() > 255 {
+        let mut chars = name.chars();
+        chars.next_back();
+        name = chars.as_str();
+    }
+    Some(name)
+}
+
+// Encode the parameters of a contribution as snarkjs does
+pub(crate) fn encode_params(name: Option<&str>, beacon: Option<&Beacon>) -> Vec<u8> {
+    let mut params = Vec::new();
+    if let Some(name) = recorded_name(name) {
+        params.push(1);
+        params.push(name.len() as u8);
+        params.extend_from_slice(name.as_bytes());
+    }
+    if let Some(beacon) = beacon {
+        params.extend_from_slice(&[2, beacon.num_iterations_exp, 3, beacon.hash.len() as u8]);
+        params.extend_from_slice(&beacon.hash);
+    }
+    params
+}
+
+// Parse the parameters of a contribution: a sequence of fields, each a type
+// byte followed by its value. snarkjs writes the name (type 1) and, for a
+// beacon, the iteration exponent (type 2) and the beacon hash (type 3).
+fn parse_params(mut params: &[u8]) -> Result<(Option<String>, Option<Beacon>), Error> {
+    fn next<'a>(params: &mut &'a [u8], len: usize) -> Result<&'a [u8], Error> {
+        if params.len() < len {
+            return Err(Error::InvalidContributions);
+        }
+        let (value, rest) = params.split_at(len);
+        *params = rest;
+        Ok(value)
+    }
+
+    let mut name = None;
+    let mut num_iterations_exp = None;
+    let mut hash = None;
+    while !params.is_empty() {
+        let field = next(&mut params, 1)?[0];
+        match field {
+            1 => {
+                let len = next(&mut params, 1)?[0];
+                let value = next(&mut params, len as usize)?;
+                name = Some(String::from_utf8_lossy(value).into_owned());
+            }
+            2 => num_iterations_exp = Some(next(&mut params, 1)?[0]),
+            3 => {
+                let len = next(&mut params, 1)?[0];
+                hash = Some(next(&mut params, len as usize)?.to_vec());
+            }
+            _ => return Err(Error::InvalidContributions),
+        }
+    }
+    let beacon = match (num_iterations_exp, hash) {
+        (Some(num_iterations_exp), Some(hash)) => Some(Beacon {
+            num_iterations_exp,
+            hash,
+        }),
+        (None, None) => None,
+        _ => return Err(Error::InvalidContributions),
+    };
+    Ok((name, beacon))
+}
+
+fn read_u32<R: Read + Seek>(reader: &mut R) -> Result<u32, Error> {
+    let mut buf = [0u8; 4];
+    read_bytes(reader, &mut buf, 7)?;
+    Ok(u32::from_le_bytes(buf))
+}
+
+fn read_g1<R: Read + Seek>(reader: &mut R, options: &ReadOptions) -> Result<G1Affine, Error> {
+    let mut buf = [0u8; 64];
+    read_bytes(reader, &mut buf, 7)?;
+    decode_g1(&buf, options)
+}
+
+fn read_g2<R: Read + Seek>(reader: &mut R, options: &ReadOptions) -> Result<G2Affine, Error> {
+    let mut buf = [0u8; 128];
+    read_bytes(reader, &mut buf, 7)?;
+    decode_g2(&buf, options)
+}
+
+// The point `g2_sp` of a key pair, derived from the hash of the challenge
+// and its G1 points as snarkjs and the original Rust ceremony code do
+pub(crate) fn g2_sp(
+    personalization: u8,
+    challenge: &[u8; 64],
+    g1_s: &G1Affine,
+    g1_sx: &G1Affine,
+) -> G2Affine {
+    let mut bytes = vec![personalization];
+    bytes.extend_from_slice(challenge);
+    uncompressed_g1(g1_s, &mut bytes);
+    uncompressed_g1(g1_sx, &mut bytes);
+    let hash = Blake2b512::digest(&bytes);
+
+    let mut seed = [0u32; 8];
+    for (i, word) in seed.iter_mut().enumerate() {
+        *word = u32::from_be_bytes(hash[4 * i..4 * i + 4].try_into().unwrap());
+    }
+    let mut rng = ChaCha::new(seed);
+    loop {
+        let x = Fq2::new(random_fq(&mut rng), random_fq(&mut rng));
+        let greatest = rng.next_u32() & 1 == 1;
+        if let Some(p) = G2Affine::get_point_from_x(x, greatest) {
+            if !p.is_zero() {
+                return p.scale_by_cofactor().into();
+            }
+        }
+    }
+}
+
+// A random G1 point, drawn as snarkjs does for the public keys of
+// contributions
+pub(crate) fn random_g1<R: RngCore>(rng: &mut R) -> G1Affine {
+    loop {
+        let x = random_fq(rng);
+        let greatest = rng.next_u32() & 1 == 1;
+        if let Some(p) = G1Affine::get_point_from_x(x, greatest) {
+            return p;
+        }
+    }
+}
+
+// A random field element whose Montgomery representation is uniform below
+// the modulus
+fn random_fq<R: RngCore>(rng: &mut R) -> Fq {
+    loop {
+        let mut limbs = [0u64; 4];
+        for limb in limbs.iter_mut() {
+            *limb = rng.next_u64();
+        }
+        limbs[3] &= u64::MAX >> 2;
+        let repr = BigInteger256(limbs);
+        if repr < FQ_MODULUS {
+            return Fq::new(repr);
+        }
+    }
+}
+
+// The big-endian uncompressed encoding, with the infinity flag set for zero
+pub(crate) fn uncompressed_g1(p: &G1Affine, out: &mut Vec<u8>) {
+    if p.is_zero() {
+        out.push(0x40);
+        out.extend_from_slice(&[0u8; 63]);
+        return;
+    }
+    out.extend_from_slice(&p.x.into_repr().to_bytes_be());
+    out.extend_from_slice(&p.y.into_repr().to_bytes_be());
+}
+
+pub(crate) fn uncompressed_g2(p: &G2Affine, out: &mut Vec<u8>) {
+    if p.is_zero() {
+        out.push(0x40);
+        out.extend_from_slice(&[0u8; 127]);
+        return;
+    }
+    for c in [p.x.c1, p.x.c0, p.y.c1, p.y.c0] {
+        out.extend_from_slice(&c.into_repr().to_bytes_be());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        encode_params, key_statuses, parse_params, read_contributions, verify_keys, Beacon,
+    };
+    use crate::ptau::{Error, ReadOptions};
+
+    #[test]
+    pub fn test_read_contributions() {
+        let contributions = read_contributions("8.ptau", &ReadOptions::new()).unwrap();
+        assert_eq!(contributions.len(), 55);
+        assert!(contributions[..54].iter().all(|c| c.contribution_type == 0));
+        assert_eq!(contributions[54].contribution_type, 1);
+        assert_eq!(contributions[0].name.as_deref(), Some("weijie"));
+        assert_eq!(contributions[53].name.as_deref(), Some("jarrad"));
+        assert!(contributions[..54].iter().all(|c| c.beacon.is_none()));
+        let beacon = contributions[54].beacon.as_ref().unwrap();
+        assert_eq!(beacon.num_iterations_exp, 10);
+        assert_eq!(
+            hex::encode(&beacon.hash),
+            "e586fccaf245c9a1d7e78294d4802018f3001149a71b8f10cd997ef8235aa372"
+        );
+        assert_eq!(contributions[54].name, None);
+
+        // Response hashes are unique to each contribution
+        let mut hashes: Vec<_> = contributions.iter().map(|c| c.response_hash).collect();
+        hashes.sort();
+        hashes.dedup();
+        assert_eq!(hashes.len(), 55);
+
+        // The last contribution is the one that produced the file
+        let (g1_points, g2_points) = crate::ptau::read("8.ptau", 2, 2).unwrap();
+        assert_eq!(contributions[54].tau_g1, g1_points[1]);
+        assert_eq!(contributions[54].tau_g2, g2_points[1]);
+    }
+
+    #[test]
+    pub fn test_verify_keys() {
+        let contributions = read_contributions("8.ptau", &ReadOptions::new()).unwrap();
+        for i in 1..contributions.len() {
+            assert!(contributions[i]
+                .key
+                .verify(&contributions[i - 1].next_challenge));
+        }
+        assert!(!contributions[2]
+            .key
+            .verify(&contributions[0].next_challenge));
+
+        let first_challenge = contributions[0].next_challenge;
+        assert_eq!(verify_keys(&contributions[1..], &first_challenge), Ok(()));
+
+        let statuses = key_statuses(&contributions[1..4], None);
+        assert_eq!(statuses, vec![None, Some(true), Some(true)]);
+        let mut reordered = contributions[1..4].to_vec();
+        reordered.swap(1, 2);
+        assert_eq!(
+            key_statuses(&reordered, Some(&first_challenge)),
+            vec![Some(true), Some(false), Some(false)]
+        );
+        assert_eq!(
+            verify_keys(&contributions[2..], &first_challenge),
+            Err(Error::InvalidContributionKey { index: 0 })
+        );
+    }
+
+    #[test]
+    pub fn test_parse_params() {
+        assert_eq!(parse_params(&[]), Ok((None, None)));
+        let (name, beacon) = parse_params(b"\x01\x03abc").unwrap();
+        assert_eq!(name.as_deref(), Some("abc"));
+        assert_eq!(beacon, None);
+
+        // Truncated values, unknown fields and half a beacon are rejected
+        for params in [&b"\x01\x04abc"[..], b"\x04", b"\x02\x0a", b"\x03\x01"] {
+            assert_eq!(parse_params(params), Err(Error::InvalidContributions));
+        }
+
+        // Names are cut to 64 characters, and empty ones are not recorded
+        let beacon = Beacon {
+            num_iterations_exp: 10,
+            hash: vec![0xab; 32],
+        };
+        let name = "é".repeat(70);
+        let params = encode_params(Some(&name), Some(&beacon));
+        let (parsed_name, parsed_beacon) = parse_params(&params).unwrap();
+        assert_eq!(parsed_name, Some("é".repeat(64)));
+        assert_eq!(parsed_beacon, Some(beacon));
+        assert!(encode_params(Some(""), None).is_empty());
+    }
+}
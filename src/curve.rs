@@ -0,0 +1,304 @@
+//! The pairing-friendly curves ptau files can hold powers over, and the
+//! decoding of their points.
+//!
+//! Files store each base field element as `n8` little-endian bytes in
+//! Montgomery form, G1 points as `x, y` and G2 points as
+//! `x.c0, x.c1, y.c0, y.c1`, with the point at infinity as all zeroes.
+
+use crate::audit;
+use crate::ptau::{
+    check_infinity, io_error, open, read_curve, read_header_for, Error, PointFormat, PtauHeader,
+    ReadOptions,
+};
+use ark_ec::models::short_weierstrass_jacobian::GroupAffine;
+use ark_ec::SWModelParameters;
+use ark_ff::biginteger::BigInteger;
+use ark_ff::fields::{FftField, FftParameters, Field, FpParameters, PrimeField};
+use ark_ff::Zero;
+use std::io::{Read, Seek, SeekFrom};
+
+/// A curve whose powers of tau can be read from a ptau file, implemented
+/// for the arkworks pairing engines of the supported curves. Other curves
+/// with short Weierstrass G1 and G2 over a prime field and its quadratic
+/// extension can implement it to use `ptau::read_curve`.
+pub trait Curve: 'static {
+    type Fq: PrimeField;
+    /// The base field of G2, a quadratic extension of `Fq`.
+    type Fqe: Field<BasePrimeField = Self::Fq>;
+    type Fr: PrimeField + FftField;
+    type G1Parameters: SWModelParameters<BaseField = Self::Fq, ScalarField = Self::Fr>;
+    type G2Parameters: SWModelParameters<BaseField = Self::Fqe, ScalarField = Self::Fr>;
+
+    /// The base field element whose Montgomery form is `repr`, which is
+    /// below the modulus.
+    fn fq_from_montgomery(repr: <Self::Fq as PrimeField>::BigInt) -> Self::Fq;
+
+    /// The size in bytes of a base field element, the `n8` of the header.
+    fn n8() -> usize {
+        <<Self::Fq as PrimeField>::BigInt as BigInteger>::NUM_LIMBS * 8
+    }
+
+    /// The size in bytes of a G1 point, 2 base field elements.
+    fn g1_size() -> usize {
+        2 * Self::n8()
+    }
+
+    /// The size in bytes of a G2 point, 4 base field elements.
+    fn g2_size() -> usize {
+        4 * Self::n8()
+    }
+
+    /// The base field modulus as `n8` little-endian bytes, as found in the
+    /// header.
+    fn modulus_le_bytes() -> Vec<u8> {
+        <Self::Fq as PrimeField>::Params::MODULUS.to_bytes_le()
+    }
+
+    /// The largest power of two dividing the order of the multiplicative
+    /// group of the scalar field.
+    fn two_adicity() -> u32 {
+        <<Self::Fr as FftField>::FftParams as FftParameters>::TWO_ADICITY
+    }
+}
+
+impl Curve for ark_bn254::Bn254 {
+    type Fq = ark_bn254::Fq;
+    type Fqe = ark_bn254::Fq2;
+    type Fr = ark_bn254::Fr;
+    type G1Parameters = ark_bn254::g1::Parameters;
+    type G2Parameters = ark_bn254::g2::Parameters;
+
+    fn fq_from_montgomery(repr: <Self::Fq as PrimeField>::BigInt) -> Self::Fq {
+        ark_bn254::Fq::new(repr)
+    }
+}
+
+#[cfg(feature = "bls12-381")]
+impl Curve for ark_bls12_381::Bls12_381 {
+    type Fq = ark_bls12_381::Fq;
+    type Fqe = ark_bls12_381::Fq2;
+    type Fr = ark_bls12_381::Fr;
+    type G1Parameters = ark_bls12_381::g1::Parameters;
+    type G2Parameters = ark_bls12_381::g2::Parameters;
+
+    fn fq_from_montgomery(repr: <Self::Fq as PrimeField>::BigInt) -> Self::Fq {
+        ark_bls12_381::Fq::new(repr)
+    }
+}
+
+/// The points of G1 over the curve `C`.
+pub type G1Affine<C> = GroupAffine<<C as Curve>::G1Parameters>;
+/// The points of G2 over the curve `C`.
+pub type G2Affine<C> = GroupAffine<<C as Curve>::G2Parameters>;
+/// The G1 and G2 tau powers of a file over the curve `C`.
+pub type TauPowers<C> = (Vec<G1Affine<C>>, Vec<G2Affine<C>>);
+
+/// The curves this crate can read ptau files over.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CurveId {
+    Bn254,
+    #[cfg(feature = "bls12-381")]
+    Bls12_381,
+}
+
+impl CurveId {
+    /// The name of the curve, as in exported descriptors.
+    pub fn name(&self) -> &'static str {
+        match self {
+            CurveId::Bn254 => "bn254",
+            #[cfg(feature = "bls12-381")]
+            CurveId::Bls12_381 => "bls12-381",
+        }
+    }
+}
+
+/// The tau powers of a file over any supported curve, as returned by
+/// `read_any`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AnyTauPowers {
+    Bn254(TauPowers<ark_bn254::Bn254>),
+    #[cfg(feature = "bls12-381")]
+    Bls12_381(TauPowers<ark_bls12_381::Bls12_381>),
+}
+
+impl AnyTauPowers {
+    pub fn curve(&self) -> CurveId {
+        match self {
+            AnyTauPowers::Bn254(_) => CurveId::Bn254,
+            #[cfg(feature = "bls12-381")]
+            AnyTauPowers::Bls12_381(_) => CurveId::Bls12_381,
+        }
+    }
+}
+
+/// Find the curve of a ptau file from the base field modulus recorded in
+/// its header. Fails with `Error::InvalidPrimeOrder` if the modulus is not
+/// that of a supported curve, and otherwise as `ptau::read_header` does.
+pub fn detect_curve(ptau_file: &str) -> Result<CurveId, Error> {
+    detect_curve_with_options(ptau_file, &ReadOptions::default())
+}
+
+pub fn detect_curve_with_options(ptau_file: &str, options: &ReadOptions) -> Result<CurveId, Error> {
+    audit::record("detect_curve", ptau_file, &[], || {
+        read_any_header_from(&mut open(ptau_file, options)?, options).map(|(curve, _)| curve)
+    })
+}
+
+// Parse the header as a file over each supported curve in turn. The header
+// fields read before the modulus don't depend on the curve, so any other
+// error is the same for all curves.
+pub(crate) fn read_any_header_from<R: Read + Seek>(
+    reader: &mut R,
+    options: &ReadOptions,
+) -> Result<(CurveId, PtauHeader), Error> {
+    let parsers: &[(CurveId, HeaderParser<R>)] = &[
+        (CurveId::Bn254, read_header_for::<ark_bn254::Bn254, R>),
+        #[cfg(feature = "bls12-381")]
+        (
+            CurveId::Bls12_381,
+            read_header_for::<ark_bls12_381::Bls12_381, R>,
+        ),
+    ];
+    for (curve, parse) in parsers {
+        reader.seek(SeekFrom::Start(0)).map_err(io_error)?;
+        match parse(reader, options) {
+            Ok(header) => return Ok((*curve, header)),
+            Err(Error::InvalidPrimeOrder) => continue,
+            Err(e) => return Err(e),
+        }
+    }
+    Err(Error::InvalidPrimeOrder)
+}
+
+type HeaderParser<R> = fn(&mut R, &ReadOptions) -> Result<PtauHeader, Error>;
+
+/// Read the first tau powers of a ptau file over any supported curve, as
+/// `ptau::read_curve` does for the curve found by `detect_curve`.
+pub fn read_any(
+    ptau_file: &str,
+    num_g1_points: usize,
+    num_g2_points: usize,
+    options: &ReadOptions,
+) -> Result<AnyTauPowers, Error> {
+    Ok(match detect_curve_with_options(ptau_file, options)? {
+        CurveId::Bn254 => AnyTauPowers::Bn254(read_curve::<ark_bn254::Bn254>(
+            ptau_file,
+            num_g1_points,
+            num_g2_points,
+            options,
+        )?),
+        #[cfg(feature = "bls12-381")]
+        CurveId::Bls12_381 => AnyTauPowers::Bls12_381(read_curve::<ark_bls12_381::Bls12_381>(
+            ptau_file,
+            num_g1_points,
+            num_g2_points,
+            options,
+        )?),
+    })
+}
+
+// How G1 points of `C` are stored
+pub(crate) fn g1_format<C: Curve>() -> PointFormat<G1Affine<C>> {
+    PointFormat {
+        size: C::g1_size(),
+        decode: decode_g1::<C>,
+    }
+}
+
+// How G2 points of `C` are stored
+pub(crate) fn g2_format<C: Curve>() -> PointFormat<G2Affine<C>> {
+    PointFormat {
+        size: C::g2_size(),
+        decode: decode_g2::<C>,
+    }
+}
+
+// Decode a G1 point of `C` stored as 2 field elements
+pub(crate) fn decode_g1<C: Curve>(buf: &[u8], options: &ReadOptions) -> Result<G1Affine<C>, Error> {
+    if buf.iter().all(|b| *b == 0) {
+        check_infinity(options, "G1")?;
+        return Ok(GroupAffine::zero());
+    }
+    let n8 = C::n8();
+    let x = decode_fq::<C>(&buf[..n8], options)?;
+    let y = decode_fq::<C>(&buf[n8..2 * n8], options)?;
+    let g1 = GroupAffine::new(x, y, false);
+    if (options.check_on_curve || options.check_g1_subgroup) && !g1.is_on_curve() {
+        return Err(Error::InvalidG1Point);
+    }
+    if options.check_g1_subgroup && !g1.is_in_correct_subgroup_assuming_on_curve() {
+        return Err(Error::G1NotInSubgroup);
+    }
+    Ok(g1)
+}
+
+// Decode a G2 point of `C` stored as 4 field elements
+pub(crate) fn decode_g2<C: Curve>(buf: &[u8], options: &ReadOptions) -> Result<G2Affine<C>, Error> {
+    if buf.iter().all(|b| *b == 0) {
+        check_infinity(options, "G2")?;
+        return Ok(GroupAffine::zero());
+    }
+    let n8 = C::n8();
+    let fq = |i: usize| decode_fq::<C>(&buf[i * n8..(i + 1) * n8], options);
+    let x = C::Fqe::from_base_prime_field_elems(&[fq(0)?, fq(1)?]).unwrap();
+    let y = C::Fqe::from_base_prime_field_elems(&[fq(2)?, fq(3)?]).unwrap();
+    let g2 = GroupAffine::new(x, y, false);
+    if (options.check_on_curve || options.check_subgroup) && !g2.is_on_curve() {
+        return Err(Error::InvalidG2Point);
+    }
+    if options.check_subgroup && !g2.is_in_correct_subgroup_assuming_on_curve() {
+        return Err(Error::G2NotInSubgroup);
+    }
+    Ok(g2)
+}
+
+// Decode a base field element stored in Montgomery form as `n8`
+// little-endian bytes
+fn decode_fq<C: Curve>(buf: &[u8], options: &ReadOptions) -> Result<C::Fq, Error> {
+    let mut repr = <C::Fq as PrimeField>::BigInt::default();
+    for (limb, bytes) in repr.as_mut().iter_mut().zip(buf.chunks_exact(8)) {
+        *limb = u64::from_le_bytes(bytes.try_into().unwrap());
+    }
+    let modulus = <C::Fq as PrimeField>::Params::MODULUS;
+    if repr >= modulus {
+        if !options.lenient {
+            return Err(Error::NonCanonicalEncoding);
+        }
+        while repr >= modulus {
+            repr.sub_noborrow(&modulus);
+        }
+    }
+    Ok(C::fq_from_montgomery(repr))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{detect_curve, read_any, AnyTauPowers, CurveId};
+    use crate::ptau::{self, Error, ReadOptions};
+
+    #[test]
+    pub fn test_detect_curve() {
+        assert_eq!(detect_curve("8.ptau").unwrap(), CurveId::Bn254);
+        let srs = read_any("8.ptau", 3, 2, &ReadOptions::default()).unwrap();
+        assert_eq!(srs.curve(), CurveId::Bn254);
+        assert_eq!(
+            srs,
+            AnyTauPowers::Bn254(ptau::read("8.ptau", 3, 2).unwrap())
+        );
+
+        // Change the modulus in the header
+        let mut data = std::fs::read("8.ptau").unwrap();
+        data[28] ^= 1;
+        let path = std::env::temp_dir().join("ppot-rs-unknown-curve.ptau");
+        std::fs::write(&path, data).unwrap();
+        assert!(matches!(
+            detect_curve(path.to_str().unwrap()),
+            Err(Error::InvalidPrimeOrder)
+        ));
+        assert!(matches!(
+            detect_curve("nonexistent.ptau"),
+            Err(Error::Io(_))
+        ));
+        std::fs::remove_file(path).unwrap();
+    }
+}
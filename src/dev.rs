@@ -0,0 +1,170 @@
+//! Tau powers computed from a known tau, for tests and local development
+//! without a ceremony. Anyone who knows tau can forge proofs, so these
+//! must never be used in production.
+
+use crate::ptau::Error;
+use crate::srs::Srs;
+use crate::writer::PtauWriter;
+use ark_bn254::{Fr, G1Affine, G1Projective, G2Affine, G2Projective};
+use ark_ec::msm::FixedBaseMSM;
+use ark_ec::ProjectiveCurve;
+use ark_ff::{One, PrimeField};
+
+/// The tau powers of a file of power `power`, as returned by `generate`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DevSrs {
+    pub power: u32,
+    pub tau_g1: Vec<G1Affine>,
+    pub tau_g2: Vec<G2Affine>,
+    /// Only computed by `generate_with_alpha_beta`.
+    pub alpha_beta: Option<AlphaBetaPowers>,
+}
+
+/// `[alpha * tau^i]_1`, `[beta * tau^i]_1` and `[beta]_2`, the contents of
+/// sections 4 to 6.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AlphaBetaPowers {
+    pub alpha_tau_g1: Vec<G1Affine>,
+    pub beta_tau_g1: Vec<G1Affine>,
+    pub beta_g2: G2Affine,
+}
+
+impl DevSrs {
+    /// A writer for the ptau file holding these points, with the alpha and
+    /// beta sections if they were computed.
+    pub fn writer(&self) -> PtauWriter<'_> {
+        let writer = PtauWriter::new(self.power, &self.tau_g1, &self.tau_g2);
+        match &self.alpha_beta {
+            Some(powers) => {
+                writer.alpha_beta(&powers.alpha_tau_g1, &powers.beta_tau_g1, powers.beta_g2)
+            }
+            None => writer,
+        }
+    }
+
+    pub fn write_file(&self, ptau_file: &str) -> Result<(), Error> {
+        self.writer().write_file(ptau_file)
+    }
+
+    pub fn to_srs(&self) -> Srs {
+        Srs::new(self.tau_g1.clone(), self.tau_g2.clone())
+    }
+}
+
+/// Compute the `2^(power + 1) - 1` G1 and `2^power` G2 powers of `tau`.
+pub fn generate(tau: Fr, power: u32) -> DevSrs {
+    let tau_powers = scalar_powers(tau, (2usize << power) - 1);
+    DevSrs {
+        power,
+        tau_g1: g1_multiples(&tau_powers),
+        tau_g2: g2_multiples(&tau_powers[..1 << power]),
+        alpha_beta: None,
+    }
+}
+
+/// Like `generate`, also computing the alpha and beta sections.
+pub fn generate_with_alpha_beta(tau: Fr, alpha: Fr, beta: Fr, power: u32) -> DevSrs {
+    let srs = generate(tau, power);
+    let tau_powers = scalar_powers(tau, 1 << power);
+    let alpha_tau_powers: Vec<Fr> = tau_powers.iter().map(|t| alpha * t).collect();
+    let beta_tau_powers: Vec<Fr> = tau_powers.iter().map(|t| beta * t).collect();
+    DevSrs {
+        alpha_beta: Some(AlphaBetaPowers {
+            alpha_tau_g1: g1_multiples(&alpha_tau_powers),
+            beta_tau_g1: g1_multiples(&beta_tau_powers),
+            beta_g2: g2_multiples(&[beta])[0],
+        }),
+        ..srs
+    }
+}
+
+// 1, x, ..., x^(count - 1)
+pub(crate) fn scalar_powers(x: Fr, count: usize) -> Vec<Fr> {
+    let mut powers = Vec::with_capacity(count);
+    let mut cur = Fr::one();
+    for _ in 0..count {
+        powers.push(cur);
+        cur *= x;
+    }
+    powers
+}
+
+// The generator of G1 times each scalar
+pub(crate) fn g1_multiples(scalars: &[Fr]) -> Vec<G1Affine> {
+    let scalar_size = Fr::size_in_bits();
+    let window = FixedBaseMSM::get_mul_window_size(scalars.len());
+    let table = FixedBaseMSM::get_window_table(
+        scalar_size,
+        window,
+        G1Projective::prime_subgroup_generator(),
+    );
+    let points =
+        FixedBaseMSM::multi_scalar_mul::<G1Projective>(scalar_size, window, &table, scalars);
+    G1Projective::batch_normalization_into_affine(&points)
+}
+
+// The generator of G2 times each scalar
+pub(crate) fn g2_multiples(scalars: &[Fr]) -> Vec<G2Affine> {
+    let scalar_size = Fr::size_in_bits();
+    let window = FixedBaseMSM::get_mul_window_size(scalars.len());
+    let table = FixedBaseMSM::get_window_table(
+        scalar_size,
+        window,
+        G2Projective::prime_subgroup_generator(),
+    );
+    let points =
+        FixedBaseMSM::multi_scalar_mul::<G2Projective>(scalar_size, window, &table, scalars);
+    G2Projective::batch_normalization_into_affine(&points)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{generate, generate_with_alpha_beta};
+    use crate::ptau::{parse, read, read_alpha_tau_g1, Error, ReadOptions};
+    use crate::testing::generate_from_secrets;
+    use crate::verify::verify_file;
+    use ark_bn254::{Fr, G1Affine, G2Affine};
+    use ark_ec::AffineCurve;
+
+    #[test]
+    pub fn test_generate() {
+        let tau = Fr::from(7u64);
+        let srs = generate(tau, 2);
+        assert_eq!(srs.tau_g1.len(), 7);
+        assert_eq!(srs.tau_g2.len(), 4);
+        assert_eq!(srs.tau_g1[0], G1Affine::prime_subgroup_generator());
+        assert_eq!(srs.tau_g2[1], G2Affine::prime_subgroup_generator().mul(tau));
+        assert_eq!(srs.alpha_beta, None);
+        assert_eq!(srs.to_srs().g1_points(), &srs.tau_g1[..]);
+
+        // The same points as the test files, which also have the Lagrange
+        // sections
+        let (alpha, beta) = (Fr::from(11u64), Fr::from(13u64));
+        let srs = generate_with_alpha_beta(tau, alpha, beta, 2);
+        let ptau = parse(&generate_from_secrets(tau, alpha, beta, 2)).unwrap();
+        assert_eq!(
+            (&ptau.g1_points, &ptau.g2_points),
+            (&srs.tau_g1, &srs.tau_g2)
+        );
+
+        let options = ReadOptions::default();
+        let ptau_file = std::env::temp_dir().join("ppot-rs-dev.ptau");
+        let ptau_file = ptau_file.to_str().unwrap();
+        srs.write_file(ptau_file).unwrap();
+        assert_eq!(verify_file(ptau_file, &options), Ok(()));
+        assert_eq!(
+            read(ptau_file, 7, 4).unwrap(),
+            (srs.tau_g1.clone(), srs.tau_g2.clone())
+        );
+        assert_eq!(
+            read_alpha_tau_g1(ptau_file, 4, &options).unwrap(),
+            srs.alpha_beta.unwrap().alpha_tau_g1
+        );
+        generate(tau, 2).write_file(ptau_file).unwrap();
+        assert_eq!(
+            read_alpha_tau_g1(ptau_file, 4, &options),
+            Err(Error::MissingSection(4))
+        );
+        std::fs::remove_file(ptau_file).unwrap();
+    }
+}
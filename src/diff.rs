@@ -0,0 +1,209 @@
+//! What a single contribution changed, for auditors comparing two
+//! snapshots of a ceremony: the public key it added and the checks that
+//! the points were updated with the secrets the key proves knowledge of.
+
+use crate::audit;
+use crate::contributions::{g2_sp, read_contributions, Contribution, ContributionKey};
+use crate::ptau::{
+    alpha_g1, beta_g1, beta_g2, read_header_with_options, read_with_options, Error, ReadOptions,
+};
+use ark_bn254::{Bn254, G1Affine, G2Affine};
+use ark_ec::{AffineCurve, PairingEngine};
+
+/// The first point of each of sections 2 to 6 (`[tau]_1`, `[tau]_2`,
+/// `[alpha]_1`, `[beta]_1` and `[beta]_2`), which every contribution
+/// updates and records.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AccumulatorState {
+    pub tau_g1: G1Affine,
+    pub tau_g2: G2Affine,
+    pub alpha_g1: G1Affine,
+    pub beta_g1: G1Affine,
+    pub beta_g2: G2Affine,
+}
+
+impl AccumulatorState {
+    /// The state before the first contribution, with every point a
+    /// generator.
+    pub fn initial() -> Self {
+        let g1 = G1Affine::prime_subgroup_generator();
+        let g2 = G2Affine::prime_subgroup_generator();
+        Self {
+            tau_g1: g1,
+            tau_g2: g2,
+            alpha_g1: g1,
+            beta_g1: g1,
+            beta_g2: g2,
+        }
+    }
+
+    /// The state of a ptau file, read from its points.
+    pub fn read(ptau_file: &str, options: &ReadOptions) -> Result<Self, Error> {
+        let (g1_points, g2_points) = read_with_options(ptau_file, 2, 2, options)?;
+        Ok(Self {
+            tau_g1: g1_points[1],
+            tau_g2: g2_points[1],
+            alpha_g1: alpha_g1(ptau_file, options)?,
+            beta_g1: beta_g1(ptau_file, options)?,
+            beta_g2: beta_g2(ptau_file, options)?,
+        })
+    }
+}
+
+impl From<&Contribution> for AccumulatorState {
+    fn from(contribution: &Contribution) -> Self {
+        Self {
+            tau_g1: contribution.tau_g1,
+            tau_g2: contribution.tau_g2,
+            alpha_g1: contribution.alpha_g1,
+            beta_g1: contribution.beta_g1,
+            beta_g2: contribution.beta_g2,
+        }
+    }
+}
+
+/// Whether each point of an `AccumulatorState` was multiplied by the
+/// secret the contribution key proves knowledge of.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UpdateChecks {
+    pub tau_g1: bool,
+    pub tau_g2: bool,
+    pub alpha_g1: bool,
+    pub beta_g1: bool,
+    pub beta_g2: bool,
+}
+
+impl UpdateChecks {
+    pub fn all_valid(&self) -> bool {
+        self.tau_g1 && self.tau_g2 && self.alpha_g1 && self.beta_g1 && self.beta_g2
+    }
+}
+
+/// The contribution that turned one ptau file into another, as found by
+/// `diff`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContributionDiff {
+    /// The contribution record added to section 7.
+    pub contribution: Contribution,
+    /// Whether the key proves knowledge of its secrets.
+    pub key_valid: bool,
+    pub updates: UpdateChecks,
+    /// Whether the record holds the points of the second file.
+    pub record_matches_file: bool,
+}
+
+impl ContributionDiff {
+    pub fn all_valid(&self) -> bool {
+        self.key_valid && self.updates.all_valid() && self.record_matches_file
+    }
+}
+
+/// Check that `after` is `before` updated with the secrets of `key`, for a
+/// contribution made to `challenge`, as `snarkjs powersoftau verify` does
+/// between consecutive contributions.
+pub fn check_update(
+    before: &AccumulatorState,
+    after: &AccumulatorState,
+    key: &ContributionKey,
+    challenge: &[u8; 64],
+) -> UpdateChecks {
+    let tau_sp = g2_sp(0, challenge, &key.tau.g1_s, &key.tau.g1_sx);
+    let alpha_sp = g2_sp(1, challenge, &key.alpha.g1_s, &key.alpha.g1_sx);
+    let beta_sp = g2_sp(2, challenge, &key.beta.g1_s, &key.beta.g1_sx);
+    UpdateChecks {
+        tau_g1: same_ratio(before.tau_g1, after.tau_g1, tau_sp, key.tau.g2_spx),
+        tau_g2: same_ratio(key.tau.g1_s, key.tau.g1_sx, before.tau_g2, after.tau_g2),
+        alpha_g1: same_ratio(before.alpha_g1, after.alpha_g1, alpha_sp, key.alpha.g2_spx),
+        beta_g1: same_ratio(before.beta_g1, after.beta_g1, beta_sp, key.beta.g2_spx),
+        beta_g2: same_ratio(key.beta.g1_s, key.beta.g1_sx, before.beta_g2, after.beta_g2),
+    }
+}
+
+/// Find the contribution that turned `before` into `after`, whose
+/// contributions must be those of `before` followed by exactly one more,
+/// and check it.
+///
+/// If `before` has no contributions, the new one was made to the first
+/// challenge of the ceremony, which is computed from the ceremony power
+/// unless given as `first_challenge` (see `contributions::first_challenge`
+/// for its cost).
+pub fn diff(
+    before: &str,
+    after: &str,
+    first_challenge: Option<&[u8; 64]>,
+    options: &ReadOptions,
+) -> Result<ContributionDiff, Error> {
+    audit::record("diff", after, &[], || {
+        let previous = read_contributions(before, options)?;
+        let mut contributions = read_contributions(after, options)?;
+        if contributions.len() != previous.len() + 1
+            || contributions[..previous.len()] != previous[..]
+        {
+            return Err(Error::NotConsecutive);
+        }
+        let contribution = contributions.pop().unwrap();
+
+        let challenge = match (previous.last(), first_challenge) {
+            (Some(last), _) => last.next_challenge,
+            (None, Some(challenge)) => *challenge,
+            (None, None) => {
+                let header = read_header_with_options(before, options)?;
+                crate::contributions::first_challenge(header.ceremony_power)
+            }
+        };
+        let before_state = AccumulatorState::read(before, options)?;
+        let after_state = AccumulatorState::read(after, options)?;
+        Ok(ContributionDiff {
+            key_valid: contribution.key.verify(&challenge),
+            updates: check_update(&before_state, &after_state, &contribution.key, &challenge),
+            record_matches_file: AccumulatorState::from(&contribution) == after_state,
+            contribution,
+        })
+    })
+}
+
+// Whether b1 = x * a1 and b2 = x * a2 for the same x
+fn same_ratio(a1: G1Affine, b1: G1Affine, a2: G2Affine, b2: G2Affine) -> bool {
+    Bn254::pairing(a1, b2) == Bn254::pairing(b1, a2)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{check_update, diff, AccumulatorState};
+    use crate::contributions::read_contributions;
+    use crate::ptau::{Error, ReadOptions};
+
+    #[test]
+    pub fn test_check_update() {
+        let options = ReadOptions::default();
+        let contributions = read_contributions("8.ptau", &options).unwrap();
+        let state = AccumulatorState::read("8.ptau", &options).unwrap();
+        assert_eq!(state, AccumulatorState::from(&contributions[54]));
+
+        // Each record is the state after its contribution
+        for i in [1, 27, 54] {
+            let before = AccumulatorState::from(&contributions[i - 1]);
+            let after = AccumulatorState::from(&contributions[i]);
+            let challenge = &contributions[i - 1].next_challenge;
+            let checks = check_update(&before, &after, &contributions[i].key, challenge);
+            assert!(checks.all_valid());
+
+            // Another contribution's key does not match the update
+            let checks = check_update(&before, &after, &contributions[i - 1].key, challenge);
+            assert!(!checks.tau_g1 && !checks.tau_g2 && !checks.beta_g2);
+        }
+        // The G1 checks depend on the challenge, unlike the G2 checks
+        let checks = check_update(
+            &AccumulatorState::from(&contributions[0]),
+            &AccumulatorState::from(&contributions[1]),
+            &contributions[1].key,
+            &[0u8; 64],
+        );
+        assert!(!checks.tau_g1 && !checks.alpha_g1 && checks.tau_g2 && checks.beta_g2);
+
+        assert_eq!(
+            diff("8.ptau", "8.ptau", None, &options),
+            Err(Error::NotConsecutive)
+        );
+    }
+}
@@ -0,0 +1,372 @@
+//! Export of point bytes, raw for consumers (GPU pipelines, other
+//! languages) that don't use arkworks, in arkworks' serialization, or as a
+//! challenge for the legacy ceremony tooling.
+
+use crate::audit;
+use crate::contributions::{read_contributions, uncompressed_g1, uncompressed_g2};
+use crate::curve::{read_any_header_from, CurveId};
+use crate::file::PtauFile;
+use crate::ptau::{
+    io_error, is_g2_section, open, point_size, Error, ReadOptions, G1_FORMAT, G2_FORMAT,
+};
+use ark_serialize::CanonicalSerialize;
+use blake2::{Blake2b512, Digest};
+use std::io::{BufWriter, Read, Seek, SeekFrom, Write};
+
+// The number of bytes buffered before writing them out when exporting
+// decoded points
+const EXPORT_CHUNK_SIZE: usize = 1 << 16;
+
+/// Describes the bytes written by `export_raw`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RawDescriptor {
+    pub curve: CurveId,
+    pub section: u32,
+    pub num_points: usize,
+    /// The size in bytes of each point.
+    pub point_size: u64,
+    /// The size in bytes of each coordinate, the `n8` of the header.
+    pub field_element_size: u32,
+}
+
+impl RawDescriptor {
+    /// The descriptor as a JSON object, to ship alongside the exported
+    /// bytes.
+    pub fn to_json(&self) -> String {
+        format!(
+            concat!(
+                "{{\"curve\":\"{}\",\"section\":{},\"num_points\":{},",
+                "\"point_size\":{},\"field_element_size\":{},",
+                "\"encoding\":\"montgomery-le\",\"infinity\":\"all-zero\"}}"
+            ),
+            self.curve.name(),
+            self.section,
+            self.num_points,
+            self.point_size,
+            self.field_element_size
+        )
+    }
+}
+
+/// Write the first `num_points` points of `section` to `out`, exactly as
+/// they are stored in the file: affine coordinates as little-endian
+/// Montgomery-form field elements of `n8` bytes, G2 coordinates as `c0`
+/// then `c1`, and the point at infinity as all zeros.
+///
+/// The header is checked as by `curve::read_any`, but the points are copied
+/// without being decoded or validated.
+pub fn export_raw<W: Write>(
+    ptau_file: &str,
+    section: u32,
+    num_points: usize,
+    out: &mut W,
+) -> Result<RawDescriptor, Error> {
+    export_raw_with_options(ptau_file, section, num_points, &ReadOptions::default(), out)
+}
+
+/// Like `export_raw`, reading the file as set by `options`.
+pub fn export_raw_with_options<W: Write>(
+    ptau_file: &str,
+    section: u32,
+    num_points: usize,
+    options: &ReadOptions,
+    out: &mut W,
+) -> Result<RawDescriptor, Error> {
+    audit::record(
+        "export_raw",
+        ptau_file,
+        &[
+            ("section", section as u64),
+            ("num_points", num_points as u64),
+        ],
+        || {
+            let mut f = open(ptau_file, options)?;
+            let (curve, header) = read_any_header_from(&mut f, options)?;
+            let point_size =
+                point_size(section, header.n8).ok_or(Error::NotAPointSection(section))?;
+            let s = header
+                .section(section)
+                .ok_or(Error::MissingSection(section))?;
+            if num_points as u64 > s.size / point_size {
+                return Err(if is_g2_section(section) {
+                    Error::InvalidNumG2Points
+                } else {
+                    Error::InvalidNumG1Points
+                });
+            }
+
+            f.seek(SeekFrom::Start(s.position)).map_err(io_error)?;
+            let expected_bytes = num_points as u64 * point_size;
+            let copied = std::io::copy(&mut f.take(expected_bytes), out).map_err(io_error)?;
+            if copied < expected_bytes {
+                return Err(Error::UnexpectedEof {
+                    section,
+                    point_index: Some((copied / point_size) as usize),
+                    expected_bytes,
+                    got_bytes: copied,
+                });
+            }
+            Ok(RawDescriptor {
+                curve,
+                section,
+                num_points,
+                point_size,
+                field_element_size: header.n8,
+            })
+        },
+    )
+}
+
+/// How `export_g2_powers` encodes points.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum G2PowersFormat {
+    /// `Vec<G2Affine>` serialized with arkworks' `CanonicalSerialize`.
+    ArkworksCompressed,
+    ArkworksUncompressed,
+    /// The point bytes as stored in the file, as for `export_raw`.
+    Raw,
+}
+
+/// Write the first `num_points` G2 tau powers `[tau^i]_2`, for protocols
+/// that need many of them, such as Marlin-style degree bounds.
+///
+/// Points are decoded and written a chunk at a time, so memory use does not
+/// grow with `num_points`. Unlike `export_raw`, the points are validated
+/// according to `options`; on an invalid point, the points before it have
+/// already been written to `out`.
+pub fn export_g2_powers<W: Write>(
+    ptau_file: &str,
+    num_points: usize,
+    format: G2PowersFormat,
+    options: &ReadOptions,
+    out: &mut W,
+) -> Result<(), Error> {
+    let compress = match format {
+        G2PowersFormat::ArkworksCompressed => true,
+        G2PowersFormat::ArkworksUncompressed => false,
+        G2PowersFormat::Raw => {
+            export_raw(ptau_file, 3, num_points, out)?;
+            return Ok(());
+        }
+    };
+
+    let mut file = PtauFile::open(ptau_file, options)?;
+    let (_, max_points) = file.header().num_points()?;
+    if num_points > max_points {
+        return Err(Error::InvalidNumG2Points);
+    }
+    // The length prefix of a serialized `Vec`, followed by its elements
+    let mut bytes = (num_points as u64).to_le_bytes().to_vec();
+    for point in file.g2_iter().take(num_points) {
+        let point = point?;
+        if compress {
+            point.serialize(&mut bytes)
+        } else {
+            point.serialize_uncompressed(&mut bytes)
+        }
+        .expect("serializing to a Vec cannot fail");
+        if bytes.len() >= EXPORT_CHUNK_SIZE {
+            out.write_all(&bytes).map_err(io_error)?;
+            bytes.clear();
+        }
+    }
+    out.write_all(&bytes).map_err(io_error)
+}
+
+/// Write the challenge file of the legacy Powers of Tau tooling (the
+/// original Rust ceremony code and its forks) for the current state of a
+/// ptau file, as `snarkjs powersoftau export challenge` does: the hash of
+/// the last response, then the points of sections 2 to 6 in uncompressed
+/// big-endian form.
+///
+/// Returns the BLAKE2b hash of the challenge, which the next contribution
+/// is made to. For a file at its ceremony power, it is the
+/// `next_challenge` of the last contribution, or
+/// `contributions::first_challenge` if there are none.
+pub fn export_challenge<W: Write>(
+    ptau_file: &str,
+    options: &ReadOptions,
+    out: &mut W,
+) -> Result<[u8; 64], Error> {
+    audit::record("export_challenge", ptau_file, &[], || {
+        let last_response_hash: [u8; 64] = match read_contributions(ptau_file, options)?.last() {
+            Some(contribution) => contribution.response_hash,
+            None => Blake2b512::digest([]).into(),
+        };
+        let mut file = PtauFile::open(ptau_file, options)?;
+        let (_, num_g2_points) = file.header().num_points()?;
+
+        let mut out = BufWriter::with_capacity(
+            EXPORT_CHUNK_SIZE,
+            HashingWriter {
+                out,
+                hasher: Blake2b512::new(),
+            },
+        );
+        out.write_all(&last_response_hash).map_err(io_error)?;
+        write_uncompressed(&mut out, file.g1_iter(), uncompressed_g1)?;
+        write_uncompressed(&mut out, file.g2_iter(), uncompressed_g2)?;
+        for section in [4, 5] {
+            let points = file.point_iter(section, num_g2_points, G1_FORMAT);
+            write_uncompressed(&mut out, points, uncompressed_g1)?;
+        }
+        write_uncompressed(&mut out, file.point_iter(6, 1, G2_FORMAT), uncompressed_g2)?;
+        let out = out.into_inner().map_err(|e| io_error(e.into_error()))?;
+        Ok(out.hasher.finalize().into())
+    })
+}
+
+fn write_uncompressed<T, W: Write>(
+    out: &mut W,
+    points: impl Iterator<Item = Result<T, Error>>,
+    encode: fn(&T, &mut Vec<u8>),
+) -> Result<(), Error> {
+    let mut buf = Vec::new();
+    for point in points {
+        buf.clear();
+        encode(&point?, &mut buf);
+        out.write_all(&buf).map_err(io_error)?;
+    }
+    Ok(())
+}
+
+// Hashes everything written through it
+struct HashingWriter<'a, W> {
+    out: &'a mut W,
+    hasher: Blake2b512,
+}
+
+impl<W: Write> Write for HashingWriter<'_, W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let n = self.out.write(buf)?;
+        self.hasher.update(&buf[..n]);
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.out.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{export_challenge, export_g2_powers, export_raw, G2PowersFormat};
+    use crate::contributions::{first_challenge, read_contributions};
+    use crate::ptau::{read, Error, ReadOptions};
+    use ark_bn254::G2Affine;
+    use ark_serialize::CanonicalDeserialize;
+
+    #[test]
+    pub fn test_export_raw() {
+        let data = std::fs::read("8.ptau").unwrap();
+
+        let mut out = Vec::new();
+        let descriptor = export_raw("8.ptau", 2, 10, &mut out).unwrap();
+        assert_eq!(out, data[80..80 + 10 * 64]);
+        assert_eq!(
+            descriptor.to_json(),
+            "{\"curve\":\"bn254\",\"section\":2,\"num_points\":10,\"point_size\":64,\
+             \"field_element_size\":32,\"encoding\":\"montgomery-le\",\"infinity\":\"all-zero\"}"
+        );
+
+        let mut out = Vec::new();
+        export_raw("8.ptau", 3, 256, &mut out).unwrap();
+        assert_eq!(out, data[32796..32796 + 256 * 128]);
+
+        assert_eq!(
+            export_raw("8.ptau", 2, 512, &mut Vec::new()),
+            Err(Error::InvalidNumG1Points)
+        );
+        assert_eq!(
+            export_raw("8.ptau", 7, 1, &mut Vec::new()),
+            Err(Error::NotAPointSection(7))
+        );
+    }
+
+    #[test]
+    pub fn test_export_g2_powers() {
+        let options = ReadOptions::default();
+        let (_, g2_points) = read("8.ptau", 0, 100).unwrap();
+
+        let mut out = Vec::new();
+        export_g2_powers(
+            "8.ptau",
+            100,
+            G2PowersFormat::ArkworksCompressed,
+            &options,
+            &mut out,
+        )
+        .unwrap();
+        assert_eq!(Vec::<G2Affine>::deserialize(&out[..]).unwrap(), g2_points);
+
+        let mut out = Vec::new();
+        export_g2_powers(
+            "8.ptau",
+            100,
+            G2PowersFormat::ArkworksUncompressed,
+            &options,
+            &mut out,
+        )
+        .unwrap();
+        assert_eq!(
+            Vec::<G2Affine>::deserialize_uncompressed(&out[..]).unwrap(),
+            g2_points
+        );
+
+        let mut out = Vec::new();
+        export_g2_powers("8.ptau", 100, G2PowersFormat::Raw, &options, &mut out).unwrap();
+        let data = std::fs::read("8.ptau").unwrap();
+        assert_eq!(out, data[32796..32796 + 100 * 128]);
+
+        assert_eq!(
+            export_g2_powers(
+                "8.ptau",
+                257,
+                G2PowersFormat::Raw,
+                &options,
+                &mut Vec::new()
+            ),
+            Err(Error::InvalidNumG2Points)
+        );
+        assert_eq!(
+            export_g2_powers(
+                "8.ptau",
+                257,
+                G2PowersFormat::ArkworksCompressed,
+                &options,
+                &mut Vec::new()
+            ),
+            Err(Error::InvalidNumG2Points)
+        );
+    }
+
+    #[test]
+    pub fn test_export_challenge() {
+        use ark_ff::One;
+        use blake2::{Blake2b512, Digest};
+
+        let options = ReadOptions::default();
+        let mut out = Vec::new();
+        let hash = export_challenge("8.ptau", &options, &mut out).unwrap();
+        assert_eq!(out.len(), 64 + (511 + 2 * 256) * 64 + (256 + 1) * 128);
+        assert_eq!(hash, <[u8; 64]>::from(Blake2b512::digest(&out)));
+        let contributions = read_contributions("8.ptau", &options).unwrap();
+        assert_eq!(out[..64], contributions[54].response_hash);
+        // The first tau power is the generator (1, 2)
+        assert_eq!(out[64 + 31], 1);
+        assert_eq!(out[64 + 63], 2);
+
+        // The challenge of a file with no contributions, at its ceremony
+        // power, is the first challenge
+        let ptau_file = std::env::temp_dir().join("ppot-rs-challenge.ptau");
+        let ptau_file = ptau_file.to_str().unwrap();
+        let one = ark_bn254::Fr::one();
+        let data = crate::testing::generate_from_secrets(one, one, one, 3);
+        std::fs::write(ptau_file, data).unwrap();
+        assert_eq!(
+            export_challenge(ptau_file, &options, &mut Vec::new()).unwrap(),
+            first_challenge(3)
+        );
+        std::fs::remove_file(ptau_file).unwrap();
+    }
+}
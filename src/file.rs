@@ -0,0 +1,456 @@
+//! An open ptau file whose points are decoded lazily, for callers that
+//! process the SRS a chunk at a time instead of holding all of it in
+//! memory.
+
+use crate::audit;
+use crate::index::PtauIndex;
+use crate::ptau::{
+    check_header, io_error, open, points_position, read_bytes, read_header_from, read_point,
+    read_section_range_from, throttled, Error, ErrorKind, PointFormat, PtauHeader, ReadOptions,
+    Section, G1_FORMAT, G2_FORMAT,
+};
+use crate::throttle::ThrottledReader;
+use ark_bn254::{Bn254, G1Affine, G2Affine};
+use ark_ff::Zero;
+use std::fs::File;
+use std::io::{BufReader, Read, Seek, SeekFrom};
+#[cfg(not(any(unix, windows)))]
+use std::sync::Mutex;
+
+type Reader = BufReader<ThrottledReader<File>>;
+
+/// A ptau file whose header has been read and validated.
+///
+/// The file can be shared between threads: `g1_range` and `g2_range` take
+/// `&self` and read at explicit offsets, so concurrent calls don't contend
+/// for a file position.
+pub struct PtauFile {
+    reader: Reader,
+    header: PtauHeader,
+    options: ReadOptions,
+    num_g1_points: usize,
+    num_g2_points: usize,
+    index: Option<PtauIndex>,
+    // Makes the seek and read of a `PositionedReader` one step on targets
+    // without positioned reads
+    #[cfg(not(any(unix, windows)))]
+    seek_lock: Mutex<()>,
+}
+
+impl PtauFile {
+    pub fn open(ptau_file: &str, options: &ReadOptions) -> Result<Self, Error> {
+        audit::record("open", ptau_file, &[], || {
+            let mut reader = BufReader::new(open(ptau_file, options)?);
+            let header = read_header_from(&mut reader, options)?;
+            let (num_g1_points, num_g2_points) = header.num_points()?;
+            Ok(Self {
+                reader,
+                header,
+                options: options.clone(),
+                num_g1_points,
+                num_g2_points,
+                index: None,
+                #[cfg(not(any(unix, windows)))]
+                seek_lock: Mutex::new(()),
+            })
+        })
+    }
+
+    /// Open a ptau file using its sidecar index (see `index::read_index`)
+    /// instead of reading its header.
+    ///
+    /// The header recorded in the index is checked as the header of the
+    /// file would be, and its sections against the size of the file, but
+    /// the points are not: use `check_block` to check them.
+    pub fn open_with_index(
+        ptau_file: &str,
+        index: PtauIndex,
+        options: &ReadOptions,
+    ) -> Result<Self, Error> {
+        audit::record("open_with_index", ptau_file, &[], || {
+            let mut reader = BufReader::new(open(ptau_file, options)?);
+            let file_size = reader.seek(SeekFrom::End(0)).map_err(io_error)?;
+            if file_size != index.file_size {
+                return Err(Error::InvalidIndex);
+            }
+            // Limits of `options` are reported as such, and anything else
+            // the header of the file would fail on as an invalid index
+            check_header::<Bn254>(&index.header, options).map_err(|e| match e.kind() {
+                ErrorKind::ResourceLimit => e,
+                _ => Error::InvalidIndex,
+            })?;
+            let beyond_file = |s: &Section| {
+                s.position
+                    .checked_add(s.size)
+                    .is_none_or(|end| end > file_size)
+            };
+            if index.header.sections.iter().any(beyond_file) {
+                return Err(Error::InvalidIndex);
+            }
+            let (num_g1_points, num_g2_points) = index.header.num_points()?;
+            Ok(Self {
+                reader,
+                header: index.header.clone(),
+                options: options.clone(),
+                num_g1_points,
+                num_g2_points,
+                index: Some(index),
+                #[cfg(not(any(unix, windows)))]
+                seek_lock: Mutex::new(()),
+            })
+        })
+    }
+
+    pub fn header(&self) -> &PtauHeader {
+        &self.header
+    }
+
+    pub fn index(&self) -> Option<&PtauIndex> {
+        self.index.as_ref()
+    }
+
+    /// Read G1 tau power `index`, seeking directly to it.
+    pub fn g1_point(&mut self, index: usize) -> Result<G1Affine, Error> {
+        if index >= self.num_g1_points {
+            return Err(Error::InvalidNumG1Points);
+        }
+        let mut iter = self.point_iter(2, index + 1, G1_FORMAT);
+        iter.index = index;
+        iter.next_point()
+    }
+
+    /// Read G2 tau power `index`, seeking directly to it.
+    pub fn g2_point(&mut self, index: usize) -> Result<G2Affine, Error> {
+        if index >= self.num_g2_points {
+            return Err(Error::InvalidNumG2Points);
+        }
+        let mut iter = self.point_iter(3, index + 1, G2_FORMAT);
+        iter.index = index;
+        iter.next_point()
+    }
+
+    /// Read the G1 tau powers `start..end`, as `ptau::read_g1_range` does.
+    ///
+    /// The throttle of the options the file was opened with applies to
+    /// each call separately.
+    pub fn g1_range(&self, start: usize, end: usize) -> Result<Vec<G1Affine>, Error> {
+        self.read_range(2, start, end, G1_FORMAT, G1Affine::zero())
+    }
+
+    /// Like `g1_range`, for the G2 tau powers.
+    pub fn g2_range(&self, start: usize, end: usize) -> Result<Vec<G2Affine>, Error> {
+        self.read_range(3, start, end, G2_FORMAT, G2Affine::zero())
+    }
+
+    fn read_range<T: Copy + Send>(
+        &self,
+        section: u32,
+        start: usize,
+        end: usize,
+        format: PointFormat<T>,
+        placeholder: T,
+    ) -> Result<Vec<T>, Error> {
+        let reader = PositionedReader {
+            file: self.reader.get_ref().get_ref(),
+            position: 0,
+            #[cfg(not(any(unix, windows)))]
+            seek_lock: &self.seek_lock,
+        };
+        let mut reader = BufReader::new(throttled(reader, &self.options));
+        read_section_range_from(
+            &mut reader,
+            &self.header,
+            section,
+            start,
+            end,
+            &self.options,
+            format,
+            placeholder,
+        )
+    }
+
+    /// Check block `block` of a point section against the sidecar index the
+    /// file was opened with.
+    pub fn check_block(&mut self, section: u32, block: usize) -> Result<(), Error> {
+        let index = self.index.as_ref().ok_or(Error::MissingIndex)?;
+        let (position, size) = index
+            .block_range(section, block)
+            .ok_or_else(|| index.missing_block(section, block))?;
+        self.reader
+            .seek(SeekFrom::Start(position))
+            .map_err(io_error)?;
+        let mut buf = vec![0u8; size as usize];
+        read_bytes(&mut self.reader, &mut buf, section)?;
+        index.check_block(section, block, &buf)
+    }
+
+    /// Iterate over the G1 tau powers of section 2, decoding each point as
+    /// it is reached.
+    ///
+    /// Iteration stops after the first error.
+    pub fn g1_iter(&mut self) -> PointIter<'_, G1Affine> {
+        let num_points = self.num_g1_points;
+        self.point_iter(2, num_points, G1_FORMAT)
+    }
+
+    /// Like `g1_iter`, for the G2 tau powers of section 3.
+    pub fn g2_iter(&mut self) -> PointIter<'_, G2Affine> {
+        let num_points = self.num_g2_points;
+        self.point_iter(3, num_points, G2_FORMAT)
+    }
+
+    pub(crate) fn point_iter<T>(
+        &mut self,
+        section: u32,
+        num_points: usize,
+        format: PointFormat<T>,
+    ) -> PointIter<'_, T> {
+        PointIter {
+            reader: &mut self.reader,
+            header: &self.header,
+            options: &self.options,
+            section,
+            index: 0,
+            num_points,
+            buf: vec![0u8; format.size],
+            format,
+            positioned: false,
+            failed: false,
+        }
+    }
+}
+
+// Reads a shared file at its own position, without moving the position of
+// the file handle where the platform supports it
+struct PositionedReader<'a> {
+    file: &'a File,
+    position: u64,
+    #[cfg(not(any(unix, windows)))]
+    seek_lock: &'a Mutex<()>,
+}
+
+impl Read for PositionedReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        #[cfg(unix)]
+        let n = std::os::unix::fs::FileExt::read_at(self.file, buf, self.position)?;
+        #[cfg(windows)]
+        let n = std::os::windows::fs::FileExt::seek_read(self.file, buf, self.position)?;
+        // Elsewhere, readers take turns moving the position of the handle
+        #[cfg(not(any(unix, windows)))]
+        let n = {
+            let _guard = self
+                .seek_lock
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+            let mut file = self.file;
+            file.seek(SeekFrom::Start(self.position))?;
+            file.read(buf)?
+        };
+        self.position += n as u64;
+        Ok(n)
+    }
+}
+
+impl Seek for PositionedReader<'_> {
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        let position = match pos {
+            SeekFrom::Start(position) => Some(position),
+            SeekFrom::Current(offset) => self.position.checked_add_signed(offset),
+            SeekFrom::End(offset) => self.file.metadata()?.len().checked_add_signed(offset),
+        };
+        self.position = position.ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "seek to a negative or overflowing position",
+            )
+        })?;
+        Ok(self.position)
+    }
+}
+
+/// A lazy iterator over the points of a section, returned by
+/// `PtauFile::g1_iter` and `PtauFile::g2_iter`.
+pub struct PointIter<'a, T> {
+    reader: &'a mut Reader,
+    header: &'a PtauHeader,
+    options: &'a ReadOptions,
+    section: u32,
+    index: usize,
+    num_points: usize,
+    format: PointFormat<T>,
+    // Holds the encoding of the current point, reused across points
+    buf: Vec<u8>,
+    // Whether the reader is at point `index`
+    positioned: bool,
+    failed: bool,
+}
+
+impl<T> PointIter<'_, T> {
+    fn next_point(&mut self) -> Result<T, Error> {
+        if !self.positioned {
+            let size = self.format.size;
+            let position = points_position(self.header, self.section, self.num_points, size)?;
+            self.reader
+                .seek(SeekFrom::Start(position + (self.index * size) as u64))
+                .map_err(io_error)?;
+            self.positioned = true;
+        }
+        read_point(
+            self.reader,
+            &mut self.buf,
+            self.section,
+            self.index,
+            self.num_points,
+        )?;
+        (self.format.decode)(&self.buf, self.options)
+    }
+}
+
+impl<T> Iterator for PointIter<'_, T> {
+    type Item = Result<T, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed || self.index == self.num_points {
+            return None;
+        }
+        let point = self.next_point();
+        self.failed = point.is_err();
+        self.index += 1;
+        Some(point)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        if self.failed {
+            return (0, Some(0));
+        }
+        (0, Some(self.num_points - self.index))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PtauFile;
+    use crate::index::PtauIndex;
+    use crate::ptau::{self, Error, ReadOptions};
+
+    #[test]
+    pub fn test_point_iters() {
+        let (g1_points, g2_points) = ptau::read("8.ptau", 511, 256).unwrap();
+        let mut file = PtauFile::open("8.ptau", &ReadOptions::new()).unwrap();
+        assert_eq!(file.header().power, 8);
+
+        let g1: Vec<_> = file.g1_iter().collect::<Result<_, _>>().unwrap();
+        assert_eq!(g1, g1_points);
+        let g2: Vec<_> = file.g2_iter().collect::<Result<_, _>>().unwrap();
+        assert_eq!(g2, g2_points);
+
+        // Iterators restart from the first point, and can be consumed in
+        // chunks
+        let chunk: Vec<_> = file
+            .g1_iter()
+            .skip(10)
+            .take(5)
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(chunk, g1_points[10..15]);
+    }
+
+    #[test]
+    pub fn test_concurrent_ranges() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<PtauFile>();
+
+        let (g1_points, g2_points) = ptau::read("8.ptau", 511, 256).unwrap();
+        let file = PtauFile::open("8.ptau", &ReadOptions::new()).unwrap();
+        std::thread::scope(|s| {
+            let file = &file;
+            let g1: Vec<_> = (0..8)
+                .map(|i| s.spawn(move || file.g1_range(i * 60, i * 60 + 31)))
+                .collect();
+            let g2 = s.spawn(|| file.g2_range(200, 256));
+            for (i, handle) in g1.into_iter().enumerate() {
+                assert_eq!(
+                    handle.join().unwrap(),
+                    Ok(g1_points[i * 60..i * 60 + 31].to_vec())
+                );
+            }
+            assert_eq!(g2.join().unwrap(), Ok(g2_points[200..256].to_vec()));
+        });
+        assert_eq!(file.g1_range(500, 512), Err(Error::InvalidNumG1Points));
+    }
+
+    #[test]
+    pub fn test_open_with_index() {
+        let (g1_points, g2_points) = ptau::read("8.ptau", 511, 256).unwrap();
+        let options = ReadOptions::new();
+        let index = PtauIndex::generate("8.ptau", 64, &options).unwrap();
+        let mut file = PtauFile::open_with_index("8.ptau", index, &options).unwrap();
+        assert_eq!(file.header(), &ptau::read_header("8.ptau").unwrap());
+        assert_eq!(file.g1_point(300), Ok(g1_points[300]));
+        assert_eq!(file.g2_point(7), Ok(g2_points[7]));
+        assert_eq!(file.g2_point(256), Err(Error::InvalidNumG2Points));
+        assert_eq!(file.check_block(2, 7), Ok(()));
+        assert_eq!(file.check_block(3, 3), Ok(()));
+
+        let index = PtauIndex::generate("8.ptau", 64, &options).unwrap();
+        let mut file = PtauFile::open("8.ptau", &options).unwrap();
+        assert_eq!(file.g1_point(300), Ok(g1_points[300]));
+        assert_eq!(file.check_block(2, 0), Err(Error::MissingIndex));
+
+        let mut data = std::fs::read("8.ptau").unwrap();
+        data[80 + 130 * 64] ^= 1;
+        let path = std::env::temp_dir().join("ppot-rs-file-index.ptau");
+        std::fs::write(&path, data).unwrap();
+        let mut file = PtauFile::open_with_index(path.to_str().unwrap(), index, &options).unwrap();
+        assert_eq!(file.check_block(2, 1), Ok(()));
+        assert_eq!(
+            file.check_block(2, 2),
+            Err(Error::ChecksumMismatch { section: Some(2) })
+        );
+    }
+
+    #[test]
+    pub fn test_open_with_invalid_index() {
+        let options = ReadOptions::new();
+        let index = PtauIndex::generate("8.ptau", 64, &options).unwrap();
+        let open = |index: PtauIndex, options: &ReadOptions| {
+            PtauFile::open_with_index("8.ptau", index, options).map(|_| ())
+        };
+
+        // A header for another curve, as indexed from a BLS12-381 file
+        let mut other_curve = index.clone();
+        other_curve.header.n8 = 48;
+        other_curve.header.q.resize(48, 0);
+        assert_eq!(open(other_curve, &options), Err(Error::InvalidIndex));
+
+        // A power the sections are too small for
+        let mut larger = index.clone();
+        larger.header.power = 9;
+        assert_eq!(open(larger, &options), Err(Error::InvalidIndex));
+
+        // A section past the end of the file
+        let mut beyond = index.clone();
+        beyond.header.sections.last_mut().unwrap().position += 1;
+        assert_eq!(open(beyond, &options), Err(Error::InvalidIndex));
+
+        // The limits of the options still apply
+        let options = ReadOptions::new().max_power(7);
+        assert_eq!(open(index, &options), Err(Error::ExceededMaxPower));
+    }
+
+    #[test]
+    pub fn test_point_iters_invalid_point() {
+        let mut data = std::fs::read("8.ptau").unwrap();
+        // Corrupt the y coordinate of G1 point 2
+        data[80 + 2 * 64 + 32] ^= 1;
+        let path = std::env::temp_dir().join("ppot-rs-file-invalid.ptau");
+        std::fs::write(&path, data).unwrap();
+
+        let mut file = PtauFile::open(path.to_str().unwrap(), &ReadOptions::new()).unwrap();
+        let mut iter = file.g1_iter();
+        assert!(iter.next().unwrap().is_ok());
+        assert!(iter.next().unwrap().is_ok());
+        assert_eq!(iter.next(), Some(Err(Error::InvalidG1Point)));
+        assert_eq!(iter.next(), None);
+    }
+}
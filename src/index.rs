@@ -0,0 +1,291 @@
+//! A compact sidecar index of a ptau file: its header, its section table
+//! and a hash of each block of points, for fast random access and integrity
+//! checks of huge files through `PtauFile`.
+
+use crate::audit;
+use crate::curve::read_any_header_from;
+use crate::ptau::{
+    io_error, open, point_size, read_bytes, Error, PtauHeader, ReadOptions, Section,
+};
+use blake2::digest::consts::U32;
+use blake2::{Blake2b, Digest};
+use std::io::{BufReader, Seek, SeekFrom};
+
+const INDEX_MAGIC: &[u8; 8] = b"ptau-idx";
+const INDEX_VERSION: u32 = 1;
+
+/// The number of points per hashed block used by `write_index`.
+pub const DEFAULT_BLOCK_POINTS: u32 = 1 << 16;
+
+type Blake2b256 = Blake2b<U32>;
+
+/// The hashes of the blocks of `block_points` points of a section.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockHashes {
+    pub section: u32,
+    /// The BLAKE2b-256 hash of each block, the last one possibly shorter.
+    pub blake2b: Vec<[u8; 32]>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PtauIndex {
+    pub file_size: u64,
+    pub header: PtauHeader,
+    pub block_points: u32,
+    /// One entry per section made of points, in section table order.
+    pub blocks: Vec<BlockHashes>,
+}
+
+impl PtauIndex {
+    /// Index a ptau file over any supported curve, reading every point
+    /// section once.
+    pub fn generate(
+        ptau_file: &str,
+        block_points: u32,
+        options: &ReadOptions,
+    ) -> Result<Self, Error> {
+        if block_points == 0 {
+            return Err(Error::InvalidBlockPoints);
+        }
+        audit::record(
+            "index",
+            ptau_file,
+            &[("block_points", block_points as u64)],
+            || {
+                let mut f = BufReader::new(open(ptau_file, options)?);
+                let (_, header) = read_any_header_from(&mut f, options)?;
+                let file_size = f.seek(SeekFrom::End(0)).map_err(io_error)?;
+                let mut blocks = Vec::new();
+                for section in &header.sections {
+                    let point_size = match point_size(section.id, header.n8) {
+                        Some(point_size) => point_size,
+                        None => continue,
+                    };
+                    f.seek(SeekFrom::Start(section.position))
+                        .map_err(io_error)?;
+                    let block_size = block_points as u64 * point_size;
+                    let mut hashes = Vec::new();
+                    let mut buf = Vec::new();
+                    let mut remaining = section.size;
+                    while remaining > 0 {
+                        buf.resize(remaining.min(block_size) as usize, 0);
+                        read_bytes(&mut f, &mut buf, section.id)?;
+                        hashes.push(Blake2b256::digest(&buf).into());
+                        remaining -= buf.len() as u64;
+                    }
+                    blocks.push(BlockHashes {
+                        section: section.id,
+                        blake2b: hashes,
+                    });
+                }
+                Ok(Self {
+                    file_size,
+                    header,
+                    block_points,
+                    blocks,
+                })
+            },
+        )
+    }
+
+    /// The block hashes of a section, if it is made of points.
+    pub fn section_blocks(&self, section: u32) -> Option<&BlockHashes> {
+        self.blocks.iter().find(|b| b.section == section)
+    }
+
+    /// Check `bytes`, the contents of block `block` of `section`, against
+    /// the index.
+    pub fn check_block(&self, section: u32, block: usize, bytes: &[u8]) -> Result<(), Error> {
+        let expected = self
+            .section_blocks(section)
+            .and_then(|b| b.blake2b.get(block))
+            .ok_or_else(|| self.missing_block(section, block))?;
+        if Blake2b256::digest(bytes)[..] != expected[..] {
+            return Err(Error::ChecksumMismatch {
+                section: Some(section),
+            });
+        }
+        Ok(())
+    }
+
+    // The error for a block the index does not have
+    pub(crate) fn missing_block(&self, section: u32, block: usize) -> Error {
+        match self.section_blocks(section) {
+            Some(_) => Error::BlockOutOfRange { section, block },
+            None => Error::MissingSection(section),
+        }
+    }
+
+    /// The position and size in the file of block `block` of `section`.
+    pub fn block_range(&self, section: u32, block: usize) -> Option<(u64, u64)> {
+        let s = self.header.section(section)?;
+        let block_size = self.block_points as u64 * point_size(section, self.header.n8)?;
+        let start = block as u64 * block_size;
+        if start >= s.size {
+            return None;
+        }
+        Some((s.position + start, block_size.min(s.size - start)))
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let header = &self.header;
+        let mut out = Vec::new();
+        out.extend_from_slice(INDEX_MAGIC);
+        out.extend_from_slice(&INDEX_VERSION.to_le_bytes());
+        out.extend_from_slice(&self.file_size.to_le_bytes());
+        out.extend_from_slice(&header.n8.to_le_bytes());
+        out.extend_from_slice(&header.q);
+        out.extend_from_slice(&header.power.to_le_bytes());
+        out.extend_from_slice(&header.ceremony_power.to_le_bytes());
+        out.extend_from_slice(&(header.sections.len() as u32).to_le_bytes());
+        for s in &header.sections {
+            out.extend_from_slice(&s.id.to_le_bytes());
+            out.extend_from_slice(&s.position.to_le_bytes());
+            out.extend_from_slice(&s.size.to_le_bytes());
+        }
+        out.extend_from_slice(&self.block_points.to_le_bytes());
+        out.extend_from_slice(&(self.blocks.len() as u32).to_le_bytes());
+        for b in &self.blocks {
+            out.extend_from_slice(&b.section.to_le_bytes());
+            out.extend_from_slice(&(b.blake2b.len() as u32).to_le_bytes());
+            for hash in &b.blake2b {
+                out.extend_from_slice(hash);
+            }
+        }
+        out
+    }
+
+    pub fn parse(bytes: &[u8]) -> Result<Self, Error> {
+        let mut r = IndexReader(bytes);
+        if r.take(8)? != INDEX_MAGIC || r.u32()? != INDEX_VERSION {
+            return Err(Error::InvalidIndex);
+        }
+        let file_size = r.u64()?;
+        let n8 = r.u32()?;
+        let q = r.take(n8 as usize)?.to_vec();
+        let power = r.u32()?;
+        let ceremony_power = r.u32()?;
+        let num_sections = r.u32()?;
+        let mut sections = Vec::new();
+        for _ in 0..num_sections {
+            sections.push(Section {
+                id: r.u32()?,
+                position: r.u64()?,
+                size: r.u64()?,
+            });
+        }
+        let block_points = r.u32()?;
+        let num_blocks = r.u32()?;
+        let mut blocks = Vec::new();
+        for _ in 0..num_blocks {
+            let section = r.u32()?;
+            let num_hashes = r.u32()?;
+            let mut blake2b = Vec::new();
+            for _ in 0..num_hashes {
+                blake2b.push(r.take(32)?.try_into().unwrap());
+            }
+            blocks.push(BlockHashes { section, blake2b });
+        }
+        if !r.0.is_empty() || block_points == 0 {
+            return Err(Error::InvalidIndex);
+        }
+        Ok(Self {
+            file_size,
+            header: PtauHeader {
+                n8,
+                q,
+                power,
+                ceremony_power,
+                sections,
+            },
+            block_points,
+            blocks,
+        })
+    }
+}
+
+struct IndexReader<'a>(&'a [u8]);
+
+impl<'a> IndexReader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], Error> {
+        if self.0.len() < n {
+            return Err(Error::InvalidIndex);
+        }
+        let (head, tail) = self.0.split_at(n);
+        self.0 = tail;
+        Ok(head)
+    }
+
+    fn u32(&mut self) -> Result<u32, Error> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn u64(&mut self) -> Result<u64, Error> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+}
+
+/// The path of the index of a ptau file.
+pub fn index_path(ptau_file: &str) -> String {
+    format!("{}.idx", ptau_file)
+}
+
+/// Index a ptau file with blocks of `DEFAULT_BLOCK_POINTS` points and write
+/// the index next to the file.
+pub fn write_index(ptau_file: &str, options: &ReadOptions) -> Result<PtauIndex, Error> {
+    let index = PtauIndex::generate(ptau_file, DEFAULT_BLOCK_POINTS, options)?;
+    std::fs::write(index_path(ptau_file), index.to_bytes()).map_err(io_error)?;
+    Ok(index)
+}
+
+/// Read the index written next to a ptau file by `write_index`.
+pub fn read_index(ptau_file: &str) -> Result<PtauIndex, Error> {
+    let bytes = std::fs::read(index_path(ptau_file)).map_err(io_error)?;
+    PtauIndex::parse(&bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PtauIndex;
+    use crate::ptau::{Error, ReadOptions};
+
+    #[test]
+    pub fn test_index() {
+        let index = PtauIndex::generate("8.ptau", 100, &ReadOptions::new()).unwrap();
+        assert_eq!(index.file_size, std::fs::metadata("8.ptau").unwrap().len());
+        assert_eq!(index.header, crate::ptau::read_header("8.ptau").unwrap());
+        let ids: Vec<u32> = index.blocks.iter().map(|b| b.section).collect();
+        assert_eq!(ids, vec![2, 3, 4, 5, 6, 12, 13, 14, 15]);
+        // 511 G1 points in blocks of 100
+        assert_eq!(index.section_blocks(2).unwrap().blake2b.len(), 6);
+        assert_eq!(index.block_range(2, 5), Some((80 + 500 * 64, 11 * 64)));
+        assert_eq!(index.block_range(2, 6), None);
+        assert_eq!(
+            index.check_block(2, 6, &[]),
+            Err(Error::BlockOutOfRange {
+                section: 2,
+                block: 6
+            })
+        );
+        assert_eq!(index.check_block(7, 0, &[]), Err(Error::MissingSection(7)));
+        assert_eq!(
+            PtauIndex::generate("8.ptau", 0, &ReadOptions::new()),
+            Err(Error::InvalidBlockPoints)
+        );
+
+        let data = std::fs::read("8.ptau").unwrap();
+        let block = &data[80 + 100 * 64..80 + 200 * 64];
+        assert_eq!(index.check_block(2, 1, block), Ok(()));
+        assert_eq!(
+            index.check_block(2, 2, block),
+            Err(Error::ChecksumMismatch { section: Some(2) })
+        );
+
+        let bytes = index.to_bytes();
+        assert_eq!(PtauIndex::parse(&bytes), Ok(index));
+        assert_eq!(
+            PtauIndex::parse(&bytes[..bytes.len() - 1]),
+            Err(Error::InvalidIndex)
+        );
+    }
+}
@@ -0,0 +1,111 @@
+//! Checks of Lagrange-basis points, as found in prepared ptau files,
+//! against the monomial tau powers.
+
+use crate::ptau::Error;
+use ark_bn254::{Fr, G1Affine};
+use ark_ec::msm::VariableBaseMSM;
+use ark_ff::{batch_inversion, FftField, FftParameters, Field, One, PrimeField, UniformRand, Zero};
+use ark_std::rand::RngCore;
+
+/// Check that `lagrange` holds `[L_i(tau)]_1` for the domain of
+/// `lagrange.len()` points, given the tau powers `[tau^i]_1`.
+///
+/// Both sides are used to commit to the polynomial
+/// `f(X) = sum_j (rX)^j` for a random `r`, which takes one MSM each. A
+/// mismatch goes undetected with probability at most `n / |Fr|`.
+///
+/// `g1_powers` needs at least `n` points, so the largest domain of a
+/// prepared file cannot be checked against the file's own tau powers.
+pub fn check_lagrange_g1<R: RngCore>(
+    g1_powers: &[G1Affine],
+    lagrange: &[G1Affine],
+    rng: &mut R,
+) -> Result<(), Error> {
+    let n = lagrange.len();
+    if !n.is_power_of_two() || g1_powers.len() < n {
+        return Err(Error::InvalidNumG1Points);
+    }
+    let omega = Fr::get_root_of_unity(n).ok_or(Error::DomainTooLarge {
+        size: n,
+        max_size: 1 << <Fr as FftField>::FftParams::TWO_ADICITY,
+    })?;
+
+    // f(w^i) = ((r w^i)^n - 1) / (r w^i - 1) = (r^n - 1) / (r w^i - 1),
+    // which needs r^n != 1
+    let mut r = Fr::rand(rng);
+    while r.pow([n as u64]).is_one() {
+        r = Fr::rand(rng);
+    }
+
+    let mut monomial_scalars = Vec::with_capacity(n);
+    let mut power = Fr::one();
+    for _ in 0..n {
+        monomial_scalars.push(power.into_repr());
+        power *= r;
+    }
+
+    let numerator = r.pow([n as u64]) - Fr::one();
+    let mut evaluations = Vec::with_capacity(n);
+    let mut r_omega_i = r;
+    for _ in 0..n {
+        evaluations.push(r_omega_i - Fr::one());
+        r_omega_i *= omega;
+    }
+    batch_inversion(&mut evaluations);
+    let lagrange_scalars: Vec<_> = evaluations
+        .iter()
+        .map(|e| (*e * numerator).into_repr())
+        .collect();
+
+    let monomial =
+        VariableBaseMSM::multi_scalar_mul(&g1_powers[..monomial_scalars.len()], &monomial_scalars);
+    let evaluated = VariableBaseMSM::multi_scalar_mul(lagrange, &lagrange_scalars);
+    if (monomial - evaluated).is_zero() {
+        Ok(())
+    } else {
+        Err(Error::LagrangeMismatch)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::check_lagrange_g1;
+    use crate::ptau::{read, read_lagrange_g1, Error, ReadOptions};
+
+    #[test]
+    pub fn test_check_lagrange_g1() {
+        let rng = &mut ark_std::test_rng();
+        let options = ReadOptions::default();
+        let (g1_powers, _) = read("8.ptau", 511, 1).unwrap();
+        for power in [0, 1, 5, 8] {
+            let lagrange = read_lagrange_g1("8.ptau", power, &options).unwrap();
+            assert_eq!(lagrange.len(), 1 << power);
+            assert_eq!(check_lagrange_g1(&g1_powers, &lagrange, rng), Ok(()));
+        }
+
+        // The largest domain needs one more tau power than the file has
+        let lagrange = read_lagrange_g1("8.ptau", 9, &options).unwrap();
+        assert_eq!(
+            check_lagrange_g1(&g1_powers, &lagrange, rng),
+            Err(Error::InvalidNumG1Points)
+        );
+
+        let mut lagrange = read_lagrange_g1("8.ptau", 8, &options).unwrap();
+        lagrange.swap(3, 4);
+        assert_eq!(
+            check_lagrange_g1(&g1_powers, &lagrange, rng),
+            Err(Error::LagrangeMismatch)
+        );
+        assert_eq!(
+            check_lagrange_g1(&g1_powers[..254], &lagrange, rng),
+            Err(Error::InvalidNumG1Points)
+        );
+        assert_eq!(
+            read_lagrange_g1("8.ptau", 10, &options),
+            Err(Error::DomainTooLarge {
+                size: 1024,
+                max_size: 512
+            })
+        );
+    }
+}
@@ -1 +1,35 @@
+pub mod audit;
+pub mod backend;
+mod blake2b;
+#[cfg(feature = "bls12-381")]
+pub mod bls12_381;
+pub mod cache;
+mod chacha;
+pub mod checksum;
+pub mod compressed;
+pub mod contribute;
+pub mod contributions;
+pub mod curve;
+pub mod dev;
+pub mod diff;
+pub mod export;
+pub mod file;
+pub mod index;
+pub mod lagrange;
+pub mod lint;
+pub mod marker;
+#[cfg(feature = "mmap")]
+pub mod mmap;
+pub mod plan;
+pub mod prepare;
 pub mod ptau;
+pub mod remote;
+pub mod srs;
+pub mod throttle;
+pub mod truncate;
+pub mod verify;
+pub mod vk;
+pub mod writer;
+
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
@@ -0,0 +1,292 @@
+//! A linter for ptau files of unknown provenance, reporting anomalies that
+//! don't necessarily stop the file from being read.
+
+use crate::audit;
+use crate::ptau::{
+    check_section_sizes, decode_g1, decode_g2, io_error, point_size, read_header_from, read_point,
+    Error, InfinityPolicy, ReadOptions, FQ_MODULUS,
+};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::fs::File;
+use std::hash::{Hash, Hasher};
+use std::io::{BufReader, Read, Seek, SeekFrom};
+
+/// An anomaly found by `lint`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Finding {
+    /// The modulus in the header is a multiple of the base field modulus
+    /// rather than the modulus itself.
+    NonCanonicalModulus,
+    PowerExceedsCeremonyPower {
+        power: u32,
+        ceremony_power: u32,
+    },
+    /// The sections are not stored in increasing id order, as snarkjs
+    /// writes them.
+    UnusualSectionOrder(Vec<u32>),
+    SectionSizeMismatch {
+        section: u32,
+        expected: u64,
+        actual: u64,
+    },
+    /// Bytes after the last section.
+    TrailingBytes {
+        len: u64,
+        all_zero: bool,
+    },
+    NonCanonicalEncoding {
+        section: u32,
+        index: usize,
+    },
+    InvalidPoint {
+        section: u32,
+        index: usize,
+    },
+    PointAtInfinity {
+        section: u32,
+        index: usize,
+    },
+    /// Point `index` of a tau power section equals the earlier point
+    /// `first_index`, which happens when tau has a small multiplicative
+    /// order.
+    DuplicatePoint {
+        section: u32,
+        index: usize,
+        first_index: usize,
+    },
+}
+
+/// Scan a ptau file for anomalies. Errors are only returned for files
+/// whose header cannot be read at all, even leniently.
+///
+/// Every point is read, so this takes about as long as reading the whole
+/// file.
+pub fn lint(ptau_file: &str) -> Result<Vec<Finding>, Error> {
+    audit::record("lint", ptau_file, &[], || lint_file(ptau_file))
+}
+
+fn lint_file(ptau_file: &str) -> Result<Vec<Finding>, Error> {
+    let mut findings = Vec::new();
+    let mut f = BufReader::new(File::open(ptau_file).map_err(io_error)?);
+    let lenient = ReadOptions::new().max_power(u32::MAX).lenient(true);
+    let header = read_header_from(&mut f, &lenient)?;
+
+    if header.n8 != 32 || header.q.chunks(8).map(le_u64).ne(FQ_MODULUS.0) {
+        findings.push(Finding::NonCanonicalModulus);
+    }
+    if header.power > header.ceremony_power {
+        findings.push(Finding::PowerExceedsCeremonyPower {
+            power: header.power,
+            ceremony_power: header.ceremony_power,
+        });
+    }
+    let ids: Vec<u32> = header.sections.iter().map(|s| s.id).collect();
+    if ids.windows(2).any(|w| w[0] >= w[1]) {
+        findings.push(Finding::UnusualSectionOrder(ids));
+    }
+    if let Err(Error::SectionSizeMismatch {
+        section,
+        expected,
+        actual,
+    }) = check_section_sizes(&header)
+    {
+        findings.push(Finding::SectionSizeMismatch {
+            section,
+            expected,
+            actual,
+        });
+    }
+
+    let end = header
+        .sections
+        .iter()
+        .map(|s| s.position + s.size)
+        .max()
+        .unwrap_or(0);
+    f.seek(SeekFrom::Start(end)).map_err(io_error)?;
+    // The trailing bytes are streamed, as they may be most of the file
+    let (mut len, mut all_zero) = (0u64, true);
+    let mut buf = vec![0u8; 1 << 16];
+    loop {
+        let n = match f.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => n,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(io_error(e)),
+        };
+        len += n as u64;
+        all_zero &= buf[..n].iter().all(|b| *b == 0);
+    }
+    if len > 0 {
+        findings.push(Finding::TrailingBytes { len, all_zero });
+    }
+
+    // A second handle to confirm suspected duplicates without disturbing
+    // the scan
+    let mut confirm = File::open(ptau_file).map_err(io_error)?;
+    for section in &header.sections {
+        let point_size = match point_size(section.id, header.n8) {
+            Some(point_size) => point_size,
+            None => continue,
+        };
+        let num_points = (section.size / point_size) as usize;
+        // Only the plain tau powers are expected to be distinct
+        let mut seen = if section.id == 2 || section.id == 3 {
+            Some(HashMap::new())
+        } else {
+            None
+        };
+        f.seek(SeekFrom::Start(section.position))
+            .map_err(io_error)?;
+        let mut buf = vec![0u8; point_size as usize];
+        let mut first = vec![0u8; point_size as usize];
+        for index in 0..num_points {
+            read_point(&mut f, &mut buf, section.id, index, num_points)?;
+            if let Some(finding) = check_point(section.id, index, &buf) {
+                findings.push(finding);
+            }
+
+            if let Some(seen) = seen.as_mut() {
+                let mut hasher = DefaultHasher::new();
+                buf.hash(&mut hasher);
+                let first_index = *seen.entry(hasher.finish()).or_insert(index);
+                if first_index != index {
+                    confirm
+                        .seek(SeekFrom::Start(
+                            section.position + first_index as u64 * point_size,
+                        ))
+                        .map_err(io_error)?;
+                    read_point(
+                        &mut confirm,
+                        &mut first,
+                        section.id,
+                        first_index,
+                        num_points,
+                    )?;
+                    if first == buf {
+                        findings.push(Finding::DuplicatePoint {
+                            section: section.id,
+                            index,
+                            first_index,
+                        });
+                    }
+                }
+            }
+        }
+    }
+    Ok(findings)
+}
+
+fn check_point(section: u32, index: usize, buf: &[u8]) -> Option<Finding> {
+    if buf.iter().all(|b| *b == 0) {
+        return Some(Finding::PointAtInfinity { section, index });
+    }
+    let options = ReadOptions::new().infinity_policy(InfinityPolicy::Accept);
+    let r = if buf.len() == 64 {
+        decode_g1(buf, &options).map(|_| ())
+    } else {
+        decode_g2(buf, &options).map(|_| ())
+    };
+    match r {
+        Ok(()) => None,
+        Err(Error::NonCanonicalEncoding) => Some(Finding::NonCanonicalEncoding { section, index }),
+        Err(_) => Some(Finding::InvalidPoint { section, index }),
+    }
+}
+
+fn le_u64(bytes: &[u8]) -> u64 {
+    let mut buf = [0u8; 8];
+    buf[..bytes.len()].copy_from_slice(bytes);
+    u64::from_le_bytes(buf)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{lint, Finding};
+
+    fn write_ptau(name: &str, data: &[u8]) -> String {
+        let path = std::env::temp_dir().join(format!("ppot-rs-lint-{}.ptau", name));
+        std::fs::write(&path, data).unwrap();
+        path.to_str().unwrap().to_string()
+    }
+
+    #[test]
+    pub fn test_lint_clean() {
+        assert_eq!(lint("8.ptau").unwrap(), vec![]);
+    }
+
+    #[test]
+    pub fn test_lint() {
+        let mut data = std::fs::read("8.ptau").unwrap();
+        // Duplicate G1 point 1 at index 5
+        let point: Vec<u8> = data[80 + 64..80 + 128].to_vec();
+        data[80 + 5 * 64..80 + 6 * 64].copy_from_slice(&point);
+        // G1 point 7 at infinity
+        data[80 + 7 * 64..80 + 8 * 64].fill(0);
+        // A non-canonical x coordinate for G1 point 9
+        data[80 + 9 * 64..80 + 9 * 64 + 32].fill(0xff);
+        // G2 point 2 not on the curve
+        data[32796 + 2 * 128] ^= 1;
+        data.extend_from_slice(&[0; 16]);
+
+        let findings = lint(&write_ptau("anomalies", &data)).unwrap();
+        assert_eq!(
+            findings,
+            vec![
+                Finding::TrailingBytes {
+                    len: 16,
+                    all_zero: true
+                },
+                Finding::DuplicatePoint {
+                    section: 2,
+                    index: 5,
+                    first_index: 1
+                },
+                Finding::PointAtInfinity {
+                    section: 2,
+                    index: 7
+                },
+                Finding::NonCanonicalEncoding {
+                    section: 2,
+                    index: 9
+                },
+                Finding::InvalidPoint {
+                    section: 3,
+                    index: 2
+                },
+            ]
+        );
+    }
+
+    #[test]
+    pub fn test_lint_trailing_bytes() {
+        // More trailing bytes than are read at once, nonzero only at the end
+        let mut data = std::fs::read("8.ptau").unwrap();
+        data.resize(data.len() + 200_000, 0);
+        *data.last_mut().unwrap() = 1;
+        let findings = lint(&write_ptau("trailing", &data)).unwrap();
+        assert_eq!(
+            findings,
+            vec![Finding::TrailingBytes {
+                len: 200_000,
+                all_zero: false
+            }]
+        );
+    }
+
+    #[test]
+    pub fn test_lint_header() {
+        let mut data = std::fs::read("8.ptau").unwrap();
+        // Set the ceremony power (byte 64) below the power
+        data[64..68].copy_from_slice(&4u32.to_le_bytes());
+        let findings = lint(&write_ptau("header", &data)).unwrap();
+        assert_eq!(
+            findings,
+            vec![Finding::PowerExceedsCeremonyPower {
+                power: 8,
+                ceremony_power: 4
+            }]
+        );
+    }
+}
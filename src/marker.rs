@@ -0,0 +1,318 @@
+//! Markers recording that a ptau file passed `verify::verify_file`, so that
+//! later reads of the unchanged file can skip the point checks (see
+//! `ReadOptions::trust_verification_marker`).
+
+use crate::audit;
+use crate::checksum::{from_hex, to_hex, FileHasher};
+use crate::ptau::{io_error, open, read_header_from, Error, ReadOptions};
+use crate::verify::verify_points_from;
+use blake2::{Blake2b512, Digest};
+use std::fmt;
+use std::io::{BufReader, Read, Seek, SeekFrom};
+
+const MARKER_VERSION_LINE: &str = "ppot-rs-verified 1";
+
+/// The point checks a verification ran, or a read requests.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PointChecks {
+    pub on_curve: bool,
+    pub g1_subgroup: bool,
+    pub g2_subgroup: bool,
+}
+
+impl PointChecks {
+    /// The checks points are decoded with under `options`. Subgroup checks
+    /// imply the on-curve check.
+    pub fn of(options: &ReadOptions) -> Self {
+        Self {
+            on_curve: options.check_on_curve || options.check_subgroup || options.check_g1_subgroup,
+            g1_subgroup: options.check_g1_subgroup,
+            g2_subgroup: options.check_subgroup,
+        }
+    }
+
+    /// Whether every check of `other` is one of these.
+    pub fn covers(&self, other: &PointChecks) -> bool {
+        (self.on_curve || !other.on_curve)
+            && (self.g1_subgroup || !other.g1_subgroup)
+            && (self.g2_subgroup || !other.g2_subgroup)
+    }
+}
+
+/// A record that a file of the given size and hash passed the point
+/// checks of `verify::verify_file` with the version of this crate that
+/// wrote it.
+///
+/// Markers end with a digest of their contents, which detects corruption
+/// but is not a signature.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VerificationMarker {
+    pub crate_version: String,
+    pub file_size: u64,
+    pub file_blake2b: [u8; 64],
+    pub checks: PointChecks,
+}
+
+impl VerificationMarker {
+    /// Read the marker stored next to a ptau file, if there is one.
+    pub fn read(ptau_file: &str) -> Result<Option<Self>, Error> {
+        match std::fs::read_to_string(marker_path(ptau_file)) {
+            Ok(marker) => Self::parse(&marker).map(Some),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(io_error(e)),
+        }
+    }
+
+    /// Whether the marker was written by this version of the crate for the
+    /// current contents of `ptau_file`, which is hashed to find out, reading
+    /// it as set by `options`.
+    pub fn is_current(&self, ptau_file: &str, options: &ReadOptions) -> Result<bool, Error> {
+        if self.crate_version != env!("CARGO_PKG_VERSION") {
+            return Ok(false);
+        }
+        let (file_size, file_blake2b) = hash_file(ptau_file, options)?;
+        Ok(file_size == self.file_size && file_blake2b == self.file_blake2b)
+    }
+
+    pub fn parse(s: &str) -> Result<Self, Error> {
+        let (contents, digest) = s
+            .trim_end_matches('\n')
+            .rsplit_once('\n')
+            .ok_or(Error::InvalidMarker)?;
+        let digest = digest.strip_prefix("digest ").ok_or(Error::InvalidMarker)?;
+        if from_hex(digest) != Some(content_digest(&format!("{}\n", contents))) {
+            return Err(Error::InvalidMarker);
+        }
+
+        let lines: Vec<Vec<&str>> = contents
+            .lines()
+            .map(|line| line.split(' ').collect())
+            .collect();
+        let (crate_version, size, hash, checks) = match &lines[..] {
+            [version, crate_line, file_line, checks_line]
+                if version.join(" ") == MARKER_VERSION_LINE =>
+            {
+                match (&crate_line[..], &file_line[..], checks_line.split_first()) {
+                    (["crate", crate_version], ["file", size, hash], Some((&"checks", checks))) => {
+                        (crate_version, size, hash, checks)
+                    }
+                    _ => return Err(Error::InvalidMarker),
+                }
+            }
+            _ => return Err(Error::InvalidMarker),
+        };
+        let mut point_checks = PointChecks::default();
+        for check in checks {
+            match *check {
+                "on-curve" => point_checks.on_curve = true,
+                "g1-subgroup" => point_checks.g1_subgroup = true,
+                "g2-subgroup" => point_checks.g2_subgroup = true,
+                _ => return Err(Error::InvalidMarker),
+            }
+        }
+        Ok(Self {
+            crate_version: crate_version.to_string(),
+            file_size: size.parse().map_err(|_| Error::InvalidMarker)?,
+            file_blake2b: from_hex(hash).ok_or(Error::InvalidMarker)?,
+            checks: point_checks,
+        })
+    }
+
+    fn contents(&self) -> String {
+        let mut checks = String::from("checks");
+        for (enabled, name) in [
+            (self.checks.on_curve, " on-curve"),
+            (self.checks.g1_subgroup, " g1-subgroup"),
+            (self.checks.g2_subgroup, " g2-subgroup"),
+        ] {
+            if enabled {
+                checks.push_str(name);
+            }
+        }
+        format!(
+            "{}\ncrate {}\nfile {} {}\n{}\n",
+            MARKER_VERSION_LINE,
+            self.crate_version,
+            self.file_size,
+            to_hex(&self.file_blake2b),
+            checks
+        )
+    }
+}
+
+impl fmt::Display for VerificationMarker {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let contents = self.contents();
+        writeln!(
+            f,
+            "{}digest {}",
+            contents,
+            to_hex(&content_digest(&contents))
+        )
+    }
+}
+
+/// The path of the verification marker of a ptau file.
+pub fn marker_path(ptau_file: &str) -> String {
+    format!("{}.verified", ptau_file)
+}
+
+/// Verify a ptau file as by `verify::verify_file`, and write a marker next
+/// to it recording the checks it passed.
+///
+/// The recorded hash is computed over the same bytes the points are
+/// decoded from, in a single pass over the file, so it is the hash of the
+/// bytes that passed even if the file changes during verification.
+pub fn verify_and_mark(
+    ptau_file: &str,
+    options: &ReadOptions,
+) -> Result<VerificationMarker, Error> {
+    audit::record("verify_and_mark", ptau_file, &[], || {
+        let mut f = open(ptau_file, options)?;
+        let header = read_header_from(&mut f, options)?;
+        f.seek(SeekFrom::Start(0)).map_err(io_error)?;
+        let mut reader = FileHasher::new(BufReader::new(f));
+        verify_points_from(&mut reader, &header, options)?;
+        let (file_size, file_blake2b) = reader.finish().map_err(io_error)?;
+        let marker = VerificationMarker {
+            crate_version: env!("CARGO_PKG_VERSION").to_string(),
+            file_size,
+            file_blake2b,
+            checks: PointChecks::of(options),
+        };
+        std::fs::write(marker_path(ptau_file), marker.to_string()).map_err(io_error)?;
+        Ok(marker)
+    })
+}
+
+// The options to read `ptau_file` with: `options` without the point checks
+// if a current marker covers them. Markers that can't be read or are stale
+// are ignored, so that the points are checked.
+pub(crate) fn trusted_options(ptau_file: &str, options: &ReadOptions) -> ReadOptions {
+    let marker = match VerificationMarker::read(ptau_file) {
+        Ok(Some(marker)) => marker,
+        Ok(None) => return options.clone(),
+        Err(e) => {
+            log::warn!("ignoring the verification marker of {}: {}", ptau_file, e);
+            return options.clone();
+        }
+    };
+    if !marker.checks.covers(&PointChecks::of(options)) {
+        return options.clone();
+    }
+    match marker.is_current(ptau_file, options) {
+        Ok(true) => options
+            .clone()
+            .check_on_curve(false)
+            .check_subgroup(false)
+            .check_g1_subgroup(false),
+        Ok(false) => {
+            log::info!("the verification marker of {} is stale", ptau_file);
+            options.clone()
+        }
+        Err(_) => options.clone(),
+    }
+}
+
+fn content_digest(contents: &str) -> [u8; 64] {
+    Blake2b512::digest(contents.as_bytes()).into()
+}
+
+// The size and BLAKE2b-512 hash of a file
+pub(crate) fn hash_file(path: &str, options: &ReadOptions) -> Result<(u64, [u8; 64]), Error> {
+    let mut f = BufReader::new(open(path, options)?);
+    let mut hasher = Blake2b512::new();
+    let mut size = 0;
+    let mut buf = vec![0u8; 1 << 16];
+    loop {
+        let n = f.read(&mut buf).map_err(io_error)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+        size += n as u64;
+    }
+    Ok((size, hasher.finalize().into()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{hash_file, marker_path, verify_and_mark, PointChecks, VerificationMarker};
+    use crate::ptau::{read, read_with_options, Error, ReadOptions};
+
+    #[test]
+    pub fn test_verification_marker() {
+        let ptau_file = std::env::temp_dir().join("ppot-rs-marked.ptau");
+        let ptau_file = ptau_file.to_str().unwrap();
+        let mut data = std::fs::read("8.ptau").unwrap();
+        std::fs::write(ptau_file, &data).unwrap();
+
+        let options = ReadOptions::new().check_subgroup(true);
+        let marker = verify_and_mark(ptau_file, &options).unwrap();
+        assert_eq!(
+            marker.checks,
+            PointChecks {
+                on_curve: true,
+                g1_subgroup: false,
+                g2_subgroup: true,
+            }
+        );
+        assert_eq!(
+            VerificationMarker::read(ptau_file),
+            Ok(Some(marker.clone()))
+        );
+        assert_eq!(marker.is_current(ptau_file, &options), Ok(true));
+        let trusting = options.clone().trust_verification_marker(true);
+        assert_eq!(
+            read_with_options(ptau_file, 511, 256, &trusting).unwrap(),
+            read("8.ptau", 511, 256).unwrap()
+        );
+
+        // Move the second G1 point off the curve. The marker is stale, so
+        // the point is checked.
+        data[80 + 64] ^= 1;
+        std::fs::write(ptau_file, &data).unwrap();
+        assert_eq!(marker.is_current(ptau_file, &options), Ok(false));
+        assert_eq!(
+            read_with_options(ptau_file, 2, 1, &trusting),
+            Err(Error::InvalidG1Point)
+        );
+
+        // A current marker skips the checks, which is why markers must be
+        // kept where they can't be forged
+        let (file_size, file_blake2b) = hash_file(ptau_file, &options).unwrap();
+        let forged = VerificationMarker {
+            file_size,
+            file_blake2b,
+            ..marker.clone()
+        };
+        std::fs::write(marker_path(ptau_file), forged.to_string()).unwrap();
+        assert!(read_with_options(ptau_file, 2, 1, &trusting).is_ok());
+        assert_eq!(
+            read_with_options(ptau_file, 2, 1, &options),
+            Err(Error::InvalidG1Point)
+        );
+        // Unless it does not cover the checks requested
+        let g1_checks = ReadOptions::new()
+            .check_g1_subgroup(true)
+            .trust_verification_marker(true);
+        assert!(read_with_options(ptau_file, 2, 1, &g1_checks).is_err());
+
+        // Edited and malformed markers are rejected
+        let edited = forged.to_string().replace("g2-subgroup", "g1-subgroup");
+        assert_eq!(
+            VerificationMarker::parse(&edited),
+            Err(Error::InvalidMarker)
+        );
+        assert_eq!(
+            VerificationMarker::parse("ppot-rs-verified 1\n"),
+            Err(Error::InvalidMarker)
+        );
+        std::fs::write(marker_path(ptau_file), edited).unwrap();
+        assert!(read_with_options(ptau_file, 2, 1, &trusting).is_err());
+
+        std::fs::remove_file(marker_path(ptau_file)).unwrap();
+        assert_eq!(VerificationMarker::read(ptau_file), Ok(None));
+        std::fs::remove_file(ptau_file).unwrap();
+    }
+}
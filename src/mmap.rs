@@ -0,0 +1,154 @@
+//! Reading ptau files through a memory map, for large files.
+
+use crate::audit;
+use crate::curve::{self, g1_format, g2_format, Curve, TauPowers};
+use crate::ptau::{
+    check_point_counts, io_error, points_position, read_header_for, Error, PointFormat, PtauHeader,
+    ReadOptions,
+};
+use ark_bn254::{Bn254, G1Affine, G2Affine};
+use memmap2::Mmap;
+use rayon::prelude::*;
+use std::fs::File;
+use std::io::Cursor;
+
+/// Like `ptau::read_with_options`, mapping the file into memory and
+/// decoding points in parallel straight out of the mapped region.
+///
+/// The throttle of `options` does not apply, as pages are read in by the
+/// operating system. The file must not be modified while it is being read.
+pub fn read_mmap(
+    ptau_file: &str,
+    num_g1_points: usize,
+    num_g2_points: usize,
+    options: &ReadOptions,
+) -> Result<(Vec<G1Affine>, Vec<G2Affine>), Error> {
+    audit::record(
+        "read_mmap",
+        ptau_file,
+        &[
+            ("num_g1_points", num_g1_points as u64),
+            ("num_g2_points", num_g2_points as u64),
+        ],
+        || read_mapped::<Bn254>(ptau_file, num_g1_points, num_g2_points, options),
+    )
+}
+
+// `read_mmap` for a file over `C`, which `ptau` reads use with
+// `Backend::Mmap`
+pub(crate) fn read_mapped<C: Curve>(
+    ptau_file: &str,
+    num_g1_points: usize,
+    num_g2_points: usize,
+    options: &ReadOptions,
+) -> Result<TauPowers<C>, Error> {
+    let f = File::open(ptau_file).map_err(io_error)?;
+    // Safety: the file is only read, and callers are told not to modify it
+    // concurrently
+    let map = unsafe { Mmap::map(&f) }.map_err(io_error)?;
+    let header = read_header_for::<C, _>(&mut Cursor::new(&map[..]), options)?;
+    check_point_counts::<curve::G1Affine<C>, curve::G2Affine<C>>(
+        &header,
+        num_g1_points,
+        num_g2_points,
+        options,
+    )?;
+    options.install(|| {
+        let (g1_points, invalid_g1) =
+            decode_section(&map, &header, 2, num_g1_points, options, g1_format::<C>())?;
+        let (g2_points, invalid_g2) =
+            decode_section(&map, &header, 3, num_g2_points, options, g2_format::<C>())?;
+        if !invalid_g1.is_empty() || !invalid_g2.is_empty() {
+            return Err(Error::InvalidPoints {
+                g1: invalid_g1,
+                g2: invalid_g2,
+            });
+        }
+        Ok((g1_points, g2_points))
+    })?
+}
+
+// Decode the first `num_points` points of a section, returning the indices
+// of up to `options.max_invalid_points` invalid points if there are any
+fn decode_section<T: Send>(
+    map: &[u8],
+    header: &PtauHeader,
+    section: u32,
+    num_points: usize,
+    options: &ReadOptions,
+    format: PointFormat<T>,
+) -> Result<(Vec<T>, Vec<usize>), Error> {
+    let n = format.size;
+    let position = points_position(header, section, num_points, n)? as usize;
+    let expected_bytes = num_points * n;
+    let available = map.len() - position;
+    if available < expected_bytes {
+        return Err(Error::UnexpectedEof {
+            section,
+            point_index: Some(available / n),
+            expected_bytes: expected_bytes as u64,
+            got_bytes: available as u64,
+        });
+    }
+    let points = map[position..position + expected_bytes].par_chunks_exact(n);
+    let decode = |chunk: &[u8]| (format.decode)(chunk, options);
+
+    if options.max_invalid_points() == 0 {
+        return Ok((points.map(decode).collect::<Result<_, _>>()?, Vec::new()));
+    }
+    let results: Vec<Result<T, Error>> = points.map(decode).collect();
+    let invalid: Vec<usize> = results
+        .iter()
+        .enumerate()
+        .filter(|(_, r)| r.is_err())
+        .map(|(i, _)| i)
+        .take(options.max_invalid_points())
+        .collect();
+    if !invalid.is_empty() {
+        return Ok((Vec::new(), invalid));
+    }
+    Ok((results.into_iter().map(Result::unwrap).collect(), invalid))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::read_mmap;
+    use crate::ptau::{self, Error, ReadOptions};
+
+    #[test]
+    pub fn test_read_mmap() {
+        let options = ReadOptions::new();
+        assert_eq!(
+            read_mmap("8.ptau", 511, 256, &options).unwrap(),
+            ptau::read("8.ptau", 511, 256).unwrap()
+        );
+        assert_eq!(
+            read_mmap("8.ptau", 512, 1, &options),
+            Err(Error::InvalidNumG1Points)
+        );
+        let options = ReadOptions::new().threads(2);
+        assert_eq!(read_mmap("8.ptau", 3, 0, &options).unwrap().0.len(), 3);
+    }
+
+    #[test]
+    pub fn test_read_mmap_invalid_points() {
+        let mut data = std::fs::read("8.ptau").unwrap();
+        // Corrupt the y coordinates of G1 points 3 and 7
+        data[80 + 3 * 64 + 32] ^= 1;
+        data[80 + 7 * 64 + 32] ^= 1;
+        let path = std::env::temp_dir().join("ppot-rs-mmap-invalid.ptau");
+        std::fs::write(&path, data).unwrap();
+        let path = path.to_str().unwrap();
+
+        let options = ReadOptions::new();
+        assert_eq!(
+            read_mmap(path, 511, 256, &options),
+            Err(Error::InvalidG1Point)
+        );
+        let options = ReadOptions::new().collect_invalid_points(10);
+        assert_eq!(
+            read_mmap(path, 511, 256, &options),
+            ptau::read_with_options(path, 511, 256, &options)
+        );
+    }
+}
@@ -0,0 +1,177 @@
+//! Estimates of the cost of an operation on a ptau file, made from its
+//! header before committing to hours of work.
+
+use crate::ptau::{
+    io_error, open, point_size, read_header_from, read_with_options, Error, ReadOptions,
+};
+use crate::throttle::Throttle;
+use ark_bn254::{G1Affine, G2Affine};
+use blake2::{Blake2b512, Digest};
+use std::io::{BufReader, Read};
+use std::time::{Duration, Instant};
+
+/// An operation to plan.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operation {
+    /// `ptau::read` of the given numbers of G1 and G2 points.
+    Read {
+        num_g1_points: usize,
+        num_g2_points: usize,
+    },
+    /// `verify::verify_file`.
+    Verify,
+    /// `lint::lint`.
+    Lint,
+    /// `checksum::Manifest::generate`.
+    Manifest,
+}
+
+/// The estimated cost of an operation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Plan {
+    /// The peak memory used, in bytes.
+    pub est_memory: u64,
+    pub est_duration: Duration,
+    /// The number of bytes read from the file.
+    pub io_bytes: u64,
+}
+
+// The number of points timed to calibrate the duration estimate
+const CALIBRATION_G1_POINTS: usize = 256;
+const CALIBRATION_G2_POINTS: usize = 64;
+// The number of bytes read and hashed to calibrate the duration estimate of
+// plain reads
+const CALIBRATION_HASHED_BYTES: u64 = 4 << 20;
+
+// Memory used per point by the duplicate detection of `lint`, including
+// hash map overhead
+const LINT_BYTES_PER_POINT: u64 = 32;
+
+/// Estimate the cost of running `operation` on a ptau file with `options`.
+///
+/// The duration is extrapolated from the time taken to read and check a
+/// few points, or to read and hash the first few MiB of the file, then
+/// limited by the throttle in `options`. Those bytes are usually in the
+/// page cache, so the estimate does not account for slow storage.
+pub fn plan(operation: Operation, ptau_file: &str, options: &ReadOptions) -> Result<Plan, Error> {
+    let mut f = open(ptau_file, options)?;
+    let header = read_header_from(&mut f, options)?;
+    let file_size = std::fs::metadata(ptau_file).map_err(io_error)?.len();
+    let points_size: u64 = header
+        .sections
+        .iter()
+        .filter(|s| point_size(s.id, header.n8).is_some())
+        .map(|s| s.size)
+        .sum();
+    let tau_section_size = |id| header.section(id).map_or(0, |s| s.size);
+
+    // Operations that decode points are timed by decoding points, and the
+    // manifest by reading and hashing
+    let (io_bytes, decoded_bytes, hashed_bytes, est_memory) = match operation {
+        Operation::Read {
+            num_g1_points,
+            num_g2_points,
+        } => {
+            let io_bytes = num_g1_points as u64 * 64 + num_g2_points as u64 * 128;
+            let memory = num_g1_points as u64 * std::mem::size_of::<G1Affine>() as u64
+                + num_g2_points as u64 * std::mem::size_of::<G2Affine>() as u64;
+            (io_bytes, io_bytes, 0, memory)
+        }
+        Operation::Verify => (points_size, points_size, 0, 1 << 13),
+        Operation::Lint => {
+            let num_tau_points = tau_section_size(2) / 64 + tau_section_size(3) / 128;
+            (
+                file_size,
+                points_size,
+                0,
+                num_tau_points.max(1) * LINT_BYTES_PER_POINT,
+            )
+        }
+        Operation::Manifest => (file_size, 0, file_size, 1 << 16),
+    };
+
+    let (g1_points, g2_points) = header.num_points()?;
+    let (g1_points, g2_points) = (
+        g1_points.min(CALIBRATION_G1_POINTS),
+        g2_points.min(CALIBRATION_G2_POINTS),
+    );
+    let unthrottled = options.clone().throttle(Throttle::new());
+    let start = Instant::now();
+    read_with_options(ptau_file, g1_points, g2_points, &unthrottled)?;
+    let calibration_bytes = (g1_points * 64 + g2_points * 128) as f64;
+    let seconds_per_byte = start.elapsed().as_secs_f64() / calibration_bytes;
+
+    let mut seconds = decoded_bytes as f64 * seconds_per_byte;
+    if hashed_bytes > 0 {
+        seconds += hashed_bytes as f64 * seconds_per_hashed_byte(ptau_file, &unthrottled)?;
+    }
+    if let Some(rate) = options.bytes_per_second() {
+        seconds = seconds.max(io_bytes as f64 / rate as f64);
+    }
+    Ok(Plan {
+        est_memory,
+        est_duration: Duration::from_secs_f64(seconds),
+        io_bytes,
+    })
+}
+
+// The time taken per byte to read the start of a file and hash it twice, as
+// `Manifest::generate` hashes both the file and each of its sections
+fn seconds_per_hashed_byte(ptau_file: &str, options: &ReadOptions) -> Result<f64, Error> {
+    let mut f = BufReader::new(open(ptau_file, options)?).take(CALIBRATION_HASHED_BYTES);
+    let (mut file_hasher, mut section_hasher) = (Blake2b512::new(), Blake2b512::new());
+    let mut buf = vec![0u8; 1 << 16];
+    let mut hashed = 0;
+    let start = Instant::now();
+    loop {
+        let n = f.read(&mut buf).map_err(io_error)?;
+        if n == 0 {
+            break;
+        }
+        file_hasher.update(&buf[..n]);
+        section_hasher.update(&buf[..n]);
+        hashed += n;
+    }
+    std::hint::black_box((file_hasher.finalize(), section_hasher.finalize()));
+    Ok(start.elapsed().as_secs_f64() / hashed.max(1) as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{plan, Operation};
+    use crate::ptau::ReadOptions;
+    use crate::throttle::Throttle;
+    use std::time::Duration;
+
+    #[test]
+    pub fn test_plan() {
+        let options = ReadOptions::default();
+        let read = plan(
+            Operation::Read {
+                num_g1_points: 511,
+                num_g2_points: 256,
+            },
+            "8.ptau",
+            &options,
+        )
+        .unwrap();
+        assert_eq!(read.io_bytes, 511 * 64 + 256 * 128);
+        assert!(read.est_memory >= read.io_bytes);
+
+        let verify = plan(Operation::Verify, "8.ptau", &options).unwrap();
+        assert!(verify.io_bytes > read.io_bytes);
+
+        let manifest = plan(Operation::Manifest, "8.ptau", &options).unwrap();
+        assert_eq!(
+            manifest.io_bytes,
+            std::fs::metadata("8.ptau").unwrap().len()
+        );
+        // Hashing is timed even without a throttle
+        assert!(manifest.est_duration > Duration::ZERO);
+
+        // 1 KiB/s makes reading the whole file take minutes
+        let options = ReadOptions::new().throttle(Throttle::new().bytes_per_second(1 << 10));
+        let lint = plan(Operation::Lint, "8.ptau", &options);
+        assert!(lint.unwrap().est_duration > Duration::from_secs(60));
+    }
+}
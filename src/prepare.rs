@@ -0,0 +1,275 @@
+//! Computation of the Lagrange-basis sections 12 to 15 that snarkjs needs
+//! for phase 2, as `snarkjs powersoftau prepare phase2` does.
+
+use crate::audit;
+use crate::ptau::{
+    check_allocation, encode_g1, encode_g2, io_error, open, read_header_from,
+    read_section_range_from, Error, PointFormat, PtauHeader, ReadOptions, FR_TWO_ADICITY,
+    G1_FORMAT, G2_FORMAT,
+};
+use crate::truncate::{copy_section, write_section_header};
+use crate::writer::write_output;
+use ark_bn254::{Fr, G1Projective};
+use ark_ec::{AffineCurve, ProjectiveCurve};
+use ark_ff::Zero;
+use ark_poly::{EvaluationDomain, Radix2EvaluationDomain};
+use std::io::{BufReader, Read, Seek, Write};
+
+/// Write to `output` the prepared ptau file for `input`: its sections,
+/// followed by the Lagrange-basis points of sections 2 to 5 for each domain
+/// of size `2^0` to `2^power`, as sections 12 to 15.
+///
+/// Section 12 has an extra domain of size `2^(power + 1)`, one point larger
+/// than section 2, whose missing top tau power is taken to be zero as
+/// snarkjs does. Any Lagrange sections of `input` are replaced.
+///
+/// The largest domain is transformed in memory, so this needs room for
+/// `2^(power + 1)` projective G1 points. That domain must exist in the
+/// scalar field, so files of power 28, its two-adicity, cannot be
+/// prepared.
+///
+/// The output is written to a temporary file renamed to `output` once
+/// complete, so `output` may be `input`.
+pub fn prepare_phase2(input: &str, output: &str, options: &ReadOptions) -> Result<(), Error> {
+    audit::record("prepare_phase2", input, &[], || {
+        let mut f = BufReader::new(open(input, options)?);
+        let header = read_header_from(&mut f, options)?;
+        for id in 2..=5 {
+            header.section(id).ok_or(Error::MissingSection(id))?;
+        }
+        if header.power + 1 > FR_TWO_ADICITY {
+            return Err(Error::DomainTooLarge {
+                size: 2 << header.power,
+                max_size: 1 << FR_TWO_ADICITY,
+            });
+        }
+        check_allocation::<G1Projective>(2 << header.power, options)?;
+
+        write_output(output, |out, _| {
+            write_prepared(&mut f, &header, out, options)
+        })
+    })
+}
+
+fn write_prepared<R: Read + Seek, W: Write>(
+    reader: &mut R,
+    header: &PtauHeader,
+    out: &mut W,
+    options: &ReadOptions,
+) -> Result<(), Error> {
+    let kept: Vec<_> = header
+        .sections
+        .iter()
+        .filter(|section| !matches!(section.id, 12..=15))
+        .collect();
+    out.write_all(b"ptau").map_err(io_error)?;
+    out.write_all(&1u32.to_le_bytes()).map_err(io_error)?;
+    out.write_all(&(kept.len() as u32 + 4).to_le_bytes())
+        .map_err(io_error)?;
+    for section in kept {
+        write_section_header(out, section.id, section.size).map_err(io_error)?;
+        copy_section(reader, section, section.size, out)?;
+    }
+
+    let mut lagrange = Lagrange {
+        reader,
+        header,
+        out,
+        options,
+    };
+    lagrange.write_section(2, 12, G1_FORMAT, encode_g1)?;
+    lagrange.write_section(3, 13, G2_FORMAT, encode_g2)?;
+    lagrange.write_section(4, 14, G1_FORMAT, encode_g1)?;
+    lagrange.write_section(5, 15, G1_FORMAT, encode_g1)
+}
+
+struct Lagrange<'a, R, W> {
+    reader: &'a mut R,
+    header: &'a PtauHeader,
+    out: &'a mut W,
+    options: &'a ReadOptions,
+}
+
+impl<R: Read + Seek, W: Write> Lagrange<'_, R, W> {
+    // Write section `to`, the Lagrange-basis points of the domains of
+    // section `from`
+    fn write_section<A: AffineCurve<ScalarField = Fr>>(
+        &mut self,
+        from: u32,
+        to: u32,
+        format: PointFormat<A>,
+        encode: fn(&A, &mut Vec<u8>),
+    ) -> Result<(), Error> {
+        let power = self.header.power;
+        let max_power = if to == 12 { power + 1 } else { power };
+        // The domains of size 2^0 to 2^max_power
+        let num_points = (2u64 << max_power) - 1;
+        write_section_header(self.out, to, num_points * format.size as u64).map_err(io_error)?;
+
+        let mut buf = Vec::new();
+        for p in 0..=max_power {
+            // Only the extra domain of section 12 is larger than the section
+            // it comes from
+            let size = 1usize << p;
+            let available = if p > power { size - 1 } else { size };
+            let points = read_section_range_from(
+                self.reader,
+                self.header,
+                from,
+                0,
+                available,
+                self.options,
+                format,
+                A::zero(),
+            )?;
+            let mut evals: Vec<A::Projective> =
+                points.iter().map(|p| p.into_projective()).collect();
+            evals.resize(size, A::Projective::zero());
+            // The coefficients of the polynomial that evaluates to the tau
+            // powers on the domain are the Lagrange basis evaluated at tau
+            let domain = Radix2EvaluationDomain::<Fr>::new(size).ok_or(Error::DomainTooLarge {
+                size,
+                max_size: 1 << FR_TWO_ADICITY,
+            })?;
+            domain.ifft_in_place(&mut evals);
+
+            buf.clear();
+            for point in A::Projective::batch_normalization_into_affine(&evals) {
+                encode(&point, &mut buf);
+            }
+            self.out.write_all(&buf).map_err(io_error)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::prepare_phase2;
+    use crate::dev::generate_with_alpha_beta;
+    use crate::lagrange::check_lagrange_g1;
+    use crate::ptau::{
+        read, read_lagrange_alpha_g1, read_lagrange_beta_g1, read_lagrange_g1, read_lagrange_g2,
+        Error, ReadOptions,
+    };
+    use crate::testing::generate_from_secrets;
+    use crate::truncate::truncate;
+    use crate::verify::verify_file;
+    use ark_bn254::Fr;
+
+    #[test]
+    pub fn test_prepare_phase2() {
+        let options = ReadOptions::default();
+        let output = std::env::temp_dir().join("ppot-rs-prepared.ptau");
+        let output = output.to_str().unwrap();
+
+        // The same as the sections snarkjs computed for 8.ptau, except the
+        // extra domain of section 12, which used the real top tau power
+        prepare_phase2("8.ptau", output, &options).unwrap();
+        assert_eq!(verify_file(output, &options), Ok(()));
+        for power in 0..=8 {
+            assert_eq!(
+                read_lagrange_g1(output, power, &options),
+                read_lagrange_g1("8.ptau", power, &options)
+            );
+            assert_eq!(
+                read_lagrange_g2(output, power, &options),
+                read_lagrange_g2("8.ptau", power, &options)
+            );
+            assert_eq!(
+                read_lagrange_alpha_g1(output, power, &options),
+                read_lagrange_alpha_g1("8.ptau", power, &options)
+            );
+            assert_eq!(
+                read_lagrange_beta_g1(output, power, &options),
+                read_lagrange_beta_g1("8.ptau", power, &options)
+            );
+        }
+        assert_eq!(read_lagrange_g1(output, 9, &options).unwrap().len(), 512);
+
+        // A file without Lagrange sections, from known secrets
+        let (tau, alpha, beta) = (Fr::from(7u64), Fr::from(11u64), Fr::from(13u64));
+        let input = std::env::temp_dir().join("ppot-rs-unprepared.ptau");
+        let input = input.to_str().unwrap();
+        generate_with_alpha_beta(tau, alpha, beta, 3)
+            .write_file(input)
+            .unwrap();
+        assert_eq!(
+            read_lagrange_g1(input, 0, &options),
+            Err(Error::MissingSection(12))
+        );
+        prepare_phase2(input, output, &options).unwrap();
+        let expected = std::env::temp_dir().join("ppot-rs-expected.ptau");
+        let expected = expected.to_str().unwrap();
+        std::fs::write(expected, generate_from_secrets(tau, alpha, beta, 3)).unwrap();
+        for power in 0..=3 {
+            assert_eq!(
+                read_lagrange_g1(output, power, &options),
+                read_lagrange_g1(expected, power, &options)
+            );
+            assert_eq!(
+                read_lagrange_g2(output, power, &options),
+                read_lagrange_g2(expected, power, &options)
+            );
+            assert_eq!(
+                read_lagrange_alpha_g1(output, power, &options),
+                read_lagrange_alpha_g1(expected, power, &options)
+            );
+        }
+        let (g1_points, _) = read(output, 15, 8).unwrap();
+        let lagrange = read_lagrange_g1(output, 3, &options).unwrap();
+        let rng = &mut ark_std::test_rng();
+        assert_eq!(check_lagrange_g1(&g1_points, &lagrange, rng), Ok(()));
+
+        // Preparing a truncated file recomputes the sections it kept
+        truncate("8.ptau", input, 4, &options).unwrap();
+        prepare_phase2(input, output, &options).unwrap();
+        assert_eq!(
+            read_lagrange_beta_g1(output, 4, &options),
+            read_lagrange_beta_g1("8.ptau", 4, &options)
+        );
+
+        for file in [input, output, expected] {
+            std::fs::remove_file(file).unwrap();
+        }
+    }
+
+    #[test]
+    pub fn test_prepare_phase2_in_place() {
+        let options = ReadOptions::default();
+        let path = std::env::temp_dir().join("ppot-rs-prepare-in-place.ptau");
+        let path = path.to_str().unwrap();
+        truncate("8.ptau", path, 4, &options).unwrap();
+        prepare_phase2(path, path, &options).unwrap();
+        assert_eq!(verify_file(path, &options), Ok(()));
+        assert_eq!(
+            read_lagrange_beta_g1(path, 4, &options),
+            read_lagrange_beta_g1("8.ptau", 4, &options)
+        );
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    pub fn test_prepare_phase2_exceeds_two_adicity() {
+        // 8.ptau claiming power 28, whose section 12 would need a domain
+        // of 2^29 points
+        let mut data = std::fs::read("8.ptau").unwrap();
+        data[60..64].copy_from_slice(&28u32.to_le_bytes());
+        let dir = std::env::temp_dir();
+        let input = dir.join("ppot-rs-prepare-power-28.ptau");
+        let output = dir.join("ppot-rs-prepare-power-28-output.ptau");
+        let (input, output) = (input.to_str().unwrap(), output.to_str().unwrap());
+        std::fs::write(input, data).unwrap();
+        let _ = std::fs::remove_file(output);
+
+        let options = ReadOptions::new().lenient(true);
+        assert_eq!(
+            prepare_phase2(input, output, &options),
+            Err(Error::DomainTooLarge {
+                size: 1 << 29,
+                max_size: 1 << 28
+            })
+        );
+        assert!(!std::path::Path::new(output).exists());
+    }
+}
@@ -1,22 +1,858 @@
-use ark_bn254::{Fq, Fq2, G1Affine, G2Affine};
+use crate::audit;
+use crate::backend::{self, Backend};
+use crate::curve::{self, Curve};
+use crate::throttle::{Throttle, ThrottledReader};
+use ark_bn254::{Bn254, Fq, FrParameters, G1Affine, G2Affine};
 use ark_ff::biginteger::BigInteger256;
-use ark_ff::fields::PrimeField;
-use ark_ff::FromBytes;
-use byteorder::{LittleEndian, ReadBytesExt};
-use std::collections::BTreeMap;
+use ark_ff::fields::{FftParameters, FpParameters, PrimeField};
+use ark_ff::Zero;
 use std::fs::File;
-use std::io::{Read, Seek, SeekFrom};
+use std::io::{BufReader, Cursor, Read, Seek, SeekFrom};
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug)]
 pub enum Error {
     InvalidMagicString,
     InvalidVersion,
     InvalidPrimeOrder,
+    /// No longer returned: any number of sections is accepted, and a
+    /// section table that runs past the end of the file is reported as
+    /// `UnexpectedEof`.
     InvalidNumSections,
     InvalidNumG1Points,
     InvalidNumG2Points,
     InvalidG1Point,
     InvalidG2Point,
+    ExceededMaxPower,
+    ExceededMaxPoints,
+    ExceededMaxAllocation,
+    NonCanonicalEncoding,
+    InvalidPoints {
+        g1: Vec<usize>,
+        g2: Vec<usize>,
+    },
+    NegativeSectionSize {
+        section: u32,
+    },
+    SectionSizeMismatch {
+        section: u32,
+        expected: u64,
+        actual: u64,
+    },
+    MissingSection(u32),
+    /// The file ended early. `section` is the id of the truncated section
+    /// (0 for the section table) and `point_index` the index of the first
+    /// incomplete point, for sections made of points. `expected_bytes` is
+    /// the size of the section (or of the requested points or header field)
+    /// and `got_bytes` the number of those bytes actually present.
+    UnexpectedEof {
+        section: u32,
+        point_index: Option<usize>,
+        expected_bytes: u64,
+        got_bytes: u64,
+    },
+    /// An I/O operation on the file failed.
+    Io(std::io::Error),
+    /// Reading `section` (0 for the section table) failed, at byte `offset`
+    /// of the file if it could be determined.
+    SectionIo {
+        section: u32,
+        offset: Option<u64>,
+        source: std::io::Error,
+    },
+    PowerTooLarge,
+    /// The file claims more tau powers than the ceremony it comes from.
+    PowerExceedsCeremonyPower {
+        power: u32,
+        ceremony_power: u32,
+    },
+    PointAtInfinity,
+    /// A G1 point is on the curve but not in the prime order subgroup.
+    G1NotInSubgroup,
+    /// A G2 point is on the curve but not in the prime order subgroup.
+    G2NotInSubgroup,
+    /// The section does not hold curve points.
+    NotAPointSection(u32),
+    /// The file has more tau powers than the largest FFT domain of the
+    /// scalar field, which has `2^two_adicity` points.
+    PowerExceedsTwoAdicity {
+        power: u32,
+        two_adicity: u32,
+    },
+    /// No radix-2 evaluation domain of at least `size` points can be used
+    /// with this file; the largest has `max_size` points.
+    DomainTooLarge {
+        size: usize,
+        max_size: usize,
+    },
+    /// Lagrange-basis points are not the evaluations of the tau powers they
+    /// were checked against.
+    LagrangeMismatch,
+    /// A section, or the whole file if `section` is `None`, does not match
+    /// its checksum manifest.
+    ChecksumMismatch {
+        section: Option<u32>,
+    },
+    InvalidManifest,
+    /// A verification marker is malformed or does not match its digest.
+    InvalidMarker,
+    /// Points that should be powers of the same tau are not.
+    PairingCheckFailed,
+    /// A sidecar index is malformed or does not describe the file.
+    InvalidIndex,
+    /// The operation needs a file opened with a sidecar index.
+    MissingIndex,
+    /// The contributions section is malformed.
+    InvalidContributions,
+    /// The public key of the contribution at `index` does not prove
+    /// knowledge of its secrets.
+    InvalidContributionKey {
+        index: usize,
+    },
+    /// The second of two files compared by `diff::diff` does not extend
+    /// the contributions of the first by exactly one.
+    NotConsecutive,
+    /// The rayon pool requested with `ReadOptions::threads` could not be
+    /// created.
+    ThreadPoolBuildFailed,
+    /// Points up to index `requested` were to be read from `section`, whose
+    /// recorded size only holds `available` points. Only lenient reads get
+    /// this far, since the section sizes of other reads are checked against
+    /// the power.
+    NotEnoughPoints {
+        section: u32,
+        requested: usize,
+        available: usize,
+    },
+    /// Contributions can only be made to files at their ceremony power, not
+    /// to files truncated from them.
+    BelowCeremonyPower {
+        power: u32,
+        ceremony_power: u32,
+    },
+    /// The hash of a random beacon is empty or longer than 255 bytes, or its
+    /// number of iterations is not a power of two from `2^10` to `2^63`.
+    InvalidBeacon,
+    /// Blocks of an index must hold at least one point.
+    InvalidBlockPoints,
+    /// Chunks of a `compressed::CompressedSrs` must hold at least one
+    /// point.
+    InvalidChunkSize,
+    /// `section` of an index has fewer blocks than `block + 1`.
+    BlockOutOfRange {
+        section: u32,
+        block: usize,
+    },
+    /// Contributions need the `[tau]_1` and `[tau]_2` of a file of power at
+    /// least 1.
+    ZeroPower,
+}
+
+// I/O errors are compared by kind, as `std::io::Error` is not comparable
+impl PartialEq for Error {
+    fn eq(&self, other: &Self) -> bool {
+        use Error::*;
+        match (self, other) {
+            (Io(a), Io(b)) => a.kind() == b.kind(),
+            (
+                SectionIo {
+                    section: s1,
+                    offset: o1,
+                    source: e1,
+                },
+                SectionIo {
+                    section: s2,
+                    offset: o2,
+                    source: e2,
+                },
+            ) => s1 == s2 && o1 == o2 && e1.kind() == e2.kind(),
+            (InvalidPoints { g1: a1, g2: a2 }, InvalidPoints { g1: b1, g2: b2 }) => {
+                a1 == b1 && a2 == b2
+            }
+            (NegativeSectionSize { section: a }, NegativeSectionSize { section: b }) => a == b,
+            (
+                SectionSizeMismatch {
+                    section: s1,
+                    expected: e1,
+                    actual: a1,
+                },
+                SectionSizeMismatch {
+                    section: s2,
+                    expected: e2,
+                    actual: a2,
+                },
+            ) => s1 == s2 && e1 == e2 && a1 == a2,
+            (MissingSection(a), MissingSection(b)) => a == b,
+            (
+                UnexpectedEof {
+                    section: s1,
+                    point_index: i1,
+                    expected_bytes: e1,
+                    got_bytes: g1,
+                },
+                UnexpectedEof {
+                    section: s2,
+                    point_index: i2,
+                    expected_bytes: e2,
+                    got_bytes: g2,
+                },
+            ) => s1 == s2 && i1 == i2 && e1 == e2 && g1 == g2,
+            (
+                PowerExceedsCeremonyPower {
+                    power: p1,
+                    ceremony_power: c1,
+                },
+                PowerExceedsCeremonyPower {
+                    power: p2,
+                    ceremony_power: c2,
+                },
+            ) => p1 == p2 && c1 == c2,
+            (NotAPointSection(a), NotAPointSection(b)) => a == b,
+            (
+                PowerExceedsTwoAdicity {
+                    power: p1,
+                    two_adicity: t1,
+                },
+                PowerExceedsTwoAdicity {
+                    power: p2,
+                    two_adicity: t2,
+                },
+            ) => p1 == p2 && t1 == t2,
+            (
+                DomainTooLarge {
+                    size: s1,
+                    max_size: m1,
+                },
+                DomainTooLarge {
+                    size: s2,
+                    max_size: m2,
+                },
+            ) => s1 == s2 && m1 == m2,
+            (ChecksumMismatch { section: a }, ChecksumMismatch { section: b }) => a == b,
+            (InvalidContributionKey { index: a }, InvalidContributionKey { index: b }) => a == b,
+            (
+                NotEnoughPoints {
+                    section: s1,
+                    requested: r1,
+                    available: a1,
+                },
+                NotEnoughPoints {
+                    section: s2,
+                    requested: r2,
+                    available: a2,
+                },
+            ) => s1 == s2 && r1 == r2 && a1 == a2,
+            (
+                BelowCeremonyPower {
+                    power: p1,
+                    ceremony_power: c1,
+                },
+                BelowCeremonyPower {
+                    power: p2,
+                    ceremony_power: c2,
+                },
+            ) => p1 == p2 && c1 == c2,
+            (
+                BlockOutOfRange {
+                    section: s1,
+                    block: b1,
+                },
+                BlockOutOfRange {
+                    section: s2,
+                    block: b2,
+                },
+            ) => s1 == s2 && b1 == b2,
+            // The remaining variants carry no data
+            (a, b) => {
+                std::mem::discriminant(a) == std::mem::discriminant(b)
+                    && !matches!(
+                        a,
+                        Io(_)
+                            | SectionIo { .. }
+                            | InvalidPoints { .. }
+                            | NegativeSectionSize { .. }
+                            | SectionSizeMismatch { .. }
+                            | MissingSection(_)
+                            | UnexpectedEof { .. }
+                            | PowerExceedsCeremonyPower { .. }
+                            | NotAPointSection(_)
+                            | PowerExceedsTwoAdicity { .. }
+                            | DomainTooLarge { .. }
+                            | ChecksumMismatch { .. }
+                            | InvalidContributionKey { .. }
+                            | NotEnoughPoints { .. }
+                            | BelowCeremonyPower { .. }
+                            | BlockOutOfRange { .. }
+                    )
+            }
+        }
+    }
+}
+
+impl Eq for Error {}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use Error::*;
+        match self {
+            InvalidMagicString => write!(f, "not a ptau file: invalid magic string"),
+            InvalidVersion => write!(f, "unsupported ptau version"),
+            InvalidPrimeOrder => write!(f, "unsupported base field"),
+            InvalidNumSections => write!(f, "unexpected number of sections"),
+            InvalidNumG1Points => write!(f, "more G1 points requested than the file holds"),
+            InvalidNumG2Points => write!(f, "more G2 points requested than the file holds"),
+            InvalidG1Point => write!(f, "invalid G1 point"),
+            InvalidG2Point => write!(f, "invalid G2 point"),
+            ExceededMaxPower => write!(f, "file power exceeds the configured maximum"),
+            ExceededMaxPoints => write!(f, "requested points exceed the configured maximum"),
+            ExceededMaxAllocation => write!(f, "read would exceed the configured allocation limit"),
+            NonCanonicalEncoding => write!(f, "field element is not canonically encoded"),
+            InvalidPoints { g1, g2 } => write!(
+                f,
+                "{} invalid G1 and {} invalid G2 points",
+                g1.len(),
+                g2.len()
+            ),
+            NegativeSectionSize { section } => {
+                write!(f, "section {} has a negative size", section)
+            }
+            SectionSizeMismatch {
+                section,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "section {} is {} bytes, expected {}",
+                section, actual, expected
+            ),
+            MissingSection(section) => write!(f, "missing section {}", section),
+            UnexpectedEof {
+                section,
+                point_index,
+                expected_bytes,
+                got_bytes,
+            } => {
+                write!(
+                    f,
+                    "unexpected end of file in section {}: got {} of {} bytes",
+                    section, got_bytes, expected_bytes
+                )?;
+                if let Some(index) = point_index {
+                    write!(f, " (point {})", index)?;
+                }
+                Ok(())
+            }
+            Io(e) => write!(f, "I/O error: {}", e),
+            SectionIo {
+                section,
+                offset,
+                source,
+            } => {
+                write!(f, "I/O error reading section {}", section)?;
+                if let Some(offset) = offset {
+                    write!(f, " at offset {}", offset)?;
+                }
+                write!(f, ": {}", source)
+            }
+            PowerTooLarge => write!(f, "power is too large"),
+            PowerExceedsCeremonyPower {
+                power,
+                ceremony_power,
+            } => write!(
+                f,
+                "power {} exceeds the ceremony power {}",
+                power, ceremony_power
+            ),
+            PointAtInfinity => write!(f, "unexpected point at infinity"),
+            G1NotInSubgroup => write!(f, "G1 point is not in the prime order subgroup"),
+            G2NotInSubgroup => write!(f, "G2 point is not in the prime order subgroup"),
+            NotAPointSection(section) => write!(f, "section {} does not hold points", section),
+            PowerExceedsTwoAdicity { power, two_adicity } => write!(
+                f,
+                "power {} exceeds the scalar field two-adicity {}",
+                power, two_adicity
+            ),
+            DomainTooLarge { size, max_size } => write!(
+                f,
+                "no domain of {} points is available, the largest has {}",
+                size, max_size
+            ),
+            LagrangeMismatch => write!(f, "Lagrange basis does not match the tau powers"),
+            ChecksumMismatch {
+                section: Some(section),
+            } => {
+                write!(f, "section {} does not match its checksum", section)
+            }
+            ChecksumMismatch { section: None } => write!(f, "file does not match its checksum"),
+            InvalidManifest => write!(f, "invalid checksum manifest"),
+            InvalidMarker => write!(f, "invalid verification marker"),
+            PairingCheckFailed => write!(f, "pairing check failed"),
+            InvalidIndex => write!(f, "invalid index file"),
+            MissingIndex => write!(f, "file was opened without an index"),
+            InvalidContributions => write!(f, "invalid contributions section"),
+            InvalidContributionKey { index } => {
+                write!(f, "invalid public key in contribution {}", index)
+            }
+            NotConsecutive => write!(f, "files are not consecutive contributions"),
+            ThreadPoolBuildFailed => write!(f, "failed to build the thread pool"),
+            NotEnoughPoints {
+                section,
+                requested,
+                available,
+            } => write!(
+                f,
+                "{} points requested from section {}, which holds {}",
+                requested, section, available
+            ),
+            BelowCeremonyPower {
+                power,
+                ceremony_power,
+            } => write!(
+                f,
+                "power {} is below the ceremony power {}: cannot contribute to a truncated file",
+                power, ceremony_power
+            ),
+            InvalidBeacon => write!(f, "invalid beacon hash or number of iterations"),
+            InvalidBlockPoints => write!(f, "index blocks must hold at least one point"),
+            InvalidChunkSize => write!(f, "chunks must hold at least one point"),
+            BlockOutOfRange { section, block } => {
+                write!(f, "section {} has no block {} in the index", section, block)
+            }
+            ZeroPower => write!(f, "cannot contribute to a file of power 0"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(e) | Error::SectionIo { source: e, .. } => Some(e),
+            _ => None,
+        }
+    }
+}
+
+/// The class of an `Error`, for callers that handle errors by class rather
+/// than by variant, such as services deciding whether to retry or alert.
+///
+/// Every variant of `Error` maps to one kind, and the mapping of existing
+/// variants does not change.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    /// Reading or writing a file or remote source failed.
+    Io,
+    /// The file is not a well-formed ptau file.
+    Format,
+    /// The file is well-formed, but holds invalid points or does not have
+    /// what was asked of it.
+    Validation,
+    /// A check that the points, contributions or checksums are consistent
+    /// failed, which may mean the file was tampered with.
+    Verification,
+    /// A limit set in `ReadOptions`, or a resource of the system, was
+    /// exceeded.
+    ResourceLimit,
+}
+
+impl ErrorKind {
+    /// The exit code `ppot` uses for errors of this kind. Codes 1 and 2 are
+    /// left for failed checks and usage errors.
+    pub fn exit_code(self) -> u8 {
+        match self {
+            ErrorKind::Io => 3,
+            ErrorKind::Format => 4,
+            ErrorKind::Validation => 5,
+            ErrorKind::Verification => 6,
+            ErrorKind::ResourceLimit => 7,
+        }
+    }
+}
+
+impl Error {
+    pub fn kind(&self) -> ErrorKind {
+        use Error::*;
+        match self {
+            Io(_) | SectionIo { .. } => ErrorKind::Io,
+            InvalidMagicString
+            | InvalidVersion
+            | InvalidPrimeOrder
+            | InvalidNumSections
+            | NonCanonicalEncoding
+            | NegativeSectionSize { .. }
+            | SectionSizeMismatch { .. }
+            | MissingSection(_)
+            | UnexpectedEof { .. }
+            | PowerTooLarge
+            | InvalidManifest
+            | InvalidMarker
+            | InvalidIndex
+            | InvalidContributions
+            | NotEnoughPoints { .. } => ErrorKind::Format,
+            InvalidNumG1Points
+            | InvalidNumG2Points
+            | InvalidG1Point
+            | InvalidG2Point
+            | InvalidPoints { .. }
+            | PowerExceedsCeremonyPower { .. }
+            | PointAtInfinity
+            | G1NotInSubgroup
+            | G2NotInSubgroup
+            | NotAPointSection(_)
+            | PowerExceedsTwoAdicity { .. }
+            | DomainTooLarge { .. }
+            | MissingIndex
+            | NotConsecutive
+            | BelowCeremonyPower { .. }
+            | InvalidBeacon
+            | InvalidBlockPoints
+            | InvalidChunkSize
+            | BlockOutOfRange { .. }
+            | ZeroPower => ErrorKind::Validation,
+            LagrangeMismatch
+            | ChecksumMismatch { .. }
+            | PairingCheckFailed
+            | InvalidContributionKey { .. } => ErrorKind::Verification,
+            ExceededMaxPower
+            | ExceededMaxPoints
+            | ExceededMaxAllocation
+            | ThreadPoolBuildFailed => ErrorKind::ResourceLimit,
+        }
+    }
+
+    /// Whether the same operation may succeed if tried again: I/O errors
+    /// that are usually transient, such as timeouts and dropped
+    /// connections, and failures to create a thread pool. Errors about the
+    /// contents of a file never are.
+    pub fn is_retryable(&self) -> bool {
+        use std::io::ErrorKind::*;
+        match self {
+            Error::Io(e) | Error::SectionIo { source: e, .. } => matches!(
+                e.kind(),
+                Interrupted
+                    | WouldBlock
+                    | TimedOut
+                    | ConnectionRefused
+                    | ConnectionReset
+                    | ConnectionAborted
+                    | NotConnected
+                    | BrokenPipe
+            ),
+            Error::ThreadPoolBuildFailed => true,
+            _ => false,
+        }
+    }
+
+    /// A message for the end users of an application, saying what went
+    /// wrong in terms of its kind and what they can do about it, followed
+    /// by the details of `Display`.
+    pub fn user_message(&self) -> String {
+        let summary = match self.kind() {
+            ErrorKind::Io if self.is_retryable() => "The file could not be read; try again",
+            ErrorKind::Io => "The file could not be read; check that it exists and is readable",
+            ErrorKind::Format => "The file is not a valid ptau file; it may be corrupt",
+            ErrorKind::Validation => "The file cannot be used for this operation",
+            ErrorKind::Verification => "The file failed verification; it should not be trusted",
+            ErrorKind::ResourceLimit => {
+                "The operation exceeds the allowed limits; raise them if the file is trusted"
+            }
+        };
+        format!("{} ({})", summary, self)
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(e: std::io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+
+/// Options controlling how a ptau file is read.
+///
+/// The limits are checked against values taken from the file header (and
+/// the requested point counts) before anything is allocated from them, so
+/// that a malicious file cannot trigger huge allocations.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ReadOptions {
+    max_power: u32,
+    max_points: usize,
+    max_allocation: u64,
+    pub(crate) lenient: bool,
+    max_invalid_points: usize,
+    infinity_policy: InfinityPolicy,
+    throttle: Throttle,
+    threads: Option<usize>,
+    backend: Backend,
+    pub(crate) check_on_curve: bool,
+    pub(crate) check_subgroup: bool,
+    pub(crate) check_g1_subgroup: bool,
+    trust_marker: bool,
+}
+
+/// What to do with points at infinity, which ptau files encode as all-zero
+/// coordinates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum InfinityPolicy {
+    /// Fail with `Error::PointAtInfinity`. No power of a valid tau is the
+    /// point at infinity.
+    Reject,
+    Accept,
+    /// Accept the point and log a warning.
+    Warn,
+}
+
+impl Default for ReadOptions {
+    fn default() -> Self {
+        // Power 28 is the largest ceremony published by PPOT/Hermez
+        Self {
+            max_power: 28,
+            max_points: (1 << 29) - 1,
+            max_allocation: 1 << 36,
+            lenient: false,
+            max_invalid_points: 0,
+            infinity_policy: InfinityPolicy::Reject,
+            throttle: Throttle::new(),
+            threads: None,
+            backend: Backend::Auto,
+            check_on_curve: true,
+            check_subgroup: false,
+            check_g1_subgroup: false,
+            trust_marker: false,
+        }
+    }
+}
+
+impl ReadOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The largest `power` accepted from the header.
+    pub fn max_power(mut self, max_power: u32) -> Self {
+        self.max_power = max_power;
+        self
+    }
+
+    /// The largest number of points that may be read from a single section.
+    pub fn max_points(mut self, max_points: usize) -> Self {
+        self.max_points = max_points;
+        self
+    }
+
+    /// The largest number of bytes that may be allocated while reading.
+    pub fn max_allocation(mut self, max_allocation: u64) -> Self {
+        self.max_allocation = max_allocation;
+        self
+    }
+
+    /// Check that points are on the curve (the default). Only disable this
+    /// for trusted files, as computing with points off the curve gives
+    /// meaningless results.
+    pub fn check_on_curve(mut self, check_on_curve: bool) -> Self {
+        self.check_on_curve = check_on_curve;
+        self
+    }
+
+    /// Check that G2 points are in the prime order subgroup, failing with
+    /// `Error::G2NotInSubgroup` otherwise. This is much slower than decoding
+    /// them; see `verify::check_g2_subgroup_batched` for a faster check of
+    /// points already read. Points are then also checked to be on the
+    /// curve.
+    pub fn check_subgroup(mut self, check_subgroup: bool) -> Self {
+        self.check_subgroup = check_subgroup;
+        self
+    }
+
+    /// Also check that G1 points are in the prime order subgroup. G1 has
+    /// cofactor 1, so this only fails for points that are not on the curve
+    /// and is only useful as a defense in depth.
+    pub fn check_g1_subgroup(mut self, check_g1_subgroup: bool) -> Self {
+        self.check_g1_subgroup = check_g1_subgroup;
+        self
+    }
+
+    /// In lenient mode, field elements that are not canonically encoded
+    /// (i.e. whose value is not below the modulus) are reduced instead of
+    /// rejected with `Error::NonCanonicalEncoding`, and a header whose power
+    /// exceeds its ceremony power is accepted.
+    pub fn lenient(mut self, lenient: bool) -> Self {
+        self.lenient = lenient;
+        self
+    }
+
+    /// Instead of failing on the first invalid point, keep scanning and
+    /// record the indices of up to `max_invalid_points` invalid points per
+    /// section, returned in `Error::InvalidPoints`. Zero (the default)
+    /// stops at the first invalid point.
+    pub fn collect_invalid_points(mut self, max_invalid_points: usize) -> Self {
+        self.max_invalid_points = max_invalid_points;
+        self
+    }
+
+    /// How points at infinity are handled. Defaults to
+    /// `InfinityPolicy::Reject`.
+    pub fn infinity_policy(mut self, infinity_policy: InfinityPolicy) -> Self {
+        self.infinity_policy = infinity_policy;
+        self
+    }
+
+    /// Limits on the rate at which files are read. Unlimited by default.
+    pub fn throttle(mut self, throttle: Throttle) -> Self {
+        self.throttle = throttle;
+        self
+    }
+
+    /// Run parallel work on a dedicated rayon pool of `threads` threads.
+    /// By default it runs on the current rayon pool: the global pool, or
+    /// the caller's own pool when called within `ThreadPool::install`.
+    pub fn threads(mut self, threads: usize) -> Self {
+        self.threads = Some(threads);
+        self
+    }
+
+    /// Skip the point checks of reads of tau powers from a path if a
+    /// marker written by `marker::verify_and_mark` shows that the file
+    /// passed them. Off by default.
+    ///
+    /// The marker is only trusted if the file still has the hash it
+    /// records, which costs hashing the whole file on every read; this pays
+    /// off when subgroup checks are enabled. Anyone who can write next to
+    /// the file can forge a marker, so only enable this for files in
+    /// trusted directories.
+    pub fn trust_verification_marker(mut self, trust_marker: bool) -> Self {
+        self.trust_marker = trust_marker;
+        self
+    }
+
+    /// How points are read and decoded. Defaults to `Backend::Auto`.
+    pub fn backend(mut self, backend: Backend) -> Self {
+        self.backend = backend;
+        self
+    }
+
+    pub(crate) fn backend_choice(&self) -> Backend {
+        self.backend
+    }
+
+    // The number of threads parallel work runs on
+    pub(crate) fn num_threads(&self) -> usize {
+        self.threads.unwrap_or_else(rayon::current_num_threads)
+    }
+
+    #[cfg(feature = "mmap")]
+    pub(crate) fn max_invalid_points(&self) -> usize {
+        self.max_invalid_points
+    }
+
+    // The read bandwidth limit, if any
+    pub(crate) fn bytes_per_second(&self) -> Option<u64> {
+        self.throttle.bytes_per_second_limit()
+    }
+
+    // Run `f` on the rayon pool selected by these options
+    pub(crate) fn install<T: Send>(&self, f: impl FnOnce() -> T + Send) -> Result<T, Error> {
+        match self.thread_pool()? {
+            Some(pool) => Ok(pool.install(f)),
+            None => Ok(f()),
+        }
+    }
+
+    // The dedicated rayon pool requested with `threads`, if any
+    pub(crate) fn thread_pool(&self) -> Result<Option<rayon::ThreadPool>, Error> {
+        self.threads
+            .map(|threads| {
+                rayon::ThreadPoolBuilder::new()
+                    .num_threads(threads)
+                    .build()
+                    .map_err(|_| Error::ThreadPoolBuildFailed)
+            })
+            .transpose()
+    }
+}
+
+/// The points recovered from a possibly damaged ptau file by
+/// `read_recover`.
+///
+/// Points that could not be decoded or failed validation are replaced by
+/// the point at infinity, and their indices are listed in `invalid_g1` and
+/// `invalid_g2`. These points must not be used as an SRS.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecoveredSrs<G1 = G1Affine, G2 = G2Affine> {
+    pub g1_points: Vec<G1>,
+    pub g2_points: Vec<G2>,
+    pub invalid_g1: Vec<usize>,
+    pub invalid_g2: Vec<usize>,
+}
+
+/// A section of a ptau file, as listed in its section table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct Section {
+    pub id: u32,
+    /// The position of the section contents in the file.
+    pub position: u64,
+    pub size: u64,
+}
+
+/// The contents of the header section (section 1) of a ptau file, plus the
+/// section table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct PtauHeader {
+    /// The size in bytes of a base field element.
+    pub n8: u32,
+    /// The base field modulus, as `n8` little-endian bytes.
+    pub q: Vec<u8>,
+    /// The file holds `2^power` tau powers in G2.
+    pub power: u32,
+    /// The power of the ceremony the file comes from: every contribution
+    /// was made to a file of this power, of which this file may be a
+    /// truncation. It is never less than `power` in a valid file, and is
+    /// kept unchanged by `truncate`.
+    pub ceremony_power: u32,
+    pub sections: Vec<Section>,
+}
+
+impl PtauHeader {
+    /// Look up a section by its id.
+    pub fn section(&self, id: u32) -> Option<&Section> {
+        self.sections.iter().find(|s| s.id == id)
+    }
+
+    /// The sections whose ids snarkjs does not define, such as those added
+    /// by forks of the format. They are listed but otherwise ignored.
+    pub fn unknown_sections(&self) -> impl Iterator<Item = &Section> {
+        self.sections
+            .iter()
+            .filter(|s| !matches!(s.id, 1..=7 | 12..=15))
+    }
+
+    /// The number of tau powers in G1 (`2^power * 2 - 1`) and in G2
+    /// (`2^power`) contained in the file.
+    pub fn num_points(&self) -> Result<(usize, usize), Error> {
+        let num_g2_points = 1usize.checked_shl(self.power).ok_or(Error::PowerTooLarge)?;
+        let num_g1_points = num_g2_points.checked_mul(2).ok_or(Error::PowerTooLarge)? - 1;
+        Ok((num_g1_points, num_g2_points))
+    }
+
+    /// The size of the smallest radix-2 evaluation domain with at least
+    /// `size` points, checking that the domain can be constructed over the
+    /// scalar field and that this file has enough tau powers for it.
+    pub fn domain_size(&self, size: usize) -> Result<usize, Error> {
+        let max_power = self.power.min(FR_TWO_ADICITY);
+        let max_size = 1usize.checked_shl(max_power).unwrap_or(0);
+        match size.checked_next_power_of_two() {
+            Some(domain_size) if domain_size <= max_size => Ok(domain_size),
+            _ => Err(Error::DomainTooLarge { size, max_size }),
+        }
+    }
+}
+
+/// The header and all tau powers of a ptau file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Ptau {
+    pub header: PtauHeader,
+    pub g1_points: Vec<G1Affine>,
+    pub g2_points: Vec<G2Affine>,
 }
 
 pub fn read(
@@ -24,136 +860,1429 @@ pub fn read(
     num_g1_points: usize,
     num_g2_points: usize,
 ) -> Result<(Vec<G1Affine>, Vec<G2Affine>), Error> {
-    let mut f = File::open(ptau_file).unwrap();
+    read_with_options(
+        ptau_file,
+        num_g1_points,
+        num_g2_points,
+        &ReadOptions::default(),
+    )
+}
 
+pub fn read_with_options(
+    ptau_file: &str,
+    num_g1_points: usize,
+    num_g2_points: usize,
+    options: &ReadOptions,
+) -> Result<(Vec<G1Affine>, Vec<G2Affine>), Error> {
+    read_curve::<Bn254>(ptau_file, num_g1_points, num_g2_points, options)
+}
+
+/// Like `read_with_options`, for a file over the curve `C`, such as
+/// `ark_bn254::Bn254`. The header must record the base field modulus of
+/// `C`, and points take `2 * n8` bytes in G1 and `4 * n8` bytes in G2,
+/// where `n8` is the size of its base field elements.
+pub fn read_curve<C: Curve>(
+    ptau_file: &str,
+    num_g1_points: usize,
+    num_g2_points: usize,
+    options: &ReadOptions,
+) -> Result<curve::TauPowers<C>, Error> {
+    let srs = read_points::<C>(ptau_file, num_g1_points, num_g2_points, options, false)?;
+    Ok((srs.g1_points, srs.g2_points))
+}
+
+/// Called by `read_with_hook` with each tau power and its index as the
+/// power is decoded, keeping what it returns.
+///
+/// A hook can filter the points, convert them to another representation,
+/// or accumulate them into a sum or hash and return `None`, without the
+/// decoded SRS in memory.
+pub trait PointHook<C: Curve = Bn254> {
+    type G1;
+    type G2;
+
+    fn g1(&mut self, index: usize, point: curve::G1Affine<C>) -> Option<Self::G1>;
+
+    fn g2(&mut self, index: usize, point: curve::G2Affine<C>) -> Option<Self::G2>;
+}
+
+/// The G1 and G2 values kept by a `PointHook`.
+pub type HookedPoints<H, C = Bn254> = (Vec<<H as PointHook<C>>::G1>, Vec<<H as PointHook<C>>::G2>);
+
+/// Like `read_with_options`, passing each point to `hook` as it is decoded
+/// and returning what the hook keeps.
+///
+/// The hook sees the G1 points in order, then the G2 points. With the
+/// parallel backend a block of points is decoded on the thread pool before
+/// the hook sees any of them. The file is read through a buffer rather
+/// than mapped, whatever the backend.
+pub fn read_with_hook<H: PointHook>(
+    ptau_file: &str,
+    num_g1_points: usize,
+    num_g2_points: usize,
+    options: &ReadOptions,
+    hook: &mut H,
+) -> Result<HookedPoints<H>, Error> {
+    read_curve_with_hook::<Bn254, H>(ptau_file, num_g1_points, num_g2_points, options, hook)
+}
+
+/// Like `read_with_hook`, for a file over the curve `C`.
+pub fn read_curve_with_hook<C: Curve, H: PointHook<C>>(
+    ptau_file: &str,
+    num_g1_points: usize,
+    num_g2_points: usize,
+    options: &ReadOptions,
+    hook: &mut H,
+) -> Result<HookedPoints<H, C>, Error> {
+    audit::record(
+        "read",
+        ptau_file,
+        &[
+            ("num_g1_points", num_g1_points as u64),
+            ("num_g2_points", num_g2_points as u64),
+        ],
+        || {
+            let trusted;
+            let options = if options.trust_marker {
+                trusted = crate::marker::trusted_options(ptau_file, options);
+                &trusted
+            } else {
+                options
+            };
+            let mut f = BufReader::new(open(ptau_file, options)?);
+            let header = read_header_for::<C, _>(&mut f, options)?;
+            check_point_counts::<H::G1, H::G2>(&header, num_g1_points, num_g2_points, options)?;
+            let mut reader = section_reader(&mut f, &header, options)?;
+            let (g1_points, invalid_g1) = reader.read_section_with(
+                2,
+                0,
+                num_g1_points,
+                curve::g1_format::<C>(),
+                Zero::zero(),
+                Vec::new(),
+                |index, point| hook.g1(index, point),
+            )?;
+            let (g2_points, invalid_g2) = reader.read_section_with(
+                3,
+                0,
+                num_g2_points,
+                curve::g2_format::<C>(),
+                Zero::zero(),
+                Vec::new(),
+                |index, point| hook.g2(index, point),
+            )?;
+            if !invalid_g1.is_empty() || !invalid_g2.is_empty() {
+                return Err(Error::InvalidPoints {
+                    g1: invalid_g1,
+                    g2: invalid_g2,
+                });
+            }
+            Ok((g1_points, g2_points))
+        },
+    )
+}
+
+/// Read the header and every tau power of a ptau file: `2^power * 2 - 1`
+/// points in G1 and `2^power` in G2.
+pub fn read_all(ptau_file: &str) -> Result<Ptau, Error> {
+    read_all_with_options(ptau_file, &ReadOptions::default())
+}
+
+pub fn read_all_with_options(ptau_file: &str, options: &ReadOptions) -> Result<Ptau, Error> {
+    audit::record("read_all", ptau_file, &[], || {
+        let mut f = BufReader::new(open(ptau_file, options)?);
+        let header = read_header_from(&mut f, options)?;
+        let (num_g1_points, num_g2_points) = header.num_points()?;
+        let srs = read_points_from(
+            &mut f,
+            &header,
+            num_g1_points,
+            num_g2_points,
+            options,
+            false,
+        )?;
+        Ok(Ptau {
+            header,
+            g1_points: srs.g1_points,
+            g2_points: srs.g2_points,
+        })
+    })
+}
+
+/// Read the tau powers of the smallest power `p` with `2^p >= num_powers`,
+/// as in a file of power `p`: `2^p * 2 - 1` points in G1 and `2^p` in G2.
+///
+/// Fails with `Error::InvalidNumG1Points` if the file has fewer than
+/// `num_powers` powers.
+pub fn read_at_least(
+    ptau_file: &str,
+    num_powers: usize,
+    options: &ReadOptions,
+) -> Result<(Vec<G1Affine>, Vec<G2Affine>), Error> {
+    let num_g2_points = num_powers
+        .checked_next_power_of_two()
+        .ok_or(Error::InvalidNumG1Points)?;
+    let num_g1_points = num_g2_points
+        .checked_mul(2)
+        .ok_or(Error::InvalidNumG1Points)?
+        - 1;
+    read_with_options(ptau_file, num_g1_points, num_g2_points, options)
+}
+
+/// Read and validate the header and section table of a ptau file, without
+/// reading any point.
+pub fn read_header(ptau_file: &str) -> Result<PtauHeader, Error> {
+    read_header_with_options(ptau_file, &ReadOptions::default())
+}
+
+pub fn read_header_with_options(
+    ptau_file: &str,
+    options: &ReadOptions,
+) -> Result<PtauHeader, Error> {
+    read_curve_header::<Bn254>(ptau_file, options)
+}
+
+/// Like `read_header_with_options`, for a file over the curve `C`.
+pub fn read_curve_header<C: Curve>(
+    ptau_file: &str,
+    options: &ReadOptions,
+) -> Result<PtauHeader, Error> {
+    audit::record("read_header", ptau_file, &[], || {
+        read_header_for::<C, _>(&mut open(ptau_file, options)?, options)
+    })
+}
+
+/// Like `read_with_options`, reading from any seekable source, such as a
+/// `Cursor` over ptau data held in memory.
+///
+/// The reader is used as is: wrap files in a `BufReader`. The throttle of
+/// `options` only applies to files opened by path.
+pub fn read_from<R: Read + Seek>(
+    reader: R,
+    num_g1_points: usize,
+    num_g2_points: usize,
+    options: &ReadOptions,
+) -> Result<(Vec<G1Affine>, Vec<G2Affine>), Error> {
+    let srs = read_srs::<Bn254, _>(reader, num_g1_points, num_g2_points, options, false)?;
+    Ok((srs.g1_points, srs.g2_points))
+}
+
+/// Read a damaged ptau file for forensic analysis, replacing invalid points
+/// with a placeholder instead of failing. Errors in the file header are
+/// still reported as usual.
+pub fn read_recover(
+    ptau_file: &str,
+    num_g1_points: usize,
+    num_g2_points: usize,
+    options: &ReadOptions,
+) -> Result<RecoveredSrs, Error> {
+    read_points::<Bn254>(ptau_file, num_g1_points, num_g2_points, options, true)
+}
+
+/// Parse an in-memory ptau file, reading every tau power declared by its
+/// header.
+///
+/// This never touches the filesystem and never panics, whatever the input,
+/// which makes it suitable as a fuzzing entry point.
+pub fn parse(data: &[u8]) -> Result<Ptau, Error> {
+    parse_with_options(data, &ReadOptions::default())
+}
+
+pub fn parse_with_options(data: &[u8], options: &ReadOptions) -> Result<Ptau, Error> {
+    // A well-formed file cannot need much more memory than its own size, so
+    // don't let a hostile header allocate beyond that
+    let mut options = options.clone();
+    options.max_allocation = options
+        .max_allocation
+        .min((data.len() as u64).saturating_mul(2));
+
+    let mut reader = Cursor::new(data);
+    let header = read_header_from(&mut reader, &options)?;
+    let (num_g1_points, num_g2_points) = header.num_points()?;
+    let srs = read_points_from(
+        &mut reader,
+        &header,
+        num_g1_points,
+        num_g2_points,
+        &options,
+        false,
+    )?;
+    Ok(Ptau {
+        header,
+        g1_points: srs.g1_points,
+        g2_points: srs.g2_points,
+    })
+}
+
+/// Read `count` G1 points from section 2, starting at point `start` and
+/// then every `stride`-th point, seeking over the points in between.
+///
+/// Unlike `read_with_options`, this stops at the first invalid point.
+pub fn read_g1_strided(
+    ptau_file: &str,
+    start: usize,
+    stride: usize,
+    count: usize,
+    options: &ReadOptions,
+) -> Result<Vec<G1Affine>, Error> {
+    audit::record(
+        "read_g1_strided",
+        ptau_file,
+        &[
+            ("start", start as u64),
+            ("stride", stride as u64),
+            ("count", count as u64),
+        ],
+        || {
+            let mut f = open(ptau_file, options)?;
+            let header = read_header_from(&mut f, options)?;
+            let (num_points, _) = header.num_points()?;
+            if !strided_in_bounds(start, stride, count, num_points) {
+                return Err(Error::InvalidNumG1Points);
+            }
+            check_allocation::<G1Affine>(count, options)?;
+            read_strided_from(&mut f, &header, 2, start, stride, count, G1_FORMAT, options)
+        },
+    )
+}
+
+/// Like `read_g1_strided`, for the G2 points of section 3.
+pub fn read_g2_strided(
+    ptau_file: &str,
+    start: usize,
+    stride: usize,
+    count: usize,
+    options: &ReadOptions,
+) -> Result<Vec<G2Affine>, Error> {
+    audit::record(
+        "read_g2_strided",
+        ptau_file,
+        &[
+            ("start", start as u64),
+            ("stride", stride as u64),
+            ("count", count as u64),
+        ],
+        || {
+            let mut f = open(ptau_file, options)?;
+            let header = read_header_from(&mut f, options)?;
+            let (_, num_points) = header.num_points()?;
+            if !strided_in_bounds(start, stride, count, num_points) {
+                return Err(Error::InvalidNumG2Points);
+            }
+            check_allocation::<G2Affine>(count, options)?;
+            read_strided_from(&mut f, &header, 3, start, stride, count, G2_FORMAT, options)
+        },
+    )
+}
+
+/// Read the G1 points `start..end` of section 2, seeking straight to point
+/// `start` rather than decoding the points before it.
+///
+/// Invalid points are handled as by `read_with_options`.
+pub fn read_g1_range(
+    ptau_file: &str,
+    start: usize,
+    end: usize,
+    options: &ReadOptions,
+) -> Result<Vec<G1Affine>, Error> {
+    read_section_range(
+        "read_g1_range",
+        ptau_file,
+        2,
+        start,
+        end,
+        options,
+        G1_FORMAT,
+        G1Affine::zero(),
+    )
+}
+
+/// Like `read_g1_range`, for the G2 points of section 3.
+pub fn read_g2_range(
+    ptau_file: &str,
+    start: usize,
+    end: usize,
+    options: &ReadOptions,
+) -> Result<Vec<G2Affine>, Error> {
+    read_section_range(
+        "read_g2_range",
+        ptau_file,
+        3,
+        start,
+        end,
+        options,
+        G2_FORMAT,
+        G2Affine::zero(),
+    )
+}
+
+/// Read the first `num_points` points `[alpha * tau^i]_1` of section 4.
+/// The section holds as many points as section 3.
+pub fn read_alpha_tau_g1(
+    ptau_file: &str,
+    num_points: usize,
+    options: &ReadOptions,
+) -> Result<Vec<G1Affine>, Error> {
+    read_section_range(
+        "read_alpha_tau_g1",
+        ptau_file,
+        4,
+        0,
+        num_points,
+        options,
+        G1_FORMAT,
+        G1Affine::zero(),
+    )
+}
+
+/// Read the first `num_points` points `[beta * tau^i]_1` of section 5.
+/// The section holds as many points as section 3.
+pub fn read_beta_tau_g1(
+    ptau_file: &str,
+    num_points: usize,
+    options: &ReadOptions,
+) -> Result<Vec<G1Affine>, Error> {
+    read_section_range(
+        "read_beta_tau_g1",
+        ptau_file,
+        5,
+        0,
+        num_points,
+        options,
+        G1_FORMAT,
+        G1Affine::zero(),
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+fn read_section_range<T: Copy + Send>(
+    operation: &str,
+    ptau_file: &str,
+    section: u32,
+    start: usize,
+    end: usize,
+    options: &ReadOptions,
+    format: PointFormat<T>,
+    placeholder: T,
+) -> Result<Vec<T>, Error> {
+    audit::record(
+        operation,
+        ptau_file,
+        &[("start", start as u64), ("end", end as u64)],
+        || {
+            let mut f = BufReader::new(open(ptau_file, options)?);
+            let header = read_header_from(&mut f, options)?;
+            read_section_range_from(
+                &mut f,
+                &header,
+                section,
+                start,
+                end,
+                options,
+                format,
+                placeholder,
+            )
+        },
+    )
+}
+
+// Read points `start..end` of one of the sections 2 to 5. Out of range reads
+// and invalid points are reported against G2 for section 3 and G1 otherwise.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn read_section_range_from<R: Read + Seek, T: Copy + Send>(
+    reader: &mut R,
+    header: &PtauHeader,
+    section: u32,
+    start: usize,
+    end: usize,
+    options: &ReadOptions,
+    format: PointFormat<T>,
+    placeholder: T,
+) -> Result<Vec<T>, Error> {
+    let (num_g1_points, num_g2_points) = header.num_points()?;
+    let num_points = if section == 2 {
+        num_g1_points
+    } else {
+        num_g2_points
+    };
+    let is_g2 = section == 3;
+    if start > end || end > num_points {
+        return Err(if is_g2 {
+            Error::InvalidNumG2Points
+        } else {
+            Error::InvalidNumG1Points
+        });
+    }
+    check_allocation::<T>(end - start, options)?;
+    let (points, invalid) = section_reader(reader, header, options)?.read_section(
+        section,
+        start,
+        end,
+        format,
+        placeholder,
+    )?;
+    if !invalid.is_empty() {
+        return Err(if is_g2 {
+            Error::InvalidPoints {
+                g1: Vec::new(),
+                g2: invalid,
+            }
+        } else {
+            Error::InvalidPoints {
+                g1: invalid,
+                g2: Vec::new(),
+            }
+        });
+    }
+    Ok(points)
+}
+
+/// Read the Lagrange-basis G1 points `[L_i(tau)]_1` for the domain of
+/// `2^power` points from section 12 of a prepared ptau file.
+///
+/// Prepared files hold domains of up to `2^(power + 1)` points, where
+/// `power` is the header power.
+pub fn read_lagrange_g1(
+    ptau_file: &str,
+    power: u32,
+    options: &ReadOptions,
+) -> Result<Vec<G1Affine>, Error> {
+    read_lagrange("read_lagrange_g1", ptau_file, 12, power, options, G1_FORMAT)
+}
+
+/// Read the Lagrange-basis G2 points `[L_i(tau)]_2` for the domain of
+/// `2^power` points from section 13 of a prepared ptau file.
+///
+/// Unlike section 12, sections 13 to 15 only hold domains of up to
+/// `2^power` points, where `power` is the header power.
+pub fn read_lagrange_g2(
+    ptau_file: &str,
+    power: u32,
+    options: &ReadOptions,
+) -> Result<Vec<G2Affine>, Error> {
+    read_lagrange("read_lagrange_g2", ptau_file, 13, power, options, G2_FORMAT)
+}
+
+/// Read the points `[alpha * L_i(tau)]_1` for the domain of `2^power`
+/// points from section 14 of a prepared ptau file.
+pub fn read_lagrange_alpha_g1(
+    ptau_file: &str,
+    power: u32,
+    options: &ReadOptions,
+) -> Result<Vec<G1Affine>, Error> {
+    read_lagrange(
+        "read_lagrange_alpha_g1",
+        ptau_file,
+        14,
+        power,
+        options,
+        G1_FORMAT,
+    )
+}
+
+/// Read the points `[beta * L_i(tau)]_1` for the domain of `2^power`
+/// points from section 15 of a prepared ptau file.
+pub fn read_lagrange_beta_g1(
+    ptau_file: &str,
+    power: u32,
+    options: &ReadOptions,
+) -> Result<Vec<G1Affine>, Error> {
+    read_lagrange(
+        "read_lagrange_beta_g1",
+        ptau_file,
+        15,
+        power,
+        options,
+        G1_FORMAT,
+    )
+}
+
+fn read_lagrange<T>(
+    operation: &str,
+    ptau_file: &str,
+    section: u32,
+    power: u32,
+    options: &ReadOptions,
+    format: PointFormat<T>,
+) -> Result<Vec<T>, Error> {
+    audit::record(operation, ptau_file, &[("power", power as u64)], || {
+        let mut f = open(ptau_file, options)?;
+        let header = read_header_from(&mut f, options)?;
+        let max_power = if section == 12 {
+            header.power + 1
+        } else {
+            header.power
+        };
+        if power > max_power {
+            return Err(Error::DomainTooLarge {
+                size: 1usize.checked_shl(power).unwrap_or(usize::MAX),
+                max_size: 1 << max_power,
+            });
+        }
+        // The domains are stored from smallest to largest
+        let count = 1 << power;
+        check_allocation::<T>(count, options)?;
+        read_strided_from(
+            &mut f,
+            &header,
+            section,
+            count - 1,
+            1,
+            count,
+            format,
+            options,
+        )
+    })
+}
+
+/// Read `[alpha]_1`, the first point of section 4, which Groth16 phase 2
+/// needs alongside the tau powers.
+pub fn alpha_g1(ptau_file: &str, options: &ReadOptions) -> Result<G1Affine, Error> {
+    read_single_point(ptau_file, 4, options, G1_FORMAT)
+}
+
+/// Read `[beta]_1`, the first point of section 5.
+pub fn beta_g1(ptau_file: &str, options: &ReadOptions) -> Result<G1Affine, Error> {
+    read_single_point(ptau_file, 5, options, G1_FORMAT)
+}
+
+/// Read `[beta]_2`, the point of section 6.
+pub fn beta_g2(ptau_file: &str, options: &ReadOptions) -> Result<G2Affine, Error> {
+    read_single_point(ptau_file, 6, options, G2_FORMAT)
+}
+
+fn read_single_point<T>(
+    ptau_file: &str,
+    section: u32,
+    options: &ReadOptions,
+    format: PointFormat<T>,
+) -> Result<T, Error> {
+    let mut f = open(ptau_file, options)?;
+    let header = read_header_from(&mut f, options)?;
+    let mut points = read_strided_from(&mut f, &header, section, 0, 1, 1, format, options)?;
+    Ok(points.remove(0))
+}
+
+// Whether the last point of a strided read exists
+fn strided_in_bounds(start: usize, stride: usize, count: usize, num_points: usize) -> bool {
+    count == 0
+        || stride
+            .checked_mul(count - 1)
+            .and_then(|n| n.checked_add(start))
+            .is_some_and(|last| last < num_points)
+}
+
+pub(crate) fn check_allocation<T>(count: usize, options: &ReadOptions) -> Result<(), Error> {
+    if count > options.max_points {
+        return Err(Error::ExceededMaxPoints);
+    }
+    let allocation = (count as u64).saturating_mul(std::mem::size_of::<T>() as u64);
+    if allocation > options.max_allocation {
+        return Err(Error::ExceededMaxAllocation);
+    }
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn read_strided_from<R: Read + Seek, T>(
+    reader: &mut R,
+    header: &PtauHeader,
+    section: u32,
+    start: usize,
+    stride: usize,
+    count: usize,
+    format: PointFormat<T>,
+    options: &ReadOptions,
+) -> Result<Vec<T>, Error> {
+    let end = match count {
+        0 => 0,
+        _ => start + (count - 1) * stride + 1,
+    };
+    let position = points_position(header, section, end, format.size)?;
+    let mut buf = vec![0u8; format.size];
+    let mut points = Vec::with_capacity(count);
+    for i in 0..count {
+        let index = start + i * stride;
+        reader
+            .seek(SeekFrom::Start(position + (index * format.size) as u64))
+            .map_err(io_error)?;
+        read_point(reader, &mut buf, section, index, index + 1)?;
+        points.push((format.decode)(&buf, options)?);
+    }
+    Ok(points)
+}
+
+fn read_points<C: Curve>(
+    ptau_file: &str,
+    num_g1_points: usize,
+    num_g2_points: usize,
+    options: &ReadOptions,
+    recover: bool,
+) -> Result<RecoveredSrs<curve::G1Affine<C>, curve::G2Affine<C>>, Error> {
+    audit::record(
+        "read",
+        ptau_file,
+        &[
+            ("num_g1_points", num_g1_points as u64),
+            ("num_g2_points", num_g2_points as u64),
+        ],
+        || {
+            let trusted;
+            let options = if options.trust_marker && !recover {
+                trusted = crate::marker::trusted_options(ptau_file, options);
+                &trusted
+            } else {
+                options
+            };
+            // The counts come from the caller and are checked against the
+            // file later, so may be too large to size
+            let g1_bytes = (num_g1_points as u64)
+                .checked_mul(C::g1_size() as u64)
+                .ok_or(Error::InvalidNumG1Points)?;
+            let requested_bytes = (num_g2_points as u64)
+                .checked_mul(C::g2_size() as u64)
+                .and_then(|g2_bytes| g2_bytes.checked_add(g1_bytes))
+                .ok_or(Error::InvalidNumG2Points)?;
+            let file_size = std::fs::metadata(ptau_file).ok().map(|m| m.len());
+            let backend = backend::choose(
+                options.backend,
+                &backend::Facts::new(requested_bytes, file_size, options),
+            );
+            #[cfg(feature = "mmap")]
+            if backend == Backend::Mmap && !recover {
+                let (g1_points, g2_points) = crate::mmap::read_mapped::<C>(
+                    ptau_file,
+                    num_g1_points,
+                    num_g2_points,
+                    options,
+                )?;
+                return Ok(RecoveredSrs {
+                    g1_points,
+                    g2_points,
+                    invalid_g1: Vec::new(),
+                    invalid_g2: Vec::new(),
+                });
+            }
+            let f = BufReader::new(open(ptau_file, options)?);
+            let options = &options.clone().backend(backend);
+            read_srs::<C, _>(f, num_g1_points, num_g2_points, options, recover)
+        },
+    )
+}
+
+fn read_srs<C: Curve, R: Read + Seek>(
+    mut reader: R,
+    num_g1_points: usize,
+    num_g2_points: usize,
+    options: &ReadOptions,
+    recover: bool,
+) -> Result<RecoveredSrs<curve::G1Affine<C>, curve::G2Affine<C>>, Error> {
+    let header = read_header_for::<C, _>(&mut reader, options)?;
+    read_points_for::<C, _>(
+        &mut reader,
+        &header,
+        num_g1_points,
+        num_g2_points,
+        options,
+        recover,
+    )
+}
+
+pub(crate) fn open(ptau_file: &str, options: &ReadOptions) -> Result<ThrottledReader<File>, Error> {
+    let f = File::open(ptau_file).map_err(io_error)?;
+    Ok(throttled(f, options))
+}
+
+pub(crate) fn throttled<R>(reader: R, options: &ReadOptions) -> ThrottledReader<R> {
+    ThrottledReader::new(reader, options.throttle)
+}
+
+pub(crate) fn read_header_from<R: Read + Seek>(
+    reader: &mut R,
+    options: &ReadOptions,
+) -> Result<PtauHeader, Error> {
+    read_header_for::<Bn254, R>(reader, options)
+}
+
+// Read the header of a file over the curve `C`, whose modulus and field
+// element size it must record
+pub(crate) fn read_header_for<C: Curve, R: Read + Seek>(
+    reader: &mut R,
+    options: &ReadOptions,
+) -> Result<PtauHeader, Error> {
     // Read the magic string (the first 4 bytes)
     let mut magic_string_buf = [0u8; 4];
-    let _ = f.read_exact(&mut magic_string_buf);
-    if std::str::from_utf8(&magic_string_buf).unwrap() != "ptau" {
+    read_bytes(reader, &mut magic_string_buf, 0)?;
+    if &magic_string_buf != b"ptau" {
         return Err(Error::InvalidMagicString);
     }
 
     // Read the version (a 32-bit little-endian uint)
-    let version = f.read_u32::<LittleEndian>().unwrap();
+    let version = read_u32(reader, 0)?;
     if version != 1 {
         return Err(Error::InvalidVersion);
     }
 
-    // Read the number of sections (a 32-bit little-endian uint)
-    let num_sections = f.read_u32::<LittleEndian>().unwrap();
-    if num_sections != 11 {
-        return Err(Error::InvalidNumSections);
-    }
+    // Read the number of sections (a 32-bit little-endian uint). Prepared
+    // files and forks of the format add sections, so any number is
+    // accepted, and only the sections that are used need to be present.
+    let num_sections = read_u32(reader, 0)?;
 
-    // section_num => (file position, section size)
-    let mut sections = BTreeMap::<usize, u64>::new();
+    // Find the length of the file, to detect truncated sections
+    let table_position = reader.stream_position().map_err(io_error)?;
+    let file_len = reader.seek(SeekFrom::End(0)).map_err(io_error)?;
+    reader
+        .seek(SeekFrom::Start(table_position))
+        .map_err(io_error)?;
 
+    // Each section starts with a 12-byte id and size, so the file size
+    // bounds the number of sections actually present
+    let max_sections = (file_len - table_position) / 12;
+    let mut sections =
+        Vec::<Section>::with_capacity((num_sections as u64).min(max_sections) as usize);
     for _ in 0..num_sections {
-        let num = f.read_u32::<LittleEndian>().unwrap();
-        let size = f.read_i64::<LittleEndian>().unwrap();
-        let pos = f.stream_position().unwrap();
-        let _ = f.seek(SeekFrom::Current(size));
-        sections.insert(num as usize, pos);
+        let id = read_u32(reader, 0)?;
+        let mut size_buf = [0u8; 8];
+        read_bytes(reader, &mut size_buf, 0)?;
+        let size = i64::from_le_bytes(size_buf);
+        if size < 0 {
+            return Err(Error::NegativeSectionSize { section: id });
+        }
+        let position = reader.stream_position().map_err(io_error)?;
+        let available = file_len.saturating_sub(position);
+        if available < size as u64 {
+            return Err(Error::UnexpectedEof {
+                section: id,
+                point_index: point_size(id, C::n8() as u32)
+                    .map(|point_size| (available / point_size) as usize),
+                expected_bytes: size as u64,
+                got_bytes: available,
+            });
+        }
+        reader.seek(SeekFrom::Current(size)).map_err(io_error)?;
+        sections.push(Section {
+            id,
+            position,
+            size: size as u64,
+        });
     }
 
     // Read the header (section 1)
-    let _ = f.seek(SeekFrom::Start(sections[&1]));
-    let n8 = f.read_u32::<LittleEndian>().unwrap();
+    let header_section = sections
+        .iter()
+        .find(|s| s.id == 1)
+        .ok_or(Error::MissingSection(1))?;
+    reader
+        .seek(SeekFrom::Start(header_section.position))
+        .map_err(io_error)?;
+    let n8 = read_u32(reader, 1)?;
+    if !options.lenient && n8 as usize != C::n8() {
+        return Err(Error::InvalidPrimeOrder);
+    }
+    if n8 as u64 > options.max_allocation {
+        return Err(Error::ExceededMaxAllocation);
+    }
     let mut q_buf = vec![0u8; n8 as usize];
-    let _ = f.read_exact(&mut q_buf);
+    read_bytes(reader, &mut q_buf, 1)?;
+    check_modulus::<C>(&q_buf, options)?;
+
+    // Read the power
+    let power = read_u32(reader, 1)?;
+    if power > options.max_power {
+        return Err(Error::ExceededMaxPower);
+    }
 
-    // ensure that q_buf is not all 0s
-    let mut num_zeroes = 0;
-    for b in q_buf.iter() {
-        if *b == 0u8 {
-            num_zeroes += 1;
+    // Read the ceremony power
+    let ceremony_power = read_u32(reader, 1)?;
+
+    let header = PtauHeader {
+        n8,
+        q: q_buf,
+        power,
+        ceremony_power,
+        sections,
+    };
+    check_header::<C>(&header, options)?;
+    Ok(header)
+}
+
+fn check_modulus<C: Curve>(q: &[u8], options: &ReadOptions) -> Result<(), Error> {
+    if options.lenient {
+        // ensure that q is not all 0s
+        if q.iter().all(|b| *b == 0) {
+            return Err(Error::InvalidPrimeOrder);
+        }
+
+        // q should reduce to 0 since it's the Fq modulus
+        if !C::Fq::from_le_bytes_mod_order(q).is_zero() {
+            return Err(Error::InvalidPrimeOrder);
+        }
+    } else {
+        // q must be exactly the Fq modulus, not merely a multiple of it
+        if q != C::modulus_le_bytes() {
+            return Err(Error::InvalidPrimeOrder);
         }
     }
-    if num_zeroes == 32 {
+    Ok(())
+}
+
+// Check a header read from a file or an index as `read_header_for` does:
+// its field for the curve `C`, its power against the limits of `options`
+// and the scalar field, and the sizes of its sections
+pub(crate) fn check_header<C: Curve>(
+    header: &PtauHeader,
+    options: &ReadOptions,
+) -> Result<(), Error> {
+    if !options.lenient && header.n8 as usize != C::n8() {
         return Err(Error::InvalidPrimeOrder);
     }
+    check_modulus::<C>(&header.q, options)?;
+    let (power, ceremony_power) = (header.power, header.ceremony_power);
+    if power > options.max_power {
+        return Err(Error::ExceededMaxPower);
+    }
+    if !options.lenient {
+        check_section_sizes(header)?;
+        if power > ceremony_power {
+            return Err(Error::PowerExceedsCeremonyPower {
+                power,
+                ceremony_power,
+            });
+        }
+    }
+    // The prepared sections are evaluations over domains of up to
+    // 2^(power + 1) points, but the tau powers are only usable with domains
+    // of up to 2^power points
+    if power > C::two_adicity() {
+        return Err(Error::PowerExceedsTwoAdicity {
+            power,
+            two_adicity: C::two_adicity(),
+        });
+    }
+    Ok(())
+}
 
-    // Read q_buf as an Fq element
-    let q = Fq::from_le_bytes_mod_order(&q_buf);
+// Check that the sections whose size is determined by the header have that
+// size
+pub(crate) fn check_section_sizes(header: &PtauHeader) -> Result<(), Error> {
+    let (num_g1_points, num_g2_points) = header.num_points()?;
+    let g1_size = 2 * header.n8 as u64;
+    let g2_size = 4 * header.n8 as u64;
+    let size = |num_points: u64, point_size: u64| {
+        num_points
+            .checked_mul(point_size)
+            .ok_or(Error::PowerTooLarge)
+    };
+    // The Lagrange sections hold one set of points per domain size 2^0 to
+    // 2^power, plus 2^(power + 1) for section 12
+    let num_lagrange_points = num_g1_points as u64;
 
-    // q should be 0 since it's the Fq modulus
-    if q != Fq::from(0) {
-        return Err(Error::InvalidPrimeOrder);
+    let expected_sizes = [
+        (1, 4 + header.n8 as u64 + 8),
+        (2, size(num_g1_points as u64, g1_size)?),
+        (3, size(num_g2_points as u64, g2_size)?),
+        (4, size(num_g2_points as u64, g1_size)?),
+        (5, size(num_g2_points as u64, g1_size)?),
+        (6, g2_size),
+        (
+            12,
+            size(num_lagrange_points, 2 * g1_size)?
+                .checked_add(g1_size)
+                .ok_or(Error::PowerTooLarge)?,
+        ),
+        (13, size(num_lagrange_points, g2_size)?),
+        (14, size(num_lagrange_points, g1_size)?),
+        (15, size(num_lagrange_points, g1_size)?),
+    ];
+    for (id, expected) in expected_sizes {
+        if let Some(section) = header.section(id) {
+            if section.size != expected {
+                return Err(Error::SectionSizeMismatch {
+                    section: id,
+                    expected,
+                    actual: section.size,
+                });
+            }
+        }
     }
+    Ok(())
+}
 
-    // Read the power
-    let power = f.read_u32::<LittleEndian>().unwrap();
+pub(crate) fn read_points_from<R: Read + Seek>(
+    reader: &mut R,
+    header: &PtauHeader,
+    num_g1_points: usize,
+    num_g2_points: usize,
+    options: &ReadOptions,
+    recover: bool,
+) -> Result<RecoveredSrs, Error> {
+    read_points_for::<Bn254, R>(
+        reader,
+        header,
+        num_g1_points,
+        num_g2_points,
+        options,
+        recover,
+    )
+}
 
-    // Read the ceremony power
-    let _ceremony_power = f.read_u32::<LittleEndian>().unwrap();
+// Read the tau powers of sections 2 and 3, decoding them as points of `C`
+pub(crate) fn read_points_for<C: Curve, R: Read + Seek>(
+    reader: &mut R,
+    header: &PtauHeader,
+    num_g1_points: usize,
+    num_g2_points: usize,
+    options: &ReadOptions,
+    recover: bool,
+) -> Result<RecoveredSrs<curve::G1Affine<C>, curve::G2Affine<C>>, Error> {
+    check_point_counts::<curve::G1Affine<C>, curve::G2Affine<C>>(
+        header,
+        num_g1_points,
+        num_g2_points,
+        options,
+    )?;
+
+    let mut reader = section_reader(reader, header, options)?;
+    reader.recover = recover;
+    let (g1_points, invalid_g1) =
+        reader.read_section(2, 0, num_g1_points, curve::g1_format::<C>(), Zero::zero())?;
+    let (g2_points, invalid_g2) =
+        reader.read_section(3, 0, num_g2_points, curve::g2_format::<C>(), Zero::zero())?;
+
+    if !recover && (!invalid_g1.is_empty() || !invalid_g2.is_empty()) {
+        return Err(Error::InvalidPoints {
+            g1: invalid_g1,
+            g2: invalid_g2,
+        });
+    }
+    Ok(RecoveredSrs {
+        g1_points,
+        g2_points,
+        invalid_g1,
+        invalid_g2,
+    })
+}
+
+// Reads the points at the start of sections, handling invalid points as
+// `read_points_from` does
+struct SectionReader<'a, R> {
+    reader: &'a mut R,
+    header: &'a PtauHeader,
+    options: &'a ReadOptions,
+    recover: bool,
+    #[cfg(feature = "parallel")]
+    pool: Option<rayon::ThreadPool>,
+}
+
+fn section_reader<'a, R>(
+    reader: &'a mut R,
+    header: &'a PtauHeader,
+    options: &'a ReadOptions,
+) -> Result<SectionReader<'a, R>, Error> {
+    Ok(SectionReader {
+        reader,
+        header,
+        options,
+        recover: false,
+        #[cfg(feature = "parallel")]
+        pool: options.thread_pool()?,
+    })
+}
+
+impl<R: Read + Seek> SectionReader<'_, R> {
+    // Read the points `start..end` of a section, returning the points and the
+    // indices of the invalid ones
+    fn read_section<T: Copy + Send>(
+        &mut self,
+        section: u32,
+        start: usize,
+        end: usize,
+        format: PointFormat<T>,
+        placeholder: T,
+    ) -> Result<(Vec<T>, Vec<usize>), Error> {
+        let points = Vec::with_capacity(end - start);
+        let keep = |_, point| Some(point);
+        self.read_section_with(section, start, end, format, placeholder, points, keep)
+    }
+
+    // Like `read_section`, passing each point to `map` as it is decoded and
+    // keeping what it returns in `points`
+    #[allow(clippy::too_many_arguments)]
+    fn read_section_with<T: Copy + Send, U, F: FnMut(usize, T) -> Option<U>>(
+        &mut self,
+        section: u32,
+        start: usize,
+        end: usize,
+        format: PointFormat<T>,
+        placeholder: T,
+        points: Vec<U>,
+        map: F,
+    ) -> Result<(Vec<U>, Vec<usize>), Error> {
+        let position = points_position(self.header, section, end, format.size)?;
+        self.reader
+            .seek(SeekFrom::Start(
+                position + start as u64 * format.size as u64,
+            ))
+            .map_err(io_error)?;
+        let mut points = Points {
+            points,
+            invalid: Vec::new(),
+            options: self.options,
+            recover: self.recover,
+            placeholder,
+            map,
+        };
+        // No larger than the section, as `points_position` checked `end`
+        let requested_bytes = (end - start) as u64 * format.size as u64;
+        match backend::choose(
+            self.options.backend,
+            &backend::Facts::new(requested_bytes, None, self.options),
+        ) {
+            #[cfg(feature = "parallel")]
+            Backend::Parallel => {
+                self.read_into_parallel(&mut points, section, start, end, format)?
+            }
+            _ => self.read_into(&mut points, section, start, end, format)?,
+        }
+        Ok((points.points, points.invalid))
+    }
 
-    let max_g2_points = 1 << power;
-    let max_g1_points = max_g2_points * 2 - 1;
+    fn read_into<T: Copy, U, F: FnMut(usize, T) -> Option<U>>(
+        &mut self,
+        points: &mut Points<T, U, F>,
+        section: u32,
+        start: usize,
+        end: usize,
+        format: PointFormat<T>,
+    ) -> Result<(), Error> {
+        let mut buf = vec![0u8; format.size];
+        for i in start..end {
+            read_point(self.reader, &mut buf, section, i, end)?;
+            if !points.push(i, (format.decode)(&buf, self.options))? {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    // Read the section in chunks of points, decoding the points of each
+    // chunk in parallel
+    #[cfg(feature = "parallel")]
+    fn read_into_parallel<T: Copy + Send, U, F: FnMut(usize, T) -> Option<U>>(
+        &mut self,
+        points: &mut Points<T, U, F>,
+        section: u32,
+        mut start: usize,
+        end: usize,
+        format: PointFormat<T>,
+    ) -> Result<(), Error> {
+        use rayon::prelude::*;
+
+        const CHUNK_POINTS: usize = 1 << 14;
+        let options = self.options;
+        let n = format.size;
+        let mut buf = vec![0u8; CHUNK_POINTS.min(end - start) * n];
+        while start < end {
+            let chunk_points = CHUNK_POINTS.min(end - start);
+            let chunk = &mut buf[..chunk_points * n];
+            let got = read_up_to(self.reader, chunk, section)?;
+            let decode_chunk = || {
+                chunk[..got - got % n]
+                    .par_chunks_exact(n)
+                    .map(|point| (format.decode)(point, options))
+                    .collect::<Vec<_>>()
+            };
+            let decoded = match &self.pool {
+                Some(pool) => pool.install(decode_chunk),
+                None => decode_chunk(),
+            };
+            for (i, point) in decoded.into_iter().enumerate() {
+                if !points.push(start + i, point)? {
+                    return Ok(());
+                }
+            }
+            if got < chunk.len() {
+                return Err(Error::UnexpectedEof {
+                    section,
+                    point_index: Some(start + got / n),
+                    expected_bytes: (end * n) as u64,
+                    got_bytes: (start * n + got) as u64,
+                });
+            }
+            start += chunk_points;
+        }
+        Ok(())
+    }
+}
+
+// The points decoded so far, passed through `map`
+struct Points<'a, T, U, F> {
+    points: Vec<U>,
+    invalid: Vec<usize>,
+    options: &'a ReadOptions,
+    recover: bool,
+    placeholder: T,
+    map: F,
+}
+
+impl<T: Copy, U, F: FnMut(usize, T) -> Option<U>> Points<'_, T, U, F> {
+    // Record decoded point `index`, returning whether to keep reading
+    fn push(&mut self, index: usize, point: Result<T, Error>) -> Result<bool, Error> {
+        match point {
+            Ok(point) => self.points.extend((self.map)(index, point)),
+            Err(_) if self.recover => {
+                self.points.extend((self.map)(index, self.placeholder));
+                self.invalid.push(index);
+            }
+            Err(e) if self.options.max_invalid_points == 0 => return Err(e),
+            Err(_) => {
+                self.invalid.push(index);
+                if self.invalid.len() == self.options.max_invalid_points {
+                    return Ok(false);
+                }
+            }
+        }
+        Ok(true)
+    }
+}
+
+// Check that the requested points are in the file and within the limits of
+// `options`
+pub(crate) fn check_point_counts<G1, G2>(
+    header: &PtauHeader,
+    num_g1_points: usize,
+    num_g2_points: usize,
+    options: &ReadOptions,
+) -> Result<(), Error> {
+    if num_g1_points > options.max_points || num_g2_points > options.max_points {
+        return Err(Error::ExceededMaxPoints);
+    }
+    let points_allocation = (num_g1_points as u64)
+        .saturating_mul(std::mem::size_of::<G1>() as u64)
+        .saturating_add((num_g2_points as u64).saturating_mul(std::mem::size_of::<G2>() as u64));
+    if points_allocation > options.max_allocation {
+        return Err(Error::ExceededMaxAllocation);
+    }
+
+    let (max_g1_points, max_g2_points) = header.num_points()?;
     if num_g1_points > max_g1_points {
         return Err(Error::InvalidNumG1Points);
     }
     if num_g2_points > max_g2_points {
         return Err(Error::InvalidNumG2Points);
     }
+    Ok(())
+}
+
+// The position of a section of points, checking that its recorded size holds
+// the points up to `end`. Sections of the expected size hold all the points
+// allowed by the power, but lenient reads don't check the section sizes.
+pub(crate) fn points_position(
+    header: &PtauHeader,
+    section: u32,
+    end: usize,
+    point_size: usize,
+) -> Result<u64, Error> {
+    let s = header
+        .section(section)
+        .ok_or(Error::MissingSection(section))?;
+    let available = usize::try_from(s.size / point_size as u64).unwrap_or(usize::MAX);
+    if end > available {
+        return Err(Error::NotEnoughPoints {
+            section,
+            requested: end,
+            available,
+        });
+    }
+    Ok(s.position)
+}
+
+// The size of the points stored in a section, for sections made of points,
+// in a file whose base field elements take `n8` bytes
+pub(crate) fn point_size(section: u32, n8: u32) -> Option<u64> {
+    match section {
+        2 | 4 | 5 | 12 | 14 | 15 => Some(2 * n8 as u64),
+        3 | 6 | 13 => Some(4 * n8 as u64),
+        _ => None,
+    }
+}
+
+// Whether a section is made of G2 points
+pub(crate) fn is_g2_section(section: u32) -> bool {
+    matches!(section, 3 | 6 | 13)
+}
+
+pub(crate) fn io_error(e: std::io::Error) -> Error {
+    Error::Io(e)
+}
+
+// Read until `buf` is full or the reader is exhausted, returning the number
+// of bytes read
+fn read_up_to<R: Read + Seek>(
+    reader: &mut R,
+    buf: &mut [u8],
+    section: u32,
+) -> Result<usize, Error> {
+    let mut read = 0;
+    while read < buf.len() {
+        match reader.read(&mut buf[read..]) {
+            Ok(0) => break,
+            Ok(n) => read += n,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+            Err(e) => {
+                return Err(Error::SectionIo {
+                    section,
+                    offset: reader.stream_position().ok(),
+                    source: e,
+                })
+            }
+        }
+    }
+    Ok(read)
+}
+
+pub(crate) fn read_bytes<R: Read + Seek>(
+    reader: &mut R,
+    buf: &mut [u8],
+    section: u32,
+) -> Result<(), Error> {
+    let got = read_up_to(reader, buf, section)?;
+    if got < buf.len() {
+        return Err(Error::UnexpectedEof {
+            section,
+            point_index: None,
+            expected_bytes: buf.len() as u64,
+            got_bytes: got as u64,
+        });
+    }
+    Ok(())
+}
+
+fn read_u32<R: Read + Seek>(reader: &mut R, section: u32) -> Result<u32, Error> {
+    let mut buf = [0u8; 4];
+    read_bytes(reader, &mut buf, section)?;
+    Ok(u32::from_le_bytes(buf))
+}
+
+// Read point `index` of the `num_points` points being read from a section
+pub(crate) fn read_point<R: Read + Seek>(
+    reader: &mut R,
+    buf: &mut [u8],
+    section: u32,
+    index: usize,
+    num_points: usize,
+) -> Result<(), Error> {
+    let got = read_up_to(reader, buf, section)?;
+    if got < buf.len() {
+        let point_size = buf.len() as u64;
+        return Err(Error::UnexpectedEof {
+            section,
+            point_index: Some(index),
+            expected_bytes: num_points as u64 * point_size,
+            got_bytes: index as u64 * point_size + got as u64,
+        });
+    }
+    Ok(())
+}
+
+pub(crate) fn check_infinity(options: &ReadOptions, group: &str) -> Result<(), Error> {
+    match options.infinity_policy {
+        InfinityPolicy::Reject => Err(Error::PointAtInfinity),
+        InfinityPolicy::Accept => Ok(()),
+        InfinityPolicy::Warn => {
+            log::warn!("accepting a {} point at infinity", group);
+            Ok(())
+        }
+    }
+}
+
+// How the points of a section are stored: their size in bytes, and how to
+// decode them from a buffer of that size
+pub(crate) struct PointFormat<T> {
+    pub(crate) size: usize,
+    pub(crate) decode: fn(&[u8], &ReadOptions) -> Result<T, Error>,
+}
+
+impl<T> Clone for PointFormat<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for PointFormat<T> {}
+
+pub(crate) const G1_FORMAT: PointFormat<G1Affine> = PointFormat {
+    size: 64,
+    decode: decode_g1,
+};
+
+pub(crate) const G2_FORMAT: PointFormat<G2Affine> = PointFormat {
+    size: 128,
+    decode: decode_g2,
+};
+
+// Decode a G1 point stored as 2 field elements
+pub(crate) fn decode_g1(buf: &[u8], options: &ReadOptions) -> Result<G1Affine, Error> {
+    curve::decode_g1::<Bn254>(buf, options)
+}
+
+// Decode a G2 point stored as 4 field elements
+pub(crate) fn decode_g2(buf: &[u8], options: &ReadOptions) -> Result<G2Affine, Error> {
+    curve::decode_g2::<Bn254>(buf, options)
+}
+
+pub(crate) const FR_TWO_ADICITY: u32 = <FrParameters as FftParameters>::TWO_ADICITY;
+
+pub(crate) const FQ_MODULUS: BigInteger256 = <Fq as PrimeField>::Params::MODULUS;
+
+// Encode a G1 point the way `decode_g1` expects it, with the point at
+// infinity encoded as all zeroes
+pub(crate) fn encode_g1(g1: &G1Affine, out: &mut Vec<u8>) {
+    if g1.infinity {
+        out.extend_from_slice(&[0u8; 64]);
+    } else {
+        encode_fq(&g1.x, out);
+        encode_fq(&g1.y, out);
+    }
+}
+
+// Encode a G2 point the way `decode_g2` expects it, with the point at
+// infinity encoded as all zeroes
+pub(crate) fn encode_g2(g2: &G2Affine, out: &mut Vec<u8>) {
+    if g2.infinity {
+        out.extend_from_slice(&[0u8; 128]);
+    } else {
+        encode_fq(&g2.x.c0, out);
+        encode_fq(&g2.x.c1, out);
+        encode_fq(&g2.y.c0, out);
+        encode_fq(&g2.y.c1, out);
+    }
+}
 
-    // Read the G1 points
-    // Seek to section 2
-    let mut g1_points = Vec::<G1Affine>::with_capacity(num_g1_points);
-    let _ = f.seek(SeekFrom::Start(sections[&2]));
-    for _ in 0..num_g1_points {
-        let mut x_buf = [0u8; 32];
-        let mut y_buf = [0u8; 32];
-        let _ = f.read_exact(&mut x_buf);
-        let _ = f.read_exact(&mut y_buf);
-
-        let x_bigint = BigInteger256::read(x_buf.as_slice()).unwrap();
-        let y_bigint = BigInteger256::read(y_buf.as_slice()).unwrap();
-        let x = Fq::new(x_bigint);
-        let y = Fq::new(y_bigint);
-        let g1 = G1Affine::new(x, y, false);
-        if !g1.is_on_curve() {
-            return Err(Error::InvalidG1Point);
-        }
-        g1_points.push(g1);
-    }
-
-    // Seek to section 3
-    let _ = f.seek(SeekFrom::Start(sections[&3]));
-
-    let mut g2_points = Vec::<G2Affine>::with_capacity(num_g2_points);
-    for _ in 0..num_g2_points {
-        let mut x0_buf = [0u8; 32];
-        let mut x1_buf = [0u8; 32];
-        let mut y0_buf = [0u8; 32];
-        let mut y1_buf = [0u8; 32];
-        let _ = f.read_exact(&mut x0_buf);
-        let _ = f.read_exact(&mut x1_buf);
-        let _ = f.read_exact(&mut y0_buf);
-        let _ = f.read_exact(&mut y1_buf);
-        let x0_bigint = BigInteger256::read(x0_buf.as_slice()).unwrap();
-        let x1_bigint = BigInteger256::read(x1_buf.as_slice()).unwrap();
-        let y0_bigint = BigInteger256::read(y0_buf.as_slice()).unwrap();
-        let y1_bigint = BigInteger256::read(y1_buf.as_slice()).unwrap();
-        let x0 = Fq::new(x0_bigint);
-        let x1 = Fq::new(x1_bigint);
-        let y0 = Fq::new(y0_bigint);
-        let y1 = Fq::new(y1_bigint);
-        let x = Fq2::new(x0, x1);
-        let y = Fq2::new(y0, y1);
-        let g2 = G2Affine::new(x, y, false);
-        if !g2.is_on_curve() {
-            return Err(Error::InvalidG2Point);
-        }
-        g2_points.push(g2);
-    }
-    Ok((g1_points, g2_points))
+fn encode_fq(fq: &Fq, out: &mut Vec<u8>) {
+    for limb in (fq.0).0.iter() {
+        out.extend_from_slice(&limb.to_le_bytes());
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::Error;
-    use ark_bn254::{Fq, Fq2, G1Affine, G2Affine};
-    use ark_ff::FromBytes;
+    use super::{Error, ErrorKind, InfinityPolicy, ReadOptions, FQ_MODULUS};
+    use crate::throttle::Throttle;
+    use ark_bn254::{Bn254, Fq, Fq2, G1Affine, G1Projective, G2Affine, G2Projective};
+    use ark_ec::{AffineCurve, PairingEngine, ProjectiveCurve};
+    use ark_ff::Zero;
+    use ark_ff::{BigInteger, BigInteger256, FromBytes, ToBytes};
+
+    // Write a copy of 8.ptau with `bytes` written at `offset`
+    fn tampered_ptau(name: &str, offset: usize, bytes: &[u8]) -> String {
+        let mut data = std::fs::read("8.ptau").unwrap();
+        data[offset..offset + bytes.len()].copy_from_slice(bytes);
+        let path = std::env::temp_dir().join(format!("ppot-rs-{}.ptau", name));
+        std::fs::write(&path, data).unwrap();
+        path.to_str().unwrap().to_string()
+    }
+
     fn hex_to_fq(val: &str) -> Fq {
         assert_eq!(val.len(), 64);
         let bytes_vec = hex::decode(val).unwrap();
@@ -228,4 +2357,841 @@ mod tests {
         let r = super::read(ptau_file, num_g1_points, num_g2_points);
         assert_eq!(r.err().unwrap(), Error::InvalidNumG2Points);
     }
+
+    #[test]
+    pub fn test_read_exceeds_max_power() {
+        let options = ReadOptions::new().max_power(7);
+        let r = super::read_with_options("8.ptau", 1, 1, &options);
+        assert_eq!(r.err().unwrap(), Error::ExceededMaxPower);
+    }
+
+    #[test]
+    pub fn test_read_exceeds_max_points() {
+        let options = ReadOptions::new().max_points(255);
+        let r = super::read_with_options("8.ptau", 256, 1, &options);
+        assert_eq!(r.err().unwrap(), Error::ExceededMaxPoints);
+
+        let r = super::read_with_options("8.ptau", 1, 256, &options);
+        assert_eq!(r.err().unwrap(), Error::ExceededMaxPoints);
+    }
+
+    #[test]
+    pub fn test_read_overflowing_counts() {
+        // Counts whose size in bytes overflows are rejected before the
+        // backend is chosen from it
+        let options = ReadOptions::new().max_points(usize::MAX);
+        let r = super::read_with_options("8.ptau", usize::MAX, 1, &options);
+        assert_eq!(r.err().unwrap(), Error::InvalidNumG1Points);
+        let r = super::read_with_options("8.ptau", 1, usize::MAX, &options);
+        assert_eq!(r.err().unwrap(), Error::InvalidNumG2Points);
+    }
+
+    #[test]
+    pub fn test_read_exceeds_max_allocation() {
+        let options = ReadOptions::new().max_allocation(1024);
+        let r = super::read_with_options("8.ptau", 511, 256, &options);
+        assert_eq!(r.err().unwrap(), Error::ExceededMaxAllocation);
+
+        // The 32-byte modulus in the header is itself an allocation
+        let options = ReadOptions::new().max_allocation(16);
+        let r = super::read_with_options("8.ptau", 0, 0, &options);
+        assert_eq!(r.err().unwrap(), Error::ExceededMaxAllocation);
+    }
+
+    #[test]
+    pub fn test_read_non_canonical() {
+        // The x coordinate of the first G1 point starts at byte 80; add the
+        // modulus to it so that it still reduces to the same value
+        let data = std::fs::read("8.ptau").unwrap();
+        let mut x = BigInteger256::read(&data[80..112]).unwrap();
+        assert!(!x.add_nocarry(&FQ_MODULUS));
+        let mut x_buf = vec![];
+        x.write(&mut x_buf).unwrap();
+        let ptau_file = tampered_ptau("non-canonical", 80, &x_buf);
+
+        let r = super::read(&ptau_file, 511, 256);
+        assert_eq!(r.err().unwrap(), Error::NonCanonicalEncoding);
+
+        let options = ReadOptions::new().lenient(true);
+        let (g1_points, g2_points) =
+            super::read_with_options(&ptau_file, 511, 256, &options).unwrap();
+        assert_eq!(
+            (g1_points, g2_points),
+            super::read("8.ptau", 511, 256).unwrap()
+        );
+    }
+
+    #[test]
+    pub fn test_read_non_canonical_modulus() {
+        // The modulus in the header starts at byte 28; replace it with 2q,
+        // which is only accepted in lenient mode
+        let mut q = FQ_MODULUS;
+        q.mul2();
+        let mut q_buf = vec![];
+        q.write(&mut q_buf).unwrap();
+        let ptau_file = tampered_ptau("non-canonical-modulus", 28, &q_buf);
+
+        let r = super::read(&ptau_file, 1, 1);
+        assert_eq!(r.err().unwrap(), Error::InvalidPrimeOrder);
+
+        let options = ReadOptions::new().lenient(true);
+        assert!(super::read_with_options(&ptau_file, 1, 1, &options).is_ok());
+    }
+
+    #[test]
+    pub fn test_read_collect_invalid_points() {
+        // Corrupt G1 points 3 and 7 and G2 point 5
+        let mut data = std::fs::read("8.ptau").unwrap();
+        data[80 + 3 * 64] ^= 1;
+        data[80 + 7 * 64 + 32] ^= 1;
+        data[32796 + 5 * 128] ^= 1;
+        let ptau_file = std::env::temp_dir().join("ppot-rs-invalid-points.ptau");
+        std::fs::write(&ptau_file, data).unwrap();
+        let ptau_file = ptau_file.to_str().unwrap();
+
+        let r = super::read(ptau_file, 511, 256);
+        assert_eq!(r.err().unwrap(), Error::InvalidG1Point);
+
+        let options = ReadOptions::new().collect_invalid_points(10);
+        let r = super::read_with_options(ptau_file, 511, 256, &options);
+        assert_eq!(
+            r.err().unwrap(),
+            Error::InvalidPoints {
+                g1: vec![3, 7],
+                g2: vec![5]
+            }
+        );
+
+        // The cap bounds the number of indices reported per section
+        let options = ReadOptions::new().collect_invalid_points(1);
+        let r = super::read_with_options(ptau_file, 511, 256, &options);
+        assert_eq!(
+            r.err().unwrap(),
+            Error::InvalidPoints {
+                g1: vec![3],
+                g2: vec![5]
+            }
+        );
+    }
+
+    #[test]
+    pub fn test_read_recover() {
+        // Corrupt G1 point 2 and G2 point 0
+        let mut data = std::fs::read("8.ptau").unwrap();
+        data[80 + 2 * 64] ^= 1;
+        data[32796] ^= 1;
+        let ptau_file = std::env::temp_dir().join("ppot-rs-recover.ptau");
+        std::fs::write(&ptau_file, data).unwrap();
+        let ptau_file = ptau_file.to_str().unwrap();
+
+        let options = ReadOptions::new();
+        let srs = super::read_recover(ptau_file, 511, 256, &options).unwrap();
+        assert_eq!(srs.invalid_g1, vec![2]);
+        assert_eq!(srs.invalid_g2, vec![0]);
+        assert_eq!(srs.g1_points.len(), 511);
+        assert_eq!(srs.g2_points.len(), 256);
+        assert!(srs.g1_points[2].is_zero());
+        assert!(srs.g2_points[0].is_zero());
+
+        // Every other point matches an undamaged read
+        let (g1_points, g2_points) = super::read("8.ptau", 511, 256).unwrap();
+        for i in (0..511).filter(|i| *i != 2) {
+            assert_eq!(srs.g1_points[i], g1_points[i]);
+        }
+        assert_eq!(srs.g2_points[1..], g2_points[1..]);
+    }
+
+    #[test]
+    pub fn test_parse() {
+        let data = std::fs::read("8.ptau").unwrap();
+        let ptau = super::parse(&data).unwrap();
+        assert_eq!(ptau.header.n8, 32);
+        assert_eq!(ptau.header.power, 8);
+        assert_eq!(ptau.header.ceremony_power, 28);
+        assert_eq!(ptau.header.sections.len(), 11);
+        assert_eq!(ptau.header.section(2).unwrap().position, 80);
+
+        let (g1_points, g2_points) = super::read("8.ptau", 511, 256).unwrap();
+        assert_eq!(ptau.g1_points, g1_points);
+        assert_eq!(ptau.g2_points, g2_points);
+    }
+
+    #[test]
+    pub fn test_parse_other_sections() {
+        let data = std::fs::read("8.ptau").unwrap();
+        let ptau = super::parse(&data).unwrap();
+        assert_eq!(ptau.header.unknown_sections().count(), 0);
+
+        // Only sections 1 to 3, plus a section snarkjs doesn't define
+        let mut other = data[..32796 + 256 * 128].to_vec();
+        other[8..12].copy_from_slice(&4u32.to_le_bytes());
+        other.extend_from_slice(&99u32.to_le_bytes());
+        other.extend_from_slice(&3u64.to_le_bytes());
+        other.extend_from_slice(b"abc");
+        let other_ptau = super::parse(&other).unwrap();
+        assert_eq!(other_ptau.g1_points, ptau.g1_points);
+        assert_eq!(other_ptau.g2_points, ptau.g2_points);
+        let unknown: Vec<_> = other_ptau.header.unknown_sections().collect();
+        assert_eq!(
+            unknown,
+            vec![&super::Section {
+                id: 99,
+                position: other.len() as u64 - 3,
+                size: 3
+            }]
+        );
+
+        // A section table running past the end of the file
+        other[8..12].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert_eq!(
+            super::parse(&other).err(),
+            Some(Error::UnexpectedEof {
+                section: 0,
+                point_index: None,
+                expected_bytes: 4,
+                got_bytes: 0
+            })
+        );
+    }
+
+    #[test]
+    pub fn test_parse_short_section() {
+        // Section 2 cut down to 100 points, followed by section 3
+        let data = std::fs::read("8.ptau").unwrap();
+        let mut short = data[..80 + 100 * 64].to_vec();
+        short[8..12].copy_from_slice(&3u32.to_le_bytes());
+        short[72..80].copy_from_slice(&(100u64 * 64).to_le_bytes());
+        short.extend_from_slice(&data[32784..32796 + 256 * 128]);
+
+        assert_eq!(
+            super::parse(&short).err(),
+            Some(Error::SectionSizeMismatch {
+                section: 2,
+                expected: 511 * 64,
+                actual: 100 * 64
+            })
+        );
+        // Lenient reads don't check the section sizes up front, but still
+        // don't read past the end of the section
+        let lenient = ReadOptions::new().lenient(true);
+        assert_eq!(
+            super::parse_with_options(&short, &lenient).err(),
+            Some(Error::NotEnoughPoints {
+                section: 2,
+                requested: 511,
+                available: 100
+            })
+        );
+        let ptau = super::parse(&data).unwrap();
+        let mut reader = std::io::Cursor::new(&short);
+        let (g1_points, g2_points) = super::read_from(&mut reader, 100, 256, &lenient).unwrap();
+        assert_eq!(g1_points, ptau.g1_points[..100]);
+        assert_eq!(g2_points, ptau.g2_points);
+    }
+
+    #[test]
+    pub fn test_parse_truncated() {
+        let data = std::fs::read("8.ptau").unwrap();
+        for len in [0, 3, 11, 20, 60, 80, 1000, 32796, 40000, data.len() - 1] {
+            let r = super::parse(&data[..len]);
+            assert!(matches!(r, Err(Error::UnexpectedEof { .. })), "len {}", len);
+        }
+
+        // Cut off in the middle of the magic string
+        let r = super::parse(&data[..3]);
+        assert_eq!(
+            r.err().unwrap(),
+            Error::UnexpectedEof {
+                section: 0,
+                point_index: None,
+                expected_bytes: 4,
+                got_bytes: 3
+            }
+        );
+
+        // Cut off 10 bytes into G1 point 3
+        let r = super::parse(&data[..80 + 3 * 64 + 10]);
+        assert_eq!(
+            r.err().unwrap(),
+            Error::UnexpectedEof {
+                section: 2,
+                point_index: Some(3),
+                expected_bytes: 511 * 64,
+                got_bytes: 3 * 64 + 10
+            }
+        );
+    }
+
+    #[test]
+    pub fn test_parse_mutated() {
+        // Flip random bytes of the header and section table; parse must
+        // return (an error or not) rather than panic
+        let data = std::fs::read("8.ptau").unwrap();
+        let mut state = 0x2545f4914f6cdd1du64;
+        for _ in 0..200 {
+            let mut mutated = data.clone();
+            for _ in 0..4 {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                let i = (state % 80) as usize;
+                mutated[i] = (state >> 32) as u8;
+            }
+            let _ = super::parse(&mutated);
+        }
+    }
+
+    #[test]
+    pub fn test_read_power_too_large() {
+        // The power in the header is at byte 60
+        let options = ReadOptions::new().max_power(u32::MAX);
+        for power in [usize::BITS - 1, usize::BITS, u32::MAX] {
+            let ptau_file = tampered_ptau("power-too-large", 60, &power.to_le_bytes());
+            let r = super::read_with_options(&ptau_file, 1, 1, &options);
+            assert_eq!(r.err().unwrap(), Error::PowerTooLarge);
+        }
+    }
+
+    #[test]
+    pub fn test_read_power_exceeds_two_adicity() {
+        let options = ReadOptions::new().max_power(32).lenient(true);
+        let ptau_file = tampered_ptau("power-exceeds-two-adicity", 60, &29u32.to_le_bytes());
+        let r = super::read_with_options(&ptau_file, 1, 1, &options);
+        assert_eq!(
+            r.err().unwrap(),
+            Error::PowerExceedsTwoAdicity {
+                power: 29,
+                two_adicity: 28
+            }
+        );
+    }
+
+    #[test]
+    pub fn test_domain_size() {
+        let mut f = std::fs::File::open("8.ptau").unwrap();
+        let header = super::read_header_from(&mut f, &ReadOptions::default()).unwrap();
+        assert_eq!(header.domain_size(0), Ok(1));
+        assert_eq!(header.domain_size(100), Ok(128));
+        assert_eq!(header.domain_size(256), Ok(256));
+        assert_eq!(
+            header.domain_size(257),
+            Err(Error::DomainTooLarge {
+                size: 257,
+                max_size: 256
+            })
+        );
+        assert_eq!(
+            header.domain_size(usize::MAX),
+            Err(Error::DomainTooLarge {
+                size: usize::MAX,
+                max_size: 256
+            })
+        );
+    }
+
+    #[test]
+    pub fn test_read_throttled() {
+        let throttle = Throttle::new().bytes_per_second(1 << 20);
+        let options = ReadOptions::new().throttle(throttle);
+        let start = std::time::Instant::now();
+        let (g1_points, g2_points) =
+            super::read_with_options("8.ptau", 511, 256, &options).unwrap();
+        // Reading 64 KiB of points takes at least 1/16 s, less the last
+        // buffered read
+        assert!(start.elapsed() >= std::time::Duration::from_millis(50));
+        assert_eq!(
+            (g1_points, g2_points),
+            super::read("8.ptau", 511, 256).unwrap()
+        );
+    }
+
+    #[test]
+    pub fn test_phase1_elements() {
+        let options = ReadOptions::default();
+        let alpha_g1 = super::alpha_g1("8.ptau", &options).unwrap();
+        let beta_g1 = super::beta_g1("8.ptau", &options).unwrap();
+        let beta_g2 = super::beta_g2("8.ptau", &options).unwrap();
+        let (g1_points, g2_points) = super::read("8.ptau", 1, 1).unwrap();
+        assert_eq!(
+            Bn254::pairing(beta_g1, g2_points[0]),
+            Bn254::pairing(g1_points[0], beta_g2)
+        );
+        assert_ne!(alpha_g1, beta_g1);
+
+        // Section 6 starts at byte 98368
+        let ptau_file = tampered_ptau("beta-g2", 98368, &[0; 128]);
+        assert_eq!(
+            super::beta_g2(&ptau_file, &options),
+            Err(Error::PointAtInfinity)
+        );
+    }
+
+    #[test]
+    pub fn test_read_truncated() {
+        // A download cut off one byte into G1 point 100
+        let mut data = std::fs::read("8.ptau").unwrap();
+        data.truncate(80 + 100 * 64 + 1);
+        let ptau_file = std::env::temp_dir().join("ppot-rs-truncated.ptau");
+        std::fs::write(&ptau_file, data).unwrap();
+
+        let r = super::read(ptau_file.to_str().unwrap(), 511, 256);
+        assert_eq!(
+            r.err().unwrap(),
+            Error::UnexpectedEof {
+                section: 2,
+                point_index: Some(100),
+                expected_bytes: 511 * 64,
+                got_bytes: 100 * 64 + 1
+            }
+        );
+    }
+
+    #[test]
+    pub fn test_read_section_size_mismatch() {
+        // Lower the power in the header (at byte 60) to 7, so that the
+        // sections are larger than the header says they should be
+        let ptau_file = tampered_ptau("section-size-mismatch", 60, &7u32.to_le_bytes());
+        let r = super::read(&ptau_file, 255, 128);
+        assert_eq!(
+            r.err().unwrap(),
+            Error::SectionSizeMismatch {
+                section: 2,
+                expected: 255 * 64,
+                actual: 511 * 64
+            }
+        );
+
+        let options = ReadOptions::new().lenient(true);
+        let (g1_points, g2_points) =
+            super::read_with_options(&ptau_file, 255, 128, &options).unwrap();
+        assert_eq!(g1_points.len(), 255);
+        assert_eq!(g2_points.len(), 128);
+    }
+
+    #[test]
+    pub fn test_read_strided() {
+        let (g1_points, g2_points) = super::read("8.ptau", 511, 256).unwrap();
+        let options = ReadOptions::default();
+
+        let g1 = super::read_g1_strided("8.ptau", 3, 50, 11, &options).unwrap();
+        let expected: Vec<_> = g1_points.iter().skip(3).step_by(50).copied().collect();
+        assert_eq!(g1, expected);
+        let g2 = super::read_g2_strided("8.ptau", 0, 255, 2, &options).unwrap();
+        assert_eq!(g2, vec![g2_points[0], g2_points[255]]);
+        assert!(super::read_g1_strided("8.ptau", 600, 1, 0, &options)
+            .unwrap()
+            .is_empty());
+
+        assert_eq!(
+            super::read_g1_strided("8.ptau", 11, 50, 11, &options),
+            Err(Error::InvalidNumG1Points)
+        );
+        assert_eq!(
+            super::read_g2_strided("8.ptau", 1, usize::MAX, 2, &options),
+            Err(Error::InvalidNumG2Points)
+        );
+    }
+
+    #[test]
+    pub fn test_read_range() {
+        let (g1_points, g2_points) = super::read("8.ptau", 511, 256).unwrap();
+        let options = ReadOptions::default();
+
+        let g1 = super::read_g1_range("8.ptau", 256, 511, &options).unwrap();
+        assert_eq!(g1, g1_points[256..511]);
+        let g2 = super::read_g2_range("8.ptau", 7, 9, &options).unwrap();
+        assert_eq!(g2, g2_points[7..9]);
+        assert!(super::read_g1_range("8.ptau", 3, 3, &options)
+            .unwrap()
+            .is_empty());
+
+        assert_eq!(
+            super::read_g1_range("8.ptau", 0, 512, &options),
+            Err(Error::InvalidNumG1Points)
+        );
+        assert_eq!(
+            super::read_g2_range("8.ptau", 9, 7, &options),
+            Err(Error::InvalidNumG2Points)
+        );
+
+        // Invalid points are reported by their index in the section
+        let ptau_file = tampered_ptau("range", 80 + 300 * 64, &[0xff; 64]);
+        assert_eq!(
+            super::read_g1_range(&ptau_file, 256, 511, &options),
+            Err(Error::NonCanonicalEncoding)
+        );
+        let options = ReadOptions::default().collect_invalid_points(10);
+        assert_eq!(
+            super::read_g1_range(&ptau_file, 256, 511, &options),
+            Err(Error::InvalidPoints {
+                g1: vec![300],
+                g2: vec![],
+            })
+        );
+        std::fs::remove_file(ptau_file).unwrap();
+    }
+
+    #[test]
+    pub fn test_read_alpha_beta_tau() {
+        let options = ReadOptions::default();
+        let alpha_tau = super::read_alpha_tau_g1("8.ptau", 256, &options).unwrap();
+        let beta_tau = super::read_beta_tau_g1("8.ptau", 2, &options).unwrap();
+        assert_eq!(alpha_tau.len(), 256);
+        assert_eq!(alpha_tau[0], super::alpha_g1("8.ptau", &options).unwrap());
+        assert_eq!(beta_tau[0], super::beta_g1("8.ptau", &options).unwrap());
+
+        // e(alpha * tau, 1) == e(alpha, tau)
+        let (_, g2_points) = super::read("8.ptau", 0, 2).unwrap();
+        assert_eq!(
+            Bn254::pairing(alpha_tau[1], g2_points[0]),
+            Bn254::pairing(alpha_tau[0], g2_points[1])
+        );
+        assert_eq!(
+            Bn254::pairing(beta_tau[1], g2_points[0]),
+            Bn254::pairing(beta_tau[0], g2_points[1])
+        );
+
+        assert_eq!(
+            super::read_alpha_tau_g1("8.ptau", 257, &options),
+            Err(Error::InvalidNumG1Points)
+        );
+    }
+
+    #[test]
+    pub fn test_read_lagrange() {
+        let options = ReadOptions::default();
+        let tau_g1 = super::read_lagrange_g1("8.ptau", 3, &options).unwrap();
+        let tau_g2 = super::read_lagrange_g2("8.ptau", 3, &options).unwrap();
+        let alpha_tau = super::read_lagrange_alpha_g1("8.ptau", 3, &options).unwrap();
+        let beta_tau = super::read_lagrange_beta_g1("8.ptau", 3, &options).unwrap();
+        assert_eq!(tau_g2.len(), 8);
+
+        // e(L_i(tau), 1) == e(1, L_i(tau)) and likewise for alpha and beta
+        let g1 = G1Affine::prime_subgroup_generator();
+        let g2 = G2Affine::prime_subgroup_generator();
+        let alpha = super::alpha_g1("8.ptau", &options).unwrap();
+        let beta = super::beta_g2("8.ptau", &options).unwrap();
+        for i in 0..8 {
+            assert_eq!(Bn254::pairing(tau_g1[i], g2), Bn254::pairing(g1, tau_g2[i]));
+            assert_eq!(
+                Bn254::pairing(alpha_tau[i], g2),
+                Bn254::pairing(alpha, tau_g2[i])
+            );
+            assert_eq!(
+                Bn254::pairing(beta_tau[i], g2),
+                Bn254::pairing(tau_g1[i], beta)
+            );
+        }
+
+        assert_eq!(
+            super::read_lagrange_beta_g1("8.ptau", 8, &options)
+                .unwrap()
+                .len(),
+            256
+        );
+        assert_eq!(
+            super::read_lagrange_g2("8.ptau", 9, &options),
+            Err(Error::DomainTooLarge {
+                size: 512,
+                max_size: 256
+            })
+        );
+    }
+
+    #[test]
+    pub fn test_read_point_at_infinity() {
+        // Zero out G1 point 5
+        let ptau_file = tampered_ptau("infinity", 80 + 5 * 64, &[0u8; 64]);
+        let r = super::read(&ptau_file, 511, 256);
+        assert_eq!(r.err().unwrap(), Error::PointAtInfinity);
+
+        for policy in [InfinityPolicy::Accept, InfinityPolicy::Warn] {
+            let options = ReadOptions::new().infinity_policy(policy);
+            let (g1_points, _) = super::read_with_options(&ptau_file, 511, 256, &options).unwrap();
+            assert!(g1_points[5].is_zero());
+        }
+    }
+
+    #[test]
+    pub fn test_read_power_exceeds_ceremony_power() {
+        let ptau_file = tampered_ptau("ceremony-power", 64, &7u32.to_le_bytes());
+        assert_eq!(
+            super::read(&ptau_file, 1, 1),
+            Err(Error::PowerExceedsCeremonyPower {
+                power: 8,
+                ceremony_power: 7,
+            })
+        );
+        let options = ReadOptions::new().lenient(true);
+        assert!(super::read_with_options(&ptau_file, 1, 1, &options).is_ok());
+    }
+
+    #[test]
+    pub fn test_read_point_checks() {
+        // A G2 point on the curve but outside the prime order subgroup
+        let mut x = Fq2::new(Fq::from(1u64), Fq::from(1u64));
+        let point = loop {
+            match G2Affine::get_point_from_x(x, false) {
+                Some(p) if !p.is_in_correct_subgroup_assuming_on_curve() => break p,
+                _ => x.c0 += Fq::from(1u64),
+            }
+        };
+        let mut bytes = Vec::new();
+        super::encode_g2(&point, &mut bytes);
+        let ptau_file = tampered_ptau("subgroup", 32796 + 3 * 128, &bytes);
+        let options = ReadOptions::new();
+        assert_eq!(
+            super::read_with_options(&ptau_file, 1, 256, &options)
+                .unwrap()
+                .1[3],
+            point
+        );
+        let options = ReadOptions::new().check_subgroup(true);
+        assert_eq!(
+            super::read_with_options(&ptau_file, 1, 256, &options),
+            Err(Error::G2NotInSubgroup)
+        );
+        assert!(super::read_with_options("8.ptau", 1, 256, &options).is_ok());
+
+        // A G1 point off the curve
+        let ptau_file = tampered_ptau("on-curve", 80 + 2 * 64, &[1u8]);
+        assert_eq!(super::read(&ptau_file, 3, 1), Err(Error::InvalidG1Point));
+        let options = ReadOptions::new().check_g1_subgroup(true);
+        assert!(super::read_with_options("8.ptau", 511, 1, &options).is_ok());
+        let options = options.check_on_curve(false);
+        assert_eq!(
+            super::read_with_options(&ptau_file, 3, 1, &options),
+            Err(Error::InvalidG1Point)
+        );
+        let options = ReadOptions::new().check_on_curve(false);
+        assert!(!super::read_with_options(&ptau_file, 3, 1, &options)
+            .unwrap()
+            .0[2]
+            .is_on_curve());
+    }
+
+    #[test]
+    pub fn test_read_all() {
+        let ptau = super::read_all("8.ptau").unwrap();
+        let data = std::fs::read("8.ptau").unwrap();
+        assert_eq!(ptau, super::parse(&data).unwrap());
+        assert_eq!(ptau.g1_points.len(), 511);
+        assert_eq!(ptau.g2_points.len(), 256);
+
+        let options = ReadOptions::new();
+        let (g1_points, g2_points) = super::read_at_least("8.ptau", 100, &options).unwrap();
+        assert_eq!(g1_points, ptau.g1_points[..255]);
+        assert_eq!(g2_points, ptau.g2_points[..128]);
+        let (g1_points, g2_points) = super::read_at_least("8.ptau", 256, &options).unwrap();
+        assert_eq!((g1_points.len(), g2_points.len()), (511, 256));
+        assert_eq!(
+            super::read_at_least("8.ptau", 257, &options),
+            Err(Error::InvalidNumG1Points)
+        );
+        // The largest power of two has no room for twice as many G1 points
+        assert_eq!(
+            super::read_at_least("8.ptau", 1 << (usize::BITS - 1), &options),
+            Err(Error::InvalidNumG1Points)
+        );
+    }
+
+    #[test]
+    pub fn test_read_header() {
+        let header = super::read_header("8.ptau").unwrap();
+        assert_eq!(header.n8, 32);
+        assert_eq!(header.power, 8);
+        assert_eq!(header.ceremony_power, 28);
+        let ids: Vec<u32> = header.sections.iter().map(|s| s.id).collect();
+        assert_eq!(ids, vec![1, 2, 3, 4, 5, 6, 7, 12, 13, 14, 15]);
+        assert_eq!(header.section(2).unwrap().position, 80);
+
+        let options = ReadOptions::new().max_power(7);
+        assert_eq!(
+            super::read_header_with_options("8.ptau", &options),
+            Err(Error::ExceededMaxPower)
+        );
+    }
+
+    #[test]
+    pub fn test_read_curve() {
+        let options = ReadOptions::default();
+        assert_eq!(
+            super::read_curve::<Bn254>("8.ptau", 511, 256, &options).unwrap(),
+            super::read("8.ptau", 511, 256).unwrap()
+        );
+        assert_eq!(
+            super::read_curve_header::<Bn254>("8.ptau", &options).unwrap(),
+            super::read_header("8.ptau").unwrap()
+        );
+    }
+
+    #[test]
+    pub fn test_read_with_hook() {
+        // Keeps the even G1 powers and sums the G2 powers
+        struct Hook {
+            g1_indices: Vec<usize>,
+            g2_sum: G2Projective,
+        }
+
+        impl super::PointHook for Hook {
+            type G1 = G1Projective;
+            type G2 = ();
+
+            fn g1(&mut self, index: usize, point: G1Affine) -> Option<G1Projective> {
+                self.g1_indices.push(index);
+                index.is_multiple_of(2).then(|| point.into_projective())
+            }
+
+            fn g2(&mut self, _index: usize, point: G2Affine) -> Option<()> {
+                self.g2_sum.add_assign_mixed(&point);
+                None
+            }
+        }
+
+        let (g1_points, g2_points) = super::read("8.ptau", 511, 256).unwrap();
+        let mut hook = Hook {
+            g1_indices: Vec::new(),
+            g2_sum: G2Projective::zero(),
+        };
+        let (even, kept) =
+            super::read_with_hook("8.ptau", 20, 256, &ReadOptions::new(), &mut hook).unwrap();
+        let expected: Vec<G1Projective> = g1_points[..20]
+            .iter()
+            .step_by(2)
+            .map(|point| point.into_projective())
+            .collect();
+        assert_eq!(even, expected);
+        assert!(kept.is_empty());
+        assert_eq!(hook.g1_indices, (0..20).collect::<Vec<_>>());
+        let expected = g2_points.iter().fold(G2Projective::zero(), |sum, point| {
+            sum + point.into_projective()
+        });
+        assert_eq!(hook.g2_sum, expected);
+
+        // The counts are checked before the hook sees any point
+        let mut hook = Hook {
+            g1_indices: Vec::new(),
+            g2_sum: G2Projective::zero(),
+        };
+        assert_eq!(
+            super::read_with_hook("8.ptau", 512, 256, &ReadOptions::new(), &mut hook),
+            Err(Error::InvalidNumG1Points)
+        );
+        assert!(hook.g1_indices.is_empty());
+
+        // Invalid points are reported as by `read_with_options`
+        let ptau_file = tampered_ptau("hook-invalid-point", 80 + 3 * 64, &[0xff]);
+        let options = ReadOptions::new().collect_invalid_points(10);
+        assert_eq!(
+            super::read_with_hook(&ptau_file, 511, 256, &options, &mut hook),
+            Err(Error::InvalidPoints {
+                g1: vec![3],
+                g2: vec![]
+            })
+        );
+        assert!(!hook.g1_indices.contains(&3));
+    }
+
+    #[test]
+    pub fn test_read_from() {
+        let data = std::fs::read("8.ptau").unwrap();
+        let options = ReadOptions::new();
+        assert_eq!(
+            super::read_from(std::io::Cursor::new(&data), 511, 256, &options).unwrap(),
+            super::read("8.ptau", 511, 256).unwrap()
+        );
+        assert_eq!(
+            super::read_from(std::io::Cursor::new(&data[..1000]), 16, 1, &options),
+            Err(Error::UnexpectedEof {
+                section: 2,
+                point_index: Some(14),
+                expected_bytes: 32704,
+                got_bytes: 920,
+            })
+        );
+    }
+
+    #[test]
+    pub fn test_read_io_errors() {
+        let err = super::read("does-not-exist.ptau", 1, 1).unwrap_err();
+        assert!(matches!(&err, Error::Io(e) if e.kind() == std::io::ErrorKind::NotFound));
+        assert!(std::error::Error::source(&err).is_some());
+
+        // A reader that fails inside the header section
+        struct FailingReader(std::io::Cursor<Vec<u8>>);
+        impl std::io::Read for FailingReader {
+            fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+                if (24..68).contains(&self.0.position()) {
+                    return Err(std::io::Error::other("disk failure"));
+                }
+                self.0.read(buf)
+            }
+        }
+        impl std::io::Seek for FailingReader {
+            fn seek(&mut self, pos: std::io::SeekFrom) -> std::io::Result<u64> {
+                self.0.seek(pos)
+            }
+        }
+
+        let mut reader = FailingReader(std::io::Cursor::new(std::fs::read("8.ptau").unwrap()));
+        let err = super::read_header_from(&mut reader, &ReadOptions::new()).unwrap_err();
+        assert_eq!(
+            err,
+            Error::SectionIo {
+                section: 1,
+                offset: Some(24),
+                source: std::io::ErrorKind::Other.into(),
+            }
+        );
+        assert_eq!(
+            err.to_string(),
+            "I/O error reading section 1 at offset 24: disk failure"
+        );
+    }
+
+    #[test]
+    pub fn test_error_kind() {
+        let err = super::read("does-not-exist.ptau", 1, 1).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::Io);
+        assert!(!err.is_retryable());
+        assert!(err
+            .user_message()
+            .starts_with("The file could not be read; check"));
+
+        let err = Error::Io(std::io::ErrorKind::TimedOut.into());
+        assert!(err.is_retryable());
+        assert!(err
+            .user_message()
+            .starts_with("The file could not be read; try again"));
+
+        let err = super::parse(b"ptaz").unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::Format);
+        assert!(!err.is_retryable());
+        assert_eq!(
+            err.user_message(),
+            "The file is not a valid ptau file; it may be corrupt \
+             (not a ptau file: invalid magic string)"
+        );
+
+        assert_eq!(
+            super::read("8.ptau", 512, 1).unwrap_err().kind(),
+            ErrorKind::Validation
+        );
+        assert_eq!(Error::PairingCheckFailed.kind(), ErrorKind::Verification);
+        let options = ReadOptions::new().max_power(7);
+        let err = super::read_with_options("8.ptau", 1, 1, &options).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::ResourceLimit);
+
+        let codes: Vec<u8> = [
+            ErrorKind::Io,
+            ErrorKind::Format,
+            ErrorKind::Validation,
+            ErrorKind::Verification,
+            ErrorKind::ResourceLimit,
+        ]
+        .iter()
+        .map(|kind| kind.exit_code())
+        .collect();
+        assert_eq!(codes, [3, 4, 5, 6, 7]);
+    }
 }
@@ -0,0 +1,248 @@
+//! Reading points from ptau files that are not stored locally, fetching only
+//! the byte ranges that are needed: the section table, the header and the
+//! requested points.
+
+use crate::ptau::{
+    check_allocation, points_position, read_header_from, read_point, throttled, Error, PointFormat,
+    ReadOptions, G1_FORMAT, G2_FORMAT,
+};
+use ark_bn254::{G1Affine, G2Affine};
+use std::io::{Read, Seek, SeekFrom};
+
+// The number of bytes fetched at once for small reads
+const READ_AHEAD: usize = 4096;
+
+/// A file that can be read by byte ranges, such as an object behind an
+/// HTTP server or in S3.
+pub trait RangeSource {
+    /// The size of the file.
+    fn size(&self) -> std::io::Result<u64>;
+
+    /// Fill `buf` with the bytes of the file starting at `offset`.
+    fn read_range(&self, offset: u64, buf: &mut [u8]) -> std::io::Result<()>;
+}
+
+/// A `Read + Seek` view of a `RangeSource`. Seeking is free, and reads
+/// fetch at least `READ_AHEAD` bytes (4 KiB) at a time.
+pub struct RangeReader<S> {
+    source: S,
+    len: u64,
+    position: u64,
+    buf: Vec<u8>,
+    buf_start: u64,
+}
+
+impl<S: RangeSource> RangeReader<S> {
+    pub fn new(source: S) -> std::io::Result<Self> {
+        let len = source.size()?;
+        Ok(Self {
+            source,
+            len,
+            position: 0,
+            buf: Vec::new(),
+            buf_start: 0,
+        })
+    }
+}
+
+impl<S: RangeSource> Read for RangeReader<S> {
+    fn read(&mut self, out: &mut [u8]) -> std::io::Result<usize> {
+        let buf_end = self.buf_start + self.buf.len() as u64;
+        if self.position < self.buf_start || self.position >= buf_end {
+            let remaining = self.len.saturating_sub(self.position);
+            let n = remaining.min(out.len().max(READ_AHEAD) as u64) as usize;
+            if n == 0 {
+                return Ok(0);
+            }
+            self.buf.resize(n, 0);
+            self.source.read_range(self.position, &mut self.buf)?;
+            self.buf_start = self.position;
+        }
+        let offset = (self.position - self.buf_start) as usize;
+        let n = out.len().min(self.buf.len() - offset);
+        out[..n].copy_from_slice(&self.buf[offset..offset + n]);
+        self.position += n as u64;
+        Ok(n)
+    }
+}
+
+impl<S: RangeSource> Seek for RangeReader<S> {
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        let position = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::End(offset) => self.len.checked_add_signed(offset),
+            SeekFrom::Current(offset) => self.position.checked_add_signed(offset),
+        };
+        self.position = position
+            .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::InvalidInput, "invalid seek"))?;
+        Ok(self.position)
+    }
+}
+
+/// Read the G1 tau powers at `indices`, fetching only the header and the
+/// requested points from `source`. Reads, and so the fetches they make, are
+/// throttled as set in `options`.
+pub fn read_g1_points<S: RangeSource>(
+    source: S,
+    indices: &[usize],
+    options: &ReadOptions,
+) -> Result<Vec<G1Affine>, Error> {
+    read_points_at(source, indices, options, 2, G1_FORMAT)
+}
+
+/// Like `read_g1_points`, for G2 tau powers.
+pub fn read_g2_points<S: RangeSource>(
+    source: S,
+    indices: &[usize],
+    options: &ReadOptions,
+) -> Result<Vec<G2Affine>, Error> {
+    read_points_at(source, indices, options, 3, G2_FORMAT)
+}
+
+fn read_points_at<S: RangeSource, T>(
+    source: S,
+    indices: &[usize],
+    options: &ReadOptions,
+    section: u32,
+    format: PointFormat<T>,
+) -> Result<Vec<T>, Error> {
+    let mut reader = throttled(RangeReader::new(source).map_err(Error::Io)?, options);
+    let header = read_header_from(&mut reader, options)?;
+    let (num_g1_points, num_g2_points) = header.num_points()?;
+    let (num_points, too_many) = match section {
+        2 => (num_g1_points, Error::InvalidNumG1Points),
+        _ => (num_g2_points, Error::InvalidNumG2Points),
+    };
+    if indices.iter().any(|i| *i >= num_points) {
+        return Err(too_many);
+    }
+    check_allocation::<T>(indices.len(), options)?;
+    let end = indices.iter().max().map_or(0, |i| i + 1);
+    let position = points_position(&header, section, end, format.size)?;
+
+    let mut buf = vec![0u8; format.size];
+    let mut points = Vec::with_capacity(indices.len());
+    for &index in indices {
+        reader
+            .seek(SeekFrom::Start(position + (index * format.size) as u64))
+            .map_err(Error::Io)?;
+        read_point(&mut reader, &mut buf, section, index, index + 1)?;
+        points.push((format.decode)(&buf, options)?);
+    }
+    Ok(points)
+}
+
+/// A file served over HTTP(S) by a server supporting range requests, such
+/// as S3 (including through presigned URLs).
+#[cfg(feature = "http")]
+pub struct HttpSource {
+    url: String,
+    agent: ureq::Agent,
+}
+
+#[cfg(feature = "http")]
+impl HttpSource {
+    pub fn new(url: &str) -> Self {
+        Self {
+            url: url.to_string(),
+            agent: ureq::Agent::new(),
+        }
+    }
+}
+
+#[cfg(feature = "http")]
+impl RangeSource for HttpSource {
+    fn size(&self) -> std::io::Result<u64> {
+        let response = self
+            .agent
+            .head(&self.url)
+            .call()
+            .map_err(std::io::Error::other)?;
+        response
+            .header("Content-Length")
+            .and_then(|len| len.parse().ok())
+            .ok_or_else(|| std::io::Error::other("missing Content-Length"))
+    }
+
+    fn read_range(&self, offset: u64, buf: &mut [u8]) -> std::io::Result<()> {
+        if buf.is_empty() {
+            return Ok(());
+        }
+        let range = format!("bytes={}-{}", offset, offset + buf.len() as u64 - 1);
+        let response = self
+            .agent
+            .get(&self.url)
+            .set("Range", &range)
+            .call()
+            .map_err(std::io::Error::other)?;
+        // A server ignoring the range would send the whole file
+        if response.status() != 206 {
+            return Err(std::io::Error::other("range requests are not supported"));
+        }
+        response.into_reader().read_exact(buf)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{read_g1_points, read_g2_points, RangeSource};
+    use crate::ptau::{self, Error, ReadOptions};
+    use crate::throttle::Throttle;
+    use std::cell::Cell;
+
+    // An in-memory file counting the bytes fetched from it
+    struct CountingSource {
+        data: Vec<u8>,
+        fetched: Cell<u64>,
+    }
+
+    impl RangeSource for &CountingSource {
+        fn size(&self) -> std::io::Result<u64> {
+            Ok(self.data.len() as u64)
+        }
+
+        fn read_range(&self, offset: u64, buf: &mut [u8]) -> std::io::Result<()> {
+            let offset = offset as usize;
+            buf.copy_from_slice(&self.data[offset..offset + buf.len()]);
+            self.fetched.set(self.fetched.get() + buf.len() as u64);
+            Ok(())
+        }
+    }
+
+    #[test]
+    pub fn test_read_remote_points() {
+        let (g1_points, g2_points) = ptau::read("8.ptau", 511, 256).unwrap();
+        let source = CountingSource {
+            data: std::fs::read("8.ptau").unwrap(),
+            fetched: Cell::new(0),
+        };
+        let options = ReadOptions::new();
+
+        let g1 = read_g1_points(&source, &[0, 300, 510], &options).unwrap();
+        assert_eq!(g1, vec![g1_points[0], g1_points[300], g1_points[510]]);
+        // The section table, the header and three blocks of points
+        assert!(source.fetched.get() < 20 * 4096);
+
+        let g2 = read_g2_points(&source, &[255, 1], &options).unwrap();
+        assert_eq!(g2, vec![g2_points[255], g2_points[1]]);
+        assert_eq!(
+            read_g2_points(&source, &[256], &options),
+            Err(Error::InvalidNumG2Points)
+        );
+    }
+
+    #[test]
+    pub fn test_read_remote_points_throttled() {
+        let (g1_points, _) = ptau::read("8.ptau", 511, 256).unwrap();
+        let source = CountingSource {
+            data: std::fs::read("8.ptau").unwrap(),
+            fetched: Cell::new(0),
+        };
+        let options = ReadOptions::new().throttle(Throttle::new().reads_per_second(100));
+        let start = std::time::Instant::now();
+        let g1 = read_g1_points(&source, &[0, 300, 510], &options).unwrap();
+        assert_eq!(g1, vec![g1_points[0], g1_points[300], g1_points[510]]);
+        // The section table and header take more than 30 reads
+        assert!(start.elapsed() >= std::time::Duration::from_millis(250));
+    }
+}
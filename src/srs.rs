@@ -0,0 +1,324 @@
+//! A loaded SRS whose points can be shared between circuits of different
+//! sizes.
+
+use crate::cache::SharedSrs;
+use crate::ptau::{open, read_header_from, read_with_options, Error, ReadOptions};
+use ark_bn254::{G1Affine, G2Affine};
+use std::ops::Range;
+use std::sync::Arc;
+
+/// G1 and G2 tau powers, stored behind `Arc`s so that clones and slices
+/// share the same points instead of copying them.
+#[derive(Debug, Clone)]
+pub struct Srs {
+    g1: Arc<[G1Affine]>,
+    g1_range: Range<usize>,
+    g2: Arc<[G2Affine]>,
+    g2_range: Range<usize>,
+}
+
+impl Srs {
+    pub fn new(g1_points: Vec<G1Affine>, g2_points: Vec<G2Affine>) -> Self {
+        Self::from((g1_points.into(), g2_points.into()))
+    }
+
+    /// Read the first `num_g1_points` and `num_g2_points` tau powers of a
+    /// ptau file.
+    pub fn read(
+        ptau_file: &str,
+        num_g1_points: usize,
+        num_g2_points: usize,
+        options: &ReadOptions,
+    ) -> Result<Self, Error> {
+        let (g1_points, g2_points) =
+            read_with_options(ptau_file, num_g1_points, num_g2_points, options)?;
+        Ok(Self::new(g1_points, g2_points))
+    }
+
+    pub fn g1_points(&self) -> &[G1Affine] {
+        &self.g1[self.g1_range.clone()]
+    }
+
+    pub fn g2_points(&self) -> &[G2Affine] {
+        &self.g2[self.g2_range.clone()]
+    }
+
+    /// A view of the G1 points in `range` (relative to this view) and all
+    /// of its G2 points, sharing storage with `self`.
+    ///
+    /// Panics if `range` is out of bounds.
+    pub fn slice(&self, range: Range<usize>) -> Self {
+        Self {
+            g1_range: subrange(&self.g1_range, range),
+            ..self.clone()
+        }
+    }
+
+    /// A view of the G2 points in `range` (relative to this view) and all
+    /// of its G1 points, sharing storage with `self`.
+    ///
+    /// Panics if `range` is out of bounds.
+    pub fn slice_g2(&self, range: Range<usize>) -> Self {
+        Self {
+            g2_range: subrange(&self.g2_range, range),
+            ..self.clone()
+        }
+    }
+}
+
+/// An SRS for polynomials of degree below `2^POWER`: at least `2^POWER`
+/// G1 powers, and `[1]_2, [tau]_2`. APIs can take a `SizedSrs` of the power
+/// they need, so that an SRS that is too small is rejected when it is
+/// built rather than when it is used.
+#[derive(Debug, Clone)]
+pub struct SizedSrs<const POWER: u32> {
+    srs: Srs,
+}
+
+impl<const POWER: u32> SizedSrs<POWER> {
+    /// The number of G1 powers of the SRS.
+    pub const NUM_G1_POINTS: usize = 1 << POWER;
+
+    /// Check that `srs` has enough points, failing with
+    /// `Error::InvalidNumG1Points` or `Error::InvalidNumG2Points`
+    /// otherwise. Only the first `2^POWER` G1 powers and 2 G2 powers are
+    /// kept.
+    pub fn new(srs: Srs) -> Result<Self, Error> {
+        if srs.g1_points().len() < Self::NUM_G1_POINTS {
+            return Err(Error::InvalidNumG1Points);
+        }
+        if srs.g2_points().len() < 2 {
+            return Err(Error::InvalidNumG2Points);
+        }
+        Ok(Self {
+            srs: srs.slice(0..Self::NUM_G1_POINTS).slice_g2(0..2),
+        })
+    }
+
+    /// Read the `2^POWER` G1 powers and 2 G2 powers of a ptau file.
+    pub fn read(ptau_file: &str, options: &ReadOptions) -> Result<Self, Error> {
+        Self::new(Srs::read(ptau_file, Self::NUM_G1_POINTS, 2, options)?)
+    }
+
+    /// A `SizedSrs` of a smaller power, sharing the points of this one.
+    /// Asking for a larger power fails to compile.
+    pub fn restrict<const SMALLER: u32>(&self) -> SizedSrs<SMALLER> {
+        const { assert!(SMALLER <= POWER, "cannot restrict an SRS to a larger power") };
+        SizedSrs {
+            srs: self.srs.slice(0..SizedSrs::<SMALLER>::NUM_G1_POINTS),
+        }
+    }
+
+    pub fn srs(&self) -> &Srs {
+        &self.srs
+    }
+
+    pub fn into_srs(self) -> Srs {
+        self.srs
+    }
+}
+
+/// How two SRS instances relate, as reported by `SrsDiff::relation`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SrsRelation {
+    Identical,
+    /// The first SRS has the same points as the start of the second.
+    FirstIsPrefix,
+    SecondIsPrefix,
+    /// The SRS disagree on a point, or each has more points than the other
+    /// in one of the groups.
+    Diverged,
+}
+
+/// A structural comparison of two SRS instances, for instance the same
+/// setup loaded from two different sources.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SrsDiff {
+    pub num_g1_points: (usize, usize),
+    pub num_g2_points: (usize, usize),
+    /// The index of the first G1 point that differs, among the points both
+    /// SRS have.
+    pub first_g1_divergence: Option<usize>,
+    pub first_g2_divergence: Option<usize>,
+    /// Whether the `[tau]_2` elements match, if both SRS have one.
+    pub tau_g2_matches: Option<bool>,
+}
+
+impl SrsDiff {
+    pub fn new(first: &Srs, second: &Srs) -> Self {
+        let (g1_a, g1_b) = (first.g1_points(), second.g1_points());
+        let (g2_a, g2_b) = (first.g2_points(), second.g2_points());
+        Self {
+            num_g1_points: (g1_a.len(), g1_b.len()),
+            num_g2_points: (g2_a.len(), g2_b.len()),
+            first_g1_divergence: g1_a.iter().zip(g1_b).position(|(a, b)| a != b),
+            first_g2_divergence: g2_a.iter().zip(g2_b).position(|(a, b)| a != b),
+            tau_g2_matches: match (g2_a.get(1), g2_b.get(1)) {
+                (Some(a), Some(b)) => Some(a == b),
+                _ => None,
+            },
+        }
+    }
+
+    pub fn relation(&self) -> SrsRelation {
+        if self.first_g1_divergence.is_some() || self.first_g2_divergence.is_some() {
+            return SrsRelation::Diverged;
+        }
+        let (g1_a, g1_b) = self.num_g1_points;
+        let (g2_a, g2_b) = self.num_g2_points;
+        if g1_a == g1_b && g2_a == g2_b {
+            SrsRelation::Identical
+        } else if g1_a <= g1_b && g2_a <= g2_b {
+            SrsRelation::FirstIsPrefix
+        } else if g1_a >= g1_b && g2_a >= g2_b {
+            SrsRelation::SecondIsPrefix
+        } else {
+            SrsRelation::Diverged
+        }
+    }
+}
+
+impl From<SharedSrs> for Srs {
+    fn from((g1, g2): SharedSrs) -> Self {
+        Self {
+            g1_range: 0..g1.len(),
+            g1,
+            g2_range: 0..g2.len(),
+            g2,
+        }
+    }
+}
+
+// The extra G1 powers needed above the domain size `n` to commit to PLONK's
+// blinded polynomials: with 2 blinding terms for the wire polynomials and 3
+// for the permutation polynomial, the last piece of the quotient polynomial
+// has degree n + 5
+const PLONK_EXTRA_G1_POWERS: usize = 6;
+
+/// Read an SRS sized for a PLONK circuit of `num_gates` gates (including
+/// public input gates): `n + 6` G1 powers and `[1]_2, [tau]_2`, where `n` is
+/// the size of the smallest radix-2 domain with at least `num_gates`
+/// points.
+pub fn srs_for_plonk(
+    ptau_file: &str,
+    num_gates: usize,
+    options: &ReadOptions,
+) -> Result<Srs, Error> {
+    let mut f = open(ptau_file, options)?;
+    let header = read_header_from(&mut f, options)?;
+    let domain_size = header.domain_size(num_gates)?;
+    Srs::read(ptau_file, domain_size + PLONK_EXTRA_G1_POWERS, 2, options)
+}
+
+fn subrange(outer: &Range<usize>, inner: Range<usize>) -> Range<usize> {
+    assert!(
+        inner.start <= inner.end && inner.end <= outer.end - outer.start,
+        "SRS slice out of bounds"
+    );
+    outer.start + inner.start..outer.start + inner.end
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{SizedSrs, Srs, SrsDiff, SrsRelation};
+    use crate::ptau::{Error, ReadOptions};
+    use std::sync::Arc;
+
+    #[test]
+    pub fn test_slice() {
+        let srs = Srs::read("8.ptau", 511, 256, &ReadOptions::default()).unwrap();
+        let (g1_points, g2_points) = (srs.g1_points().to_vec(), srs.g2_points().to_vec());
+
+        let small = srs.slice(0..64);
+        assert!(Arc::ptr_eq(&small.g1, &srs.g1));
+        assert_eq!(small.g1_points(), &g1_points[..64]);
+        assert_eq!(small.g2_points(), &g2_points[..]);
+
+        // Slices of slices are relative to the view
+        let nested = small.slice(10..20).slice_g2(1..3);
+        assert_eq!(nested.g1_points(), &g1_points[10..20]);
+        assert_eq!(nested.g2_points(), &g2_points[1..3]);
+        assert!(nested.slice(10..10).g1_points().is_empty());
+    }
+
+    #[test]
+    pub fn test_srs_for_plonk() {
+        let options = ReadOptions::default();
+        let srs = super::srs_for_plonk("8.ptau", 100, &options).unwrap();
+        assert_eq!(srs.g1_points().len(), 128 + 6);
+        assert_eq!(srs.g2_points().len(), 2);
+        let srs = super::srs_for_plonk("8.ptau", 256, &options).unwrap();
+        assert_eq!(srs.g1_points().len(), 256 + 6);
+        assert_eq!(
+            super::srs_for_plonk("8.ptau", 257, &options).err().unwrap(),
+            Error::DomainTooLarge {
+                size: 257,
+                max_size: 256
+            }
+        );
+    }
+
+    #[test]
+    pub fn test_diff() {
+        let srs = Srs::read("8.ptau", 511, 256, &ReadOptions::default()).unwrap();
+        let diff = SrsDiff::new(&srs, &srs.slice(0..100));
+        assert_eq!(diff.relation(), SrsRelation::SecondIsPrefix);
+        assert_eq!(diff.num_g1_points, (511, 100));
+        assert_eq!(diff.tau_g2_matches, Some(true));
+        assert_eq!(
+            SrsDiff::new(&srs.slice_g2(0..1), &srs).relation(),
+            SrsRelation::FirstIsPrefix
+        );
+        assert_eq!(
+            SrsDiff::new(&srs.slice(0..10), &srs.slice_g2(0..1)).relation(),
+            SrsRelation::Diverged
+        );
+        assert_eq!(
+            SrsDiff::new(&srs, &srs.clone()).relation(),
+            SrsRelation::Identical
+        );
+
+        let mut g1_points = srs.g1_points().to_vec();
+        g1_points.swap(40, 41);
+        let mut g2_points = srs.g2_points().to_vec();
+        g2_points.swap(1, 2);
+        let diff = SrsDiff::new(&srs, &Srs::new(g1_points, g2_points));
+        assert_eq!(diff.relation(), SrsRelation::Diverged);
+        assert_eq!(diff.first_g1_divergence, Some(40));
+        assert_eq!(diff.first_g2_divergence, Some(1));
+        assert_eq!(diff.tau_g2_matches, Some(false));
+    }
+
+    #[test]
+    #[should_panic(expected = "SRS slice out of bounds")]
+    pub fn test_slice_out_of_bounds() {
+        let srs = Srs::read("8.ptau", 16, 2, &ReadOptions::default()).unwrap();
+        srs.slice(4..8).slice(0..5);
+    }
+
+    #[test]
+    pub fn test_sized_srs() {
+        let options = ReadOptions::default();
+        let srs = SizedSrs::<5>::read("8.ptau", &options).unwrap();
+        assert_eq!(srs.srs().g1_points().len(), 32);
+        assert_eq!(srs.srs().g2_points().len(), 2);
+        let small: SizedSrs<3> = srs.restrict();
+        assert_eq!(small.srs().g1_points(), &srs.srs().g1_points()[..8]);
+        assert_eq!(small.srs().g2_points(), srs.srs().g2_points());
+
+        // 8.ptau has 511 G1 powers, which is not enough for power 9
+        assert_eq!(
+            SizedSrs::<9>::read("8.ptau", &options).err().unwrap(),
+            Error::InvalidNumG1Points
+        );
+        let srs = Srs::read("8.ptau", 8, 1, &options).unwrap();
+        assert_eq!(
+            SizedSrs::<4>::new(srs.clone()).err().unwrap(),
+            Error::InvalidNumG1Points
+        );
+        assert_eq!(
+            SizedSrs::<3>::new(srs).err().unwrap(),
+            Error::InvalidNumG2Points
+        );
+    }
+}
@@ -0,0 +1,276 @@
+//! Generation of random but valid ptau files, and of targeted corruptions of
+//! them, for use in tests. Requires the `testing` feature.
+
+use crate::dev::{g1_multiples, g2_multiples, scalar_powers};
+use crate::ptau::{encode_g1, encode_g2, read_header_from, Error, ReadOptions, FQ_MODULUS};
+use ark_bn254::Fr;
+use ark_ff::{FftField, Field, One, UniformRand};
+use ark_std::rand::RngCore;
+use std::io::Cursor;
+
+/// Generate a ptau file of the given power from random secrets.
+pub fn generate<R: RngCore>(rng: &mut R, power: u32) -> Vec<u8> {
+    let tau = Fr::rand(rng);
+    let alpha = Fr::rand(rng);
+    let beta = Fr::rand(rng);
+    generate_from_secrets(tau, alpha, beta, power)
+}
+
+/// Generate a ptau file of the given power from known secrets.
+///
+/// The file has the same layout as a snarkjs ptau after `prepare phase2`:
+/// sections 1 to 7 (with an empty contribution list), followed by the
+/// Lagrange basis sections 12 to 15.
+pub fn generate_from_secrets(tau: Fr, alpha: Fr, beta: Fr, power: u32) -> Vec<u8> {
+    let num_g2_points = 1usize << power;
+    let num_g1_points = num_g2_points * 2 - 1;
+
+    let tau_powers = scalar_powers(tau, num_g1_points);
+    let alpha_tau_powers: Vec<Fr> = tau_powers[..num_g2_points]
+        .iter()
+        .map(|t| alpha * t)
+        .collect();
+    let beta_tau_powers: Vec<Fr> = tau_powers[..num_g2_points]
+        .iter()
+        .map(|t| beta * t)
+        .collect();
+
+    // The Lagrange sections hold, for each domain size 2^p, the evaluations
+    // at tau of the Lagrange basis polynomials. Section 12 has an extra
+    // domain of size 2^(power + 1), which also needs the tau power missing
+    // from section 2.
+    let mut lagrange = Vec::new();
+    for p in 0..=power {
+        lagrange.extend(lagrange_evaluations(tau, p));
+    }
+    let mut lagrange_tau_g1 = lagrange.clone();
+    lagrange_tau_g1.extend(lagrange_evaluations(tau, power + 1));
+    let lagrange_alpha: Vec<Fr> = lagrange.iter().map(|l| alpha * l).collect();
+    let lagrange_beta: Vec<Fr> = lagrange.iter().map(|l| beta * l).collect();
+
+    let mut header = Vec::new();
+    header.extend_from_slice(&32u32.to_le_bytes());
+    for limb in FQ_MODULUS.0.iter() {
+        header.extend_from_slice(&limb.to_le_bytes());
+    }
+    header.extend_from_slice(&power.to_le_bytes());
+    header.extend_from_slice(&power.to_le_bytes());
+
+    let sections = vec![
+        (1u32, header),
+        (2, g1_section(&tau_powers)),
+        (3, g2_section(&tau_powers[..num_g2_points])),
+        (4, g1_section(&alpha_tau_powers)),
+        (5, g1_section(&beta_tau_powers)),
+        (6, g2_section(&[beta])),
+        (7, 0u32.to_le_bytes().to_vec()),
+        (12, g1_section(&lagrange_tau_g1)),
+        (13, g2_section(&lagrange)),
+        (14, g1_section(&lagrange_alpha)),
+        (15, g1_section(&lagrange_beta)),
+    ];
+
+    let mut data = b"ptau".to_vec();
+    data.extend_from_slice(&1u32.to_le_bytes());
+    data.extend_from_slice(&(sections.len() as u32).to_le_bytes());
+    for (id, contents) in sections.iter() {
+        data.extend_from_slice(&id.to_le_bytes());
+        data.extend_from_slice(&(contents.len() as u64).to_le_bytes());
+        data.extend_from_slice(contents);
+    }
+    data
+}
+
+// Evaluate at tau the Lagrange basis of the domain of size 2^p:
+// L_i = 1/n * sum_{j < n} (w^-i * tau)^j
+fn lagrange_evaluations(tau: Fr, p: u32) -> Vec<Fr> {
+    let n = 1usize << p;
+    let w_inv = Fr::get_root_of_unity(n).unwrap().inverse().unwrap();
+    let n_inv = Fr::from(n as u64).inverse().unwrap();
+    let mut evaluations = Vec::with_capacity(n);
+    let mut w_inv_i = Fr::one();
+    for _ in 0..n {
+        let x = w_inv_i * tau;
+        let sum = if x.is_one() {
+            Fr::from(n as u64)
+        } else {
+            (x.pow([n as u64]) - Fr::one()) * (x - Fr::one()).inverse().unwrap()
+        };
+        evaluations.push(sum * n_inv);
+        w_inv_i *= w_inv;
+    }
+    evaluations
+}
+
+fn g1_section(scalars: &[Fr]) -> Vec<u8> {
+    let mut contents = Vec::with_capacity(scalars.len() * 64);
+    for point in g1_multiples(scalars) {
+        encode_g1(&point, &mut contents);
+    }
+    contents
+}
+
+fn g2_section(scalars: &[Fr]) -> Vec<u8> {
+    let mut contents = Vec::with_capacity(scalars.len() * 128);
+    for point in g2_multiples(scalars) {
+        encode_g2(&point, &mut contents);
+    }
+    contents
+}
+
+/// A targeted corruption of a valid ptau file, such as one produced by
+/// `generate`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mutation {
+    MagicString,
+    Version,
+    /// Claim more sections than the file has room for.
+    NumSections,
+    Modulus,
+    /// Move the G1 point with this index off the curve.
+    G1Point(usize),
+    /// Move the G2 point with this index off the curve.
+    G2Point(usize),
+    /// Encode the x coordinate of the G1 point with this index
+    /// non-canonically, without changing its value.
+    NonCanonicalG1(usize),
+    /// Cut the file off in the middle of the first G1 point.
+    Truncate,
+}
+
+impl Mutation {
+    /// Apply the mutation to a valid ptau file. Point indices must be
+    /// within the section.
+    pub fn apply(&self, data: &[u8]) -> Vec<u8> {
+        let header = read_header_from(&mut Cursor::new(data), &ReadOptions::default())
+            .expect("mutations apply to valid ptau files");
+        let g1_position = header.section(2).unwrap().position as usize;
+        let g2_position = header.section(3).unwrap().position as usize;
+        let modulus_position = header.section(1).unwrap().position as usize + 4;
+
+        let mut data = data.to_vec();
+        match *self {
+            Mutation::MagicString => data[0] ^= 1,
+            Mutation::Version => data[4] ^= 2,
+            Mutation::NumSections => data[8..12].copy_from_slice(&u32::MAX.to_le_bytes()),
+            Mutation::Modulus => data[modulus_position] ^= 1,
+            Mutation::G1Point(i) => data[g1_position + i * 64] ^= 1,
+            Mutation::G2Point(i) => data[g2_position + i * 128] ^= 1,
+            Mutation::NonCanonicalG1(i) => {
+                // Adding the modulus to x can't overflow since it is below
+                // 2^254
+                let position = g1_position + i * 64;
+                let modulus = FQ_MODULUS.0;
+                let mut carry = 0u128;
+                for (j, limb) in modulus.iter().enumerate() {
+                    let range = position + j * 8..position + j * 8 + 8;
+                    let x = u64::from_le_bytes(data[range.clone()].try_into().unwrap());
+                    let sum = x as u128 + *limb as u128 + carry;
+                    data[range].copy_from_slice(&(sum as u64).to_le_bytes());
+                    carry = sum >> 64;
+                }
+            }
+            Mutation::Truncate => data.truncate(g1_position + 32),
+        }
+        data
+    }
+
+    /// The error `ptau::parse` returns for `data`, a valid ptau file, with
+    /// this mutation applied.
+    pub fn expected_error(&self, data: &[u8]) -> Error {
+        match self {
+            Mutation::MagicString => Error::InvalidMagicString,
+            Mutation::Version => Error::InvalidVersion,
+            // The section table runs out after the last section
+            Mutation::NumSections => Error::UnexpectedEof {
+                section: 0,
+                point_index: None,
+                expected_bytes: 4,
+                got_bytes: 0,
+            },
+            Mutation::Modulus => Error::InvalidPrimeOrder,
+            Mutation::G1Point(_) => Error::InvalidG1Point,
+            Mutation::G2Point(_) => Error::InvalidG2Point,
+            Mutation::NonCanonicalG1(_) => Error::NonCanonicalEncoding,
+            Mutation::Truncate => {
+                let header = read_header_from(&mut Cursor::new(data), &ReadOptions::default())
+                    .expect("mutations apply to valid ptau files");
+                let (num_g1_points, _) = header.num_points().unwrap();
+                Error::UnexpectedEof {
+                    section: 2,
+                    point_index: Some(0),
+                    expected_bytes: num_g1_points as u64 * 64,
+                    got_bytes: 32,
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{generate, Mutation};
+    use crate::ptau::{decode_g1, parse, ReadOptions};
+    use ark_bn254::{Bn254, G1Affine, G1Projective, G2Affine};
+    use ark_ec::{AffineCurve, PairingEngine, ProjectiveCurve};
+    use ark_ff::Zero;
+    use ark_std::rand::rngs::StdRng;
+    use ark_std::rand::SeedableRng;
+    use proptest::prelude::*;
+
+    fn mutation(power: u32) -> impl Strategy<Value = Mutation> {
+        let num_g2_points = 1usize << power;
+        prop_oneof![
+            Just(Mutation::MagicString),
+            Just(Mutation::Version),
+            Just(Mutation::NumSections),
+            Just(Mutation::Modulus),
+            (0..num_g2_points * 2 - 1).prop_map(Mutation::G1Point),
+            (0..num_g2_points).prop_map(Mutation::G2Point),
+            (0..num_g2_points * 2 - 1).prop_map(Mutation::NonCanonicalG1),
+            Just(Mutation::Truncate),
+        ]
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(16))]
+
+        #[test]
+        fn test_generate(seed: u64, power in 0u32..4) {
+            let data = generate(&mut StdRng::seed_from_u64(seed), power);
+            let ptau = parse(&data).unwrap();
+            prop_assert_eq!(ptau.header.power, power);
+            prop_assert_eq!(ptau.g1_points.len(), (1 << (power + 1)) - 1);
+            prop_assert_eq!(ptau.g2_points.len(), 1 << power);
+
+            // Consecutive points must share the same ratio tau
+            prop_assert_eq!(ptau.g1_points[0], G1Affine::prime_subgroup_generator());
+            prop_assert_eq!(ptau.g2_points[0], G2Affine::prime_subgroup_generator());
+            for g1_pair in ptau.g1_points.windows(2) {
+                prop_assert_eq!(
+                    Bn254::pairing(g1_pair[1], ptau.g2_points[0]),
+                    Bn254::pairing(g1_pair[0], ptau.g2_points[1])
+                );
+            }
+
+            // The Lagrange basis of the largest domain sums to one
+            let lagrange = ptau.header.section(12).unwrap();
+            let n = 1usize << power;
+            let offset = lagrange.position as usize + (n - 1) * 64;
+            let mut sum = G1Projective::zero();
+            for i in 0..n {
+                let mut x = [0u8; 64];
+                x.copy_from_slice(&data[offset + i * 64..offset + (i + 1) * 64]);
+                sum += decode_g1(&x, &ReadOptions::default()).unwrap().into_projective();
+            }
+            prop_assert_eq!(sum.into_affine(), G1Affine::prime_subgroup_generator());
+        }
+
+        #[test]
+        fn test_mutations((power, mutation) in (0u32..4).prop_flat_map(|p| (Just(p), mutation(p))), seed: u64) {
+            let data = generate(&mut StdRng::seed_from_u64(seed), power);
+            let r = parse(&mutation.apply(&data));
+            prop_assert_eq!(r.err().unwrap(), mutation.expected_error(&data));
+        }
+    }
+}
@@ -0,0 +1,121 @@
+//! Bandwidth and IOPS limits for reading ptau files, so that long reads on
+//! shared storage don't starve other processes.
+
+use std::io::{Read, Result, Seek, SeekFrom};
+use std::time::{Duration, Instant};
+
+/// Limits on the rate at which a file is read. Unset limits are not
+/// enforced.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Throttle {
+    bytes_per_second: Option<u64>,
+    reads_per_second: Option<u64>,
+}
+
+impl Throttle {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn bytes_per_second(mut self, bytes_per_second: u64) -> Self {
+        self.bytes_per_second = Some(bytes_per_second.max(1));
+        self
+    }
+
+    pub(crate) fn bytes_per_second_limit(&self) -> Option<u64> {
+        self.bytes_per_second
+    }
+
+    /// The largest number of read calls made on the file per second.
+    pub fn reads_per_second(mut self, reads_per_second: u64) -> Self {
+        self.reads_per_second = Some(reads_per_second.max(1));
+        self
+    }
+}
+
+/// A reader that sleeps as needed to keep its average rate within a
+/// `Throttle` since it was created.
+pub struct ThrottledReader<R> {
+    inner: R,
+    throttle: Throttle,
+    start: Instant,
+    bytes: u64,
+    reads: u64,
+}
+
+impl<R> ThrottledReader<R> {
+    pub fn new(inner: R, throttle: Throttle) -> Self {
+        Self {
+            inner,
+            throttle,
+            start: Instant::now(),
+            bytes: 0,
+            reads: 0,
+        }
+    }
+
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    // Sleep until `count` operations at `rate` per second are due
+    fn wait(&self, count: u64, rate: Option<u64>) {
+        if let Some(rate) = rate {
+            let due = self.start + Duration::from_secs_f64(count as f64 / rate as f64);
+            let now = Instant::now();
+            if due > now {
+                std::thread::sleep(due - now);
+            }
+        }
+    }
+}
+
+impl<R: Read> Read for ThrottledReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        // Don't let a single large read exceed a tenth of a second's worth
+        // of bytes
+        let len = match self.throttle.bytes_per_second {
+            Some(rate) => buf.len().min((rate / 10).max(1) as usize),
+            None => buf.len(),
+        };
+        self.wait(self.reads, self.throttle.reads_per_second);
+        self.wait(self.bytes, self.throttle.bytes_per_second);
+        let n = self.inner.read(&mut buf[..len])?;
+        self.reads += 1;
+        self.bytes += n as u64;
+        Ok(n)
+    }
+}
+
+impl<R: Seek> Seek for ThrottledReader<R> {
+    fn seek(&mut self, pos: SeekFrom) -> Result<u64> {
+        self.inner.seek(pos)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Throttle, ThrottledReader};
+    use std::io::Read;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    pub fn test_throttled_reader() {
+        let data = vec![7u8; 1000];
+
+        let start = Instant::now();
+        let mut reader = ThrottledReader::new(&data[..], Throttle::new().bytes_per_second(5000));
+        let mut out = Vec::new();
+        reader.read_to_end(&mut out).unwrap();
+        assert_eq!(out, data);
+        assert!(start.elapsed() >= Duration::from_millis(150));
+
+        let start = Instant::now();
+        let mut reader = ThrottledReader::new(&data[..], Throttle::new().reads_per_second(50));
+        let mut buf = [0u8; 100];
+        for _ in 0..6 {
+            reader.read_exact(&mut buf).unwrap();
+        }
+        assert!(start.elapsed() >= Duration::from_millis(100));
+    }
+}
@@ -0,0 +1,208 @@
+//! Derivation of a lower-power ptau file from a higher-power one.
+
+use crate::audit;
+use crate::ptau::{io_error, open, read_header_from, Error, PtauHeader, ReadOptions, Section};
+use crate::writer::write_output;
+use std::io::{BufReader, Read, Seek, SeekFrom, Write};
+
+/// Write to `output` the ptau file of power `power` made of the first tau
+/// powers of `input`, keeping its contributions.
+///
+/// The Lagrange sections of a prepared file store their domains from
+/// smallest to largest, so those of the smaller file are prefixes of the
+/// larger file's and are copied rather than recomputed. The largest domain
+/// of the smaller file uses its real top tau power, as snarkjs does when
+/// truncating prepared files.
+///
+/// The ceremony power of `input` is kept, so that the output still records
+/// the power its contributions were made at. The output is written to a
+/// temporary file renamed to `output` once complete, so `output` may be
+/// `input`.
+pub fn truncate(input: &str, output: &str, power: u32, options: &ReadOptions) -> Result<(), Error> {
+    audit::record("truncate", input, &[("power", power as u64)], || {
+        let mut f = BufReader::new(open(input, options)?);
+        let header = read_header_from(&mut f, options)?;
+        if power > header.power {
+            return Err(Error::DomainTooLarge {
+                size: 1usize.checked_shl(power).unwrap_or(usize::MAX),
+                max_size: 1 << header.power,
+            });
+        }
+        write_output(output, |out, _| {
+            write_truncated(&mut f, &header, out, power)
+        })
+    })
+}
+
+fn write_truncated<R: Read + Seek, W: Write>(
+    reader: &mut R,
+    header: &PtauHeader,
+    out: &mut W,
+    power: u32,
+) -> Result<(), Error> {
+    let g1_size = 2 * header.n8 as u64;
+    let g2_size = 4 * header.n8 as u64;
+    // 2^power tau powers in G2 and 2^(power + 1) - 1 in G1, which is also
+    // the number of points in the domains of size 2^0 to 2^power
+    let num_g2_points = 1u64 << power;
+    let num_g1_points = 2 * num_g2_points - 1;
+
+    out.write_all(b"ptau").map_err(io_error)?;
+    out.write_all(&1u32.to_le_bytes()).map_err(io_error)?;
+    out.write_all(&(header.sections.len() as u32).to_le_bytes())
+        .map_err(io_error)?;
+    for section in &header.sections {
+        let size = match section.id {
+            1 => {
+                let mut contents = Vec::new();
+                contents.extend_from_slice(&header.n8.to_le_bytes());
+                contents.extend_from_slice(&header.q);
+                contents.extend_from_slice(&power.to_le_bytes());
+                contents.extend_from_slice(&header.ceremony_power.to_le_bytes());
+                write_section_header(out, 1, contents.len() as u64).map_err(io_error)?;
+                out.write_all(&contents).map_err(io_error)?;
+                continue;
+            }
+            2 => num_g1_points * g1_size,
+            3 => num_g2_points * g2_size,
+            4 | 5 => num_g2_points * g1_size,
+            // Section 12 has an extra domain of size 2^(power + 1)
+            12 => (2 * num_g1_points + 1) * g1_size,
+            13 => num_g1_points * g2_size,
+            14 | 15 => num_g1_points * g1_size,
+            _ => section.size,
+        };
+        write_section_header(out, section.id, size).map_err(io_error)?;
+        copy_section(reader, section, size, out)?;
+    }
+    Ok(())
+}
+
+// Copy the first `size` bytes of `section` to `out`, failing if the file
+// ends first
+pub(crate) fn copy_section<R: Read + Seek, W: Write>(
+    reader: &mut R,
+    section: &Section,
+    size: u64,
+    out: &mut W,
+) -> Result<(), Error> {
+    reader
+        .seek(SeekFrom::Start(section.position))
+        .map_err(io_error)?;
+    let copied = std::io::copy(&mut reader.by_ref().take(size), out).map_err(io_error)?;
+    if copied < size {
+        return Err(Error::UnexpectedEof {
+            section: section.id,
+            point_index: None,
+            expected_bytes: size,
+            got_bytes: copied,
+        });
+    }
+    Ok(())
+}
+
+pub(crate) fn write_section_header<W: Write>(
+    out: &mut W,
+    id: u32,
+    size: u64,
+) -> std::io::Result<()> {
+    out.write_all(&id.to_le_bytes())?;
+    out.write_all(&(size as i64).to_le_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{copy_section, truncate};
+    use crate::lagrange::check_lagrange_g1;
+    use crate::ptau::{read, read_lagrange_g1, Error, ReadOptions, Section};
+    use crate::verify::verify_file;
+    use std::io::Cursor;
+
+    #[test]
+    pub fn test_truncate() {
+        let options = ReadOptions::default();
+        let output = std::env::temp_dir().join("ppot-rs-truncate-5.ptau");
+        let output = output.to_str().unwrap();
+        truncate("8.ptau", output, 5, &options).unwrap();
+        assert_eq!(verify_file(output, &options), Ok(()));
+
+        let (g1_points, g2_points) = read("8.ptau", 63, 32).unwrap();
+        assert_eq!(
+            read(output, 63, 32).unwrap(),
+            (g1_points.clone(), g2_points)
+        );
+        assert_eq!(read(output, 64, 1), Err(Error::InvalidNumG1Points));
+        for power in 0..=6 {
+            let lagrange = read_lagrange_g1(output, power, &options).unwrap();
+            assert_eq!(
+                lagrange,
+                read_lagrange_g1("8.ptau", power, &options).unwrap()
+            );
+            if power <= 5 {
+                let rng = &mut ark_std::test_rng();
+                assert_eq!(check_lagrange_g1(&g1_points, &lagrange, rng), Ok(()));
+            }
+        }
+
+        assert_eq!(
+            truncate("8.ptau", output, 9, &options),
+            Err(Error::DomainTooLarge {
+                size: 512,
+                max_size: 256
+            })
+        );
+    }
+
+    #[test]
+    pub fn test_truncate_in_place() {
+        let options = ReadOptions::default();
+        let path = std::env::temp_dir().join("ppot-rs-truncate-in-place.ptau");
+        let path = path.to_str().unwrap();
+        std::fs::copy("8.ptau", path).unwrap();
+        truncate(path, path, 4, &options).unwrap();
+        assert_eq!(verify_file(path, &options), Ok(()));
+        let (g1_points, g2_points) = read("8.ptau", 31, 16).unwrap();
+        assert_eq!(read(path, 31, 16).unwrap(), (g1_points, g2_points));
+    }
+
+    #[test]
+    pub fn test_truncate_short_input() {
+        // A download cut off one byte into G1 point 100
+        let dir = std::env::temp_dir();
+        let input = dir.join("ppot-rs-truncate-short-input.ptau");
+        let output = dir.join("ppot-rs-truncate-short-output.ptau");
+        let (input, output) = (input.to_str().unwrap(), output.to_str().unwrap());
+        let mut data = std::fs::read("8.ptau").unwrap();
+        data.truncate(80 + 100 * 64 + 1);
+        std::fs::write(input, data).unwrap();
+        let _ = std::fs::remove_file(output);
+
+        assert_eq!(
+            truncate(input, output, 7, &ReadOptions::default()),
+            Err(Error::UnexpectedEof {
+                section: 2,
+                point_index: Some(100),
+                expected_bytes: 511 * 64,
+                got_bytes: 100 * 64 + 1
+            })
+        );
+        assert!(!std::path::Path::new(output).exists());
+
+        // A file that shrinks after its header is read
+        let section = Section {
+            id: 2,
+            position: 80,
+            size: 511 * 64,
+        };
+        let mut reader = Cursor::new(&b"ptau"[..]);
+        assert_eq!(
+            copy_section(&mut reader, &section, 255 * 64, &mut Vec::new()),
+            Err(Error::UnexpectedEof {
+                section: 2,
+                point_index: None,
+                expected_bytes: 255 * 64,
+                got_bytes: 0
+            })
+        );
+    }
+}
@@ -0,0 +1,563 @@
+//! Validation of ptau files, for release checks of setup bundles, and of
+//! KZG SRS material from other sources.
+
+use crate::audit;
+use crate::audit::json_string;
+use crate::chacha::ChaCha;
+use crate::checksum::{to_hex, Manifest};
+use crate::ptau::{
+    decode_g1, decode_g2, io_error, is_g2_section, open, point_size, read_all_with_options,
+    read_header_from, read_point, read_points_from, read_strided_from, Error, PtauHeader,
+    ReadOptions, G1_FORMAT, G2_FORMAT,
+};
+use ark_bn254::{Bn254, Fr, G1Affine, G2Affine};
+use ark_ec::msm::VariableBaseMSM;
+use ark_ec::{AffineCurve, PairingEngine, ProjectiveCurve};
+use ark_ff::{PrimeField, UniformRand, Zero};
+use ark_std::rand::RngCore;
+use blake2::digest::consts::U32;
+use blake2::{Blake2b, Digest};
+use rayon::prelude::*;
+use std::io::{BufReader, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Check the header of a ptau file and decode every point of every section
+/// made of points, without keeping the points in memory.
+pub fn verify_file(ptau_file: &str, options: &ReadOptions) -> Result<(), Error> {
+    audit::record("verify_file", ptau_file, &[], || {
+        let mut f = BufReader::new(open(ptau_file, options)?);
+        let header = read_header_from(&mut f, options)?;
+        verify_points_from(&mut f, &header, options)
+    })
+}
+
+// Decode every point of the sections of `header` made of points, reading
+// the sections in the order of the table, which is the order of the file
+pub(crate) fn verify_points_from<R: Read + Seek>(
+    reader: &mut R,
+    header: &PtauHeader,
+    options: &ReadOptions,
+) -> Result<(), Error> {
+    for section in &header.sections {
+        let point_size = match point_size(section.id, header.n8) {
+            Some(point_size) => point_size,
+            None => continue,
+        };
+        let num_points = (section.size / point_size) as usize;
+        reader
+            .seek(SeekFrom::Start(section.position))
+            .map_err(io_error)?;
+        let mut buf = vec![0u8; point_size as usize];
+        for index in 0..num_points {
+            read_point(reader, &mut buf, section.id, index, num_points)?;
+            if is_g2_section(section.id) {
+                let _ = decode_g2(&buf, options)?;
+            } else {
+                let _ = decode_g1(&buf, options)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Verify only the first `2^power` tau powers of a ptau file (and
+/// `2^(power + 1) - 1` in G1), for users who will never consume more of a
+/// large ceremony file.
+///
+/// The points of sections 2 to 6 in that range are decoded and checked as
+/// by `verify_kzg_srs`, and the alpha and beta powers are checked to use
+/// the same tau. Lagrange sections are not checked.
+pub fn verify_prefix<R: RngCore>(
+    ptau_file: &str,
+    power: u32,
+    options: &ReadOptions,
+    rng: &mut R,
+) -> Result<(), Error> {
+    audit::record(
+        "verify_prefix",
+        ptau_file,
+        &[("power", power as u64)],
+        || {
+            let mut f = BufReader::new(open(ptau_file, options)?);
+            let header = read_header_from(&mut f, options)?;
+            if power > header.power {
+                return Err(Error::DomainTooLarge {
+                    size: 1usize.checked_shl(power).unwrap_or(usize::MAX),
+                    max_size: 1 << header.power,
+                });
+            }
+            let num_g2_points = 1usize << power;
+            let num_g1_points = 2 * num_g2_points - 1;
+            let srs = read_points_from(
+                &mut f,
+                &header,
+                num_g1_points,
+                num_g2_points,
+                options,
+                false,
+            )?;
+            let (g1_powers, g2_powers) = (srs.g1_points, srs.g2_points);
+            let mut read_g1 = |section| {
+                read_strided_from(
+                    &mut f,
+                    &header,
+                    section,
+                    0,
+                    1,
+                    num_g2_points,
+                    G1_FORMAT,
+                    options,
+                )
+            };
+            let alpha_powers = read_g1(4)?;
+            let beta_powers = read_g1(5)?;
+            let beta_g2 = read_strided_from(&mut f, &header, 6, 0, 1, 1, G2_FORMAT, options)?[0];
+
+            // A single tau power has no ratio to check
+            if power == 0 {
+                return Ok(());
+            }
+            verify_kzg_srs(&g1_powers, &g2_powers, rng)?;
+            for powers in [&alpha_powers, &beta_powers] {
+                let (lo, hi) = random_combinations(powers, rng);
+                if Bn254::pairing(hi, g2_powers[0]) != Bn254::pairing(lo, g2_powers[1]) {
+                    return Err(Error::PairingCheckFailed);
+                }
+            }
+            if Bn254::pairing(beta_powers[0], g2_powers[0]) != Bn254::pairing(g1_powers[0], beta_g2)
+            {
+                return Err(Error::PairingCheckFailed);
+            }
+            Ok(())
+        },
+    )
+}
+
+/// The result of verifying one file found by `verify_dir`.
+#[derive(Debug, PartialEq, Eq)]
+pub enum FileOutcome {
+    /// The header is valid and every point decoded, with the point checks
+    /// of the `ReadOptions`. The points were not checked to be powers of
+    /// the same tau: see `verify_prefix` for that.
+    Decoded,
+    Invalid(Error),
+    /// A setup file in a format that cannot be verified yet (zkey).
+    Unsupported,
+}
+
+/// The outcome of `verify_dir` for each file found, sorted by path.
+#[derive(Debug, PartialEq, Eq)]
+pub struct VerifyReport {
+    pub files: Vec<(PathBuf, FileOutcome)>,
+}
+
+impl VerifyReport {
+    /// Whether every file found was verified and decoded.
+    pub fn all_decoded(&self) -> bool {
+        self.files
+            .iter()
+            .all(|(_, outcome)| *outcome == FileOutcome::Decoded)
+    }
+}
+
+/// Find all `.ptau` and `.zkey` files under `dir`, recursively, and verify
+/// them as by `verify_file` in parallel on the rayon pool selected by
+/// `options`.
+///
+/// Symbolic links to directories are not followed, so links back up the
+/// tree cannot loop. Links to files are verified as the files they point
+/// to, and a broken link is reported as an invalid file.
+pub fn verify_dir<P: AsRef<Path>>(dir: P, options: &ReadOptions) -> Result<VerifyReport, Error> {
+    let mut paths = Vec::new();
+    find_setup_files(dir.as_ref(), &mut paths)?;
+    paths.sort();
+    let files = options.install(|| {
+        paths
+            .into_par_iter()
+            .map(|path| {
+                let outcome = match path.extension().and_then(|e| e.to_str()) {
+                    Some("ptau") => match verify_file(&path.to_string_lossy(), options) {
+                        Ok(()) => FileOutcome::Decoded,
+                        Err(e) => FileOutcome::Invalid(e),
+                    },
+                    _ => FileOutcome::Unsupported,
+                };
+                (path, outcome)
+            })
+            .collect()
+    })?;
+    Ok(VerifyReport { files })
+}
+
+fn find_setup_files(dir: &Path, paths: &mut Vec<PathBuf>) -> Result<(), Error> {
+    for entry in std::fs::read_dir(dir).map_err(io_error)? {
+        let entry = entry.map_err(io_error)?;
+        let path = entry.path();
+        let file_type = entry.file_type().map_err(io_error)?;
+        if file_type.is_dir() {
+            find_setup_files(&path, paths)?;
+        } else if file_type.is_symlink() && path.is_dir() {
+            continue;
+        } else if matches!(
+            path.extension().and_then(|e| e.to_str()),
+            Some("ptau") | Some("zkey")
+        ) {
+            paths.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Check that `g1_powers` and `g2_powers` are powers `[tau^i]_1` and
+/// `[tau^i]_2` of the same tau, for an SRS that did not come from a ptau
+/// file. Each side needs at least 2 points.
+///
+/// Every point must be on the curve, in the prime order subgroup and not at
+/// infinity. G2 subgroup membership is checked as by
+/// `check_g2_subgroup_batched`. The consecutive ratios are checked with 2
+/// pairings per group, using random linear combinations; an inconsistent
+/// SRS passes with probability at most about `1 / |Fr|`.
+pub fn verify_kzg_srs<R: RngCore>(
+    g1_powers: &[G1Affine],
+    g2_powers: &[G2Affine],
+    rng: &mut R,
+) -> Result<(), Error> {
+    if g1_powers.len() < 2 {
+        return Err(Error::InvalidNumG1Points);
+    }
+    if g2_powers.len() < 2 {
+        return Err(Error::InvalidNumG2Points);
+    }
+    for p in g1_powers {
+        if p.is_zero() {
+            return Err(Error::PointAtInfinity);
+        }
+        if !p.is_on_curve() {
+            return Err(Error::InvalidG1Point);
+        }
+        if !p.is_in_correct_subgroup_assuming_on_curve() {
+            return Err(Error::G1NotInSubgroup);
+        }
+    }
+    for p in g2_powers {
+        if p.is_zero() {
+            return Err(Error::PointAtInfinity);
+        }
+        if !p.is_on_curve() {
+            return Err(Error::InvalidG2Point);
+        }
+    }
+    check_g2_subgroup_batched(g2_powers, rng)?;
+
+    // e(sum r_i P_i+1, H_0) == e(sum r_i P_i, H_1), and likewise in G2
+    let (g1_lo, g1_hi) = random_combinations(g1_powers, rng);
+    if Bn254::pairing(g1_hi, g2_powers[0]) != Bn254::pairing(g1_lo, g2_powers[1]) {
+        return Err(Error::PairingCheckFailed);
+    }
+    let (g2_lo, g2_hi) = random_combinations(g2_powers, rng);
+    if Bn254::pairing(g1_powers[0], g2_hi) != Bn254::pairing(g1_powers[1], g2_lo) {
+        return Err(Error::PairingCheckFailed);
+    }
+    Ok(())
+}
+
+/// The outcome of `verify_kzg_srs_seeded`, with the seed its random
+/// challenges were derived from, so that anyone can re-run the exact same
+/// check.
+#[derive(Debug, PartialEq, Eq)]
+pub struct SrsReport {
+    pub seed: [u8; 32],
+    pub num_g1_points: usize,
+    pub num_g2_points: usize,
+    /// The version of this crate, as the challenges derived from a seed
+    /// may change between versions.
+    pub crate_version: &'static str,
+    pub outcome: Result<(), Error>,
+}
+
+impl SrsReport {
+    pub fn to_json(&self) -> String {
+        let outcome = match &self.outcome {
+            Ok(()) => "\"valid\"".to_string(),
+            Err(e) => json_string(&e.to_string()),
+        };
+        format!(
+            concat!(
+                "{{\"seed\":\"{}\",\"num_g1_points\":{},\"num_g2_points\":{},",
+                "\"crate_version\":\"{}\",\"outcome\":{}}}"
+            ),
+            to_hex(&self.seed),
+            self.num_g1_points,
+            self.num_g2_points,
+            self.crate_version,
+            outcome
+        )
+    }
+}
+
+/// A challenge seed binding a verification to a file and a time: the
+/// BLAKE2b-256 hash of the file's BLAKE2b-512 hash and a Unix timestamp.
+pub fn challenge_seed(file_blake2b: &[u8; 64], timestamp: u64) -> [u8; 32] {
+    let mut hasher = Blake2b::<U32>::new();
+    hasher.update(file_blake2b);
+    hasher.update(timestamp.to_le_bytes());
+    hasher.finalize().into()
+}
+
+/// Like `verify_kzg_srs`, drawing the random challenges from a ChaCha20
+/// stream keyed by `seed`.
+pub fn verify_kzg_srs_seeded(
+    g1_powers: &[G1Affine],
+    g2_powers: &[G2Affine],
+    seed: [u8; 32],
+) -> SrsReport {
+    let mut key = [0u32; 8];
+    for (word, bytes) in key.iter_mut().zip(seed.chunks(4)) {
+        *word = u32::from_le_bytes(bytes.try_into().unwrap());
+    }
+    SrsReport {
+        seed,
+        num_g1_points: g1_powers.len(),
+        num_g2_points: g2_powers.len(),
+        crate_version: env!("CARGO_PKG_VERSION"),
+        outcome: verify_kzg_srs(g1_powers, g2_powers, &mut ChaCha::new(key)),
+    }
+}
+
+/// Verify the tau powers of a ptau file as by `verify_kzg_srs_seeded`, with
+/// a seed derived by `challenge_seed` from the file and the current time.
+pub fn verify_file_seeded(ptau_file: &str, options: &ReadOptions) -> Result<SrsReport, Error> {
+    let file_blake2b = Manifest::generate(ptau_file)?.file_blake2b;
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    let ptau = read_all_with_options(ptau_file, options)?;
+    Ok(verify_kzg_srs_seeded(
+        &ptau.g1_points,
+        &ptau.g2_points,
+        challenge_seed(&file_blake2b, timestamp),
+    ))
+}
+
+/// Check that G2 points, assumed to be on the curve, are all in the prime
+/// order subgroup, much faster than checking them one by one.
+///
+/// A random linear combination of the points is checked instead, which
+/// misses a point outside the subgroup with probability at most 1/10069
+/// (the smallest prime factor of the cofactor); this is repeated to bring
+/// that probability below 2^-106. The failing point is not identified.
+pub fn check_g2_subgroup_batched<R: RngCore>(
+    points: &[G2Affine],
+    rng: &mut R,
+) -> Result<(), Error> {
+    const ROUNDS: usize = 8;
+    if points.len() <= ROUNDS {
+        if !points
+            .iter()
+            .all(|p| p.is_in_correct_subgroup_assuming_on_curve())
+        {
+            return Err(Error::G2NotInSubgroup);
+        }
+        return Ok(());
+    }
+    for _ in 0..ROUNDS {
+        let scalars: Vec<_> = points.iter().map(|_| Fr::rand(rng).into_repr()).collect();
+        let combination = VariableBaseMSM::multi_scalar_mul(points, &scalars).into_affine();
+        if !combination.is_in_correct_subgroup_assuming_on_curve() {
+            return Err(Error::G2NotInSubgroup);
+        }
+    }
+    Ok(())
+}
+
+// The same random linear combination of points[0..n-1] and of
+// points[1..n]
+fn random_combinations<G: AffineCurve<ScalarField = Fr>, R: RngCore>(
+    points: &[G],
+    rng: &mut R,
+) -> (G::Projective, G::Projective) {
+    let scalars: Vec<_> = (1..points.len())
+        .map(|_| Fr::rand(rng).into_repr())
+        .collect();
+    let n = points.len();
+    (
+        VariableBaseMSM::multi_scalar_mul(&points[..n - 1], &scalars),
+        VariableBaseMSM::multi_scalar_mul(&points[1..], &scalars),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        challenge_seed, check_g2_subgroup_batched, verify_dir, verify_file, verify_file_seeded,
+        verify_kzg_srs, verify_kzg_srs_seeded, verify_prefix, FileOutcome,
+    };
+    use crate::ptau::{read, Error, ReadOptions};
+    use ark_bn254::{G1Affine, G2Affine};
+    use ark_ec::AffineCurve;
+
+    #[test]
+    pub fn test_verify_file() {
+        assert_eq!(verify_file("8.ptau", &ReadOptions::default()), Ok(()));
+    }
+
+    #[test]
+    pub fn test_verify_dir() {
+        let dir = std::env::temp_dir().join("ppot-rs-verify-dir");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("nested")).unwrap();
+        let data = std::fs::read("8.ptau").unwrap();
+        std::fs::write(dir.join("a.ptau"), &data).unwrap();
+        // A Lagrange G2 point (section 13) off the curve
+        let mut tampered = data;
+        tampered[247168 + 3 * 128] ^= 1;
+        std::fs::write(dir.join("nested/b.ptau"), &tampered).unwrap();
+        std::fs::write(dir.join("c.zkey"), b"zkey").unwrap();
+        std::fs::write(dir.join("notes.txt"), b"").unwrap();
+
+        let report = verify_dir(&dir, &ReadOptions::default()).unwrap();
+        let single_threaded = verify_dir(&dir, &ReadOptions::new().threads(1)).unwrap();
+        assert_eq!(single_threaded, report);
+        assert_eq!(
+            report.files,
+            vec![
+                (dir.join("a.ptau"), FileOutcome::Decoded),
+                (dir.join("c.zkey"), FileOutcome::Unsupported),
+                (
+                    dir.join("nested/b.ptau"),
+                    FileOutcome::Invalid(Error::InvalidG2Point)
+                ),
+            ]
+        );
+        assert!(!report.all_decoded());
+
+        // Links to directories are not followed, even in a loop
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink(&dir, dir.join("nested/loop")).unwrap();
+            std::os::unix::fs::symlink(dir.join("a.ptau"), dir.join("d.ptau")).unwrap();
+            std::os::unix::fs::symlink(dir.join("missing"), dir.join("e.ptau")).unwrap();
+            let report = verify_dir(&dir, &ReadOptions::default()).unwrap();
+            let paths: Vec<_> = report.files.iter().map(|(path, _)| path).collect();
+            assert_eq!(
+                paths,
+                ["a.ptau", "c.zkey", "d.ptau", "e.ptau", "nested/b.ptau"]
+                    .map(|file| dir.join(file))
+                    .iter()
+                    .collect::<Vec<_>>()
+            );
+            assert_eq!(report.files[2].1, FileOutcome::Decoded);
+            assert!(matches!(
+                report.files[3].1,
+                FileOutcome::Invalid(Error::Io(_))
+            ));
+        }
+    }
+
+    #[test]
+    pub fn test_verify_kzg_srs() {
+        let rng = &mut ark_std::test_rng();
+        let (mut g1_powers, mut g2_powers) = read("8.ptau", 100, 20).unwrap();
+        assert_eq!(verify_kzg_srs(&g1_powers, &g2_powers, rng), Ok(()));
+
+        g1_powers.swap(10, 11);
+        assert_eq!(
+            verify_kzg_srs(&g1_powers, &g2_powers, rng),
+            Err(Error::PairingCheckFailed)
+        );
+        g1_powers.swap(10, 11);
+        g2_powers[7] = g2_powers[8];
+        assert_eq!(
+            verify_kzg_srs(&g1_powers, &g2_powers, rng),
+            Err(Error::PairingCheckFailed)
+        );
+
+        // A point of the G2 curve outside the prime order subgroup
+        let mut x = 1u64;
+        let outside = loop {
+            if let Some(p) = G2Affine::get_point_from_x(x.into(), false) {
+                if !p.is_in_correct_subgroup_assuming_on_curve() {
+                    break p;
+                }
+            }
+            x += 1;
+        };
+        g2_powers[7] = outside;
+        assert_eq!(
+            verify_kzg_srs(&g1_powers, &g2_powers, rng),
+            Err(Error::G2NotInSubgroup)
+        );
+        assert_eq!(
+            check_g2_subgroup_batched(&g2_powers, rng),
+            Err(Error::G2NotInSubgroup)
+        );
+        assert_eq!(check_g2_subgroup_batched(&g2_powers[..7], rng), Ok(()));
+        assert_eq!(
+            verify_kzg_srs(&[G1Affine::prime_subgroup_generator()], &g2_powers, rng),
+            Err(Error::InvalidNumG1Points)
+        );
+    }
+
+    #[test]
+    pub fn test_verify_prefix() {
+        let rng = &mut ark_std::test_rng();
+        let options = ReadOptions::default();
+        for power in [0, 1, 4] {
+            assert_eq!(verify_prefix("8.ptau", power, &options, rng), Ok(()));
+        }
+        assert_eq!(
+            verify_prefix("8.ptau", 9, &options, rng),
+            Err(Error::DomainTooLarge {
+                size: 512,
+                max_size: 256
+            })
+        );
+
+        // Swap alpha powers 3 and 4 (section 4 starts at byte 65576)
+        let mut data = std::fs::read("8.ptau").unwrap();
+        let (a, b) = (65576 + 3 * 64, 65576 + 4 * 64);
+        let point: Vec<u8> = data[a..b].to_vec();
+        data.copy_within(b..b + 64, a);
+        data[b..b + 64].copy_from_slice(&point);
+        let ptau_file = std::env::temp_dir().join("ppot-rs-verify-prefix.ptau");
+        std::fs::write(&ptau_file, data).unwrap();
+        let ptau_file = ptau_file.to_str().unwrap();
+        assert_eq!(
+            verify_prefix(ptau_file, 4, &options, rng),
+            Err(Error::PairingCheckFailed)
+        );
+        // Outside of the prefix
+        assert_eq!(verify_prefix(ptau_file, 1, &options, rng), Ok(()));
+    }
+
+    #[test]
+    pub fn test_verify_seeded() {
+        let report = verify_file_seeded("8.ptau", &ReadOptions::default()).unwrap();
+        assert_eq!(report.outcome, Ok(()));
+        assert_eq!((report.num_g1_points, report.num_g2_points), (511, 256));
+
+        // Re-running with the recorded seed gives the same report
+        let (g1_points, mut g2_points) = read("8.ptau", 511, 256).unwrap();
+        assert_eq!(
+            verify_kzg_srs_seeded(&g1_points, &g2_points, report.seed),
+            report
+        );
+
+        g2_points.swap(1, 2);
+        let seed = challenge_seed(&[7u8; 64], 1_700_000_000);
+        let report = verify_kzg_srs_seeded(&g1_points, &g2_points, seed);
+        assert_eq!(report.outcome, Err(Error::PairingCheckFailed));
+        assert_eq!(
+            report.to_json(),
+            format!(
+                concat!(
+                    "{{\"seed\":\"{}\",\"num_g1_points\":511,\"num_g2_points\":256,",
+                    "\"crate_version\":\"{}\",\"outcome\":\"pairing check failed\"}}"
+                ),
+                hex::encode(seed),
+                env!("CARGO_PKG_VERSION")
+            )
+        );
+    }
+}
@@ -0,0 +1,171 @@
+//! The KZG verifier key of a ptau file, and digests of it that circuits and
+//! contracts can use to commit to the setup they were built for.
+//!
+//! Groth16 verifier keys also depend on the circuit-specific phase 2, which
+//! this crate does not read, so only the phase 1 elements are covered.
+
+use crate::ptau::{read_with_options, Error, ReadOptions};
+use crate::srs::Srs;
+use ark_bn254::{Fq, G1Affine, G2Affine};
+use ark_ff::{BigInteger, PrimeField};
+use sha3::{Digest, Keccak256};
+
+/// The points a KZG verifier needs: `[1]_1`, `[1]_2` and `[tau]_2`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KzgVerifierKey {
+    pub g1: G1Affine,
+    pub g2: G2Affine,
+    pub tau_g2: G2Affine,
+}
+
+impl KzgVerifierKey {
+    /// Read the verifier key from the first tau powers of a ptau file.
+    pub fn read(ptau_file: &str, options: &ReadOptions) -> Result<Self, Error> {
+        let (g1_points, g2_points) = read_with_options(ptau_file, 1, 2, options)?;
+        Ok(Self {
+            g1: g1_points[0],
+            g2: g2_points[0],
+            tau_g2: g2_points[1],
+        })
+    }
+
+    /// The verifier key of an SRS, if it has a G1 point and two G2 points.
+    pub fn from_srs(srs: &Srs) -> Option<Self> {
+        match (srs.g1_points(), srs.g2_points()) {
+            ([g1, ..], [g2, tau_g2, ..]) => Some(Self {
+                g1: *g1,
+                g2: *g2,
+                tau_g2: *tau_g2,
+            }),
+            _ => None,
+        }
+    }
+
+    /// The points as the EVM pairing precompiles take them: each coordinate
+    /// as a 32-byte big-endian integer, G1 points as `x, y` and G2 points as
+    /// `x.c1, x.c0, y.c1, y.c0`, with the point at infinity as all zeroes.
+    pub fn evm_encoding(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(320);
+        for fq in g1_coordinates(&self.g1)
+            .into_iter()
+            .chain(g2_coordinates(&self.g2))
+            .chain(g2_coordinates(&self.tau_g2))
+        {
+            out.extend_from_slice(&fq.into_repr().to_bytes_be());
+        }
+        out
+    }
+
+    /// The keccak256 hash of `evm_encoding`, which a Solidity contract
+    /// computes as `keccak256(abi.encodePacked(...))` of the same
+    /// coordinates.
+    pub fn keccak_digest(&self) -> [u8; 32] {
+        Keccak256::digest(self.evm_encoding()).into()
+    }
+
+    /// A Poseidon digest over the BN254 scalar field, with the parameters of
+    /// circomlib's `Poseidon` template. Base field coordinates do not fit in
+    /// the scalar field, so each is split into its high and low 128 bits.
+    /// The digest is `Poseidon(h(g1), h(g2), h(tau_g2))`, where `h` hashes
+    /// the halves of the coordinates of a point in the order of
+    /// `evm_encoding`. Requires the `poseidon` feature.
+    #[cfg(feature = "poseidon")]
+    pub fn poseidon_digest(&self) -> ark_bn254::Fr {
+        let point_digests = [
+            poseidon(&coordinate_halves(&g1_coordinates(&self.g1))),
+            poseidon(&coordinate_halves(&g2_coordinates(&self.g2))),
+            poseidon(&coordinate_halves(&g2_coordinates(&self.tau_g2))),
+        ];
+        let digest = poseidon(&point_digests.iter().map(|d| &d[..]).collect::<Vec<_>>());
+        ark_bn254::Fr::from_be_bytes_mod_order(&digest)
+    }
+}
+
+fn g1_coordinates(p: &G1Affine) -> [Fq; 2] {
+    [p.x, p.y]
+}
+
+fn g2_coordinates(p: &G2Affine) -> [Fq; 4] {
+    [p.x.c1, p.x.c0, p.y.c1, p.y.c0]
+}
+
+// The high and low 16 bytes of the big-endian encoding of each coordinate
+#[cfg(feature = "poseidon")]
+fn coordinate_halves(coordinates: &[Fq]) -> Vec<Vec<u8>> {
+    coordinates
+        .iter()
+        .flat_map(|fq| {
+            let bytes = fq.into_repr().to_bytes_be();
+            [bytes[..16].to_vec(), bytes[16..].to_vec()]
+        })
+        .collect()
+}
+
+// circomlib's Poseidon of big-endian scalar field elements
+#[cfg(feature = "poseidon")]
+fn poseidon<T: AsRef<[u8]>>(inputs: &[T]) -> [u8; 32] {
+    use light_poseidon::{Poseidon, PoseidonBytesHasher};
+    let inputs: Vec<&[u8]> = inputs.iter().map(|input| input.as_ref()).collect();
+    // The inputs are below the modulus and there are at most 8 of them, so
+    // hashing cannot fail
+    Poseidon::<ark_bn254_v04::Fr>::new_circom(inputs.len())
+        .unwrap()
+        .hash_bytes_be(&inputs)
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::KzgVerifierKey;
+    use crate::ptau::{read, ReadOptions};
+    use crate::srs::Srs;
+    use ark_bn254::{G1Affine, G2Affine};
+    use ark_ec::AffineCurve;
+
+    #[test]
+    pub fn test_kzg_verifier_key() {
+        let vk = KzgVerifierKey::read("8.ptau", &ReadOptions::default()).unwrap();
+        let (g1_points, g2_points) = read("8.ptau", 1, 2).unwrap();
+        assert_eq!(vk.g1, g1_points[0]);
+        assert_eq!(vk.tau_g2, g2_points[1]);
+        assert_eq!(
+            KzgVerifierKey::from_srs(&Srs::new(g1_points.clone(), g2_points)),
+            Some(vk)
+        );
+        assert_eq!(
+            KzgVerifierKey::from_srs(&Srs::new(g1_points, vec![vk.g2])),
+            None
+        );
+
+        // The generators as found in Solidity verifiers
+        let generators = KzgVerifierKey {
+            g1: G1Affine::prime_subgroup_generator(),
+            g2: G2Affine::prime_subgroup_generator(),
+            tau_g2: G2Affine::prime_subgroup_generator(),
+        };
+        let encoding = generators.evm_encoding();
+        assert_eq!(encoding.len(), 320);
+        assert_eq!(
+            hex::encode(&encoding[..96]),
+            "0000000000000000000000000000000000000000000000000000000000000001\
+             0000000000000000000000000000000000000000000000000000000000000002\
+             198e9393920d483a7260bfb731fb5d25f1aa493335a9e71297e485b7aef312c2"
+        );
+        assert_ne!(vk.keccak_digest(), generators.keccak_digest());
+    }
+
+    #[cfg(feature = "poseidon")]
+    #[test]
+    pub fn test_poseidon() {
+        // circomlib's Poseidon([1, 2])
+        assert_eq!(
+            hex::encode(super::poseidon(&[[1u8], [2u8]])),
+            "115cc0f5e7d690413df64c6b9662e9cf2a3617f2743245519e19607a4417189a"
+        );
+        let vk = KzgVerifierKey::read("8.ptau", &ReadOptions::default()).unwrap();
+        assert_ne!(
+            vk.poseidon_digest(),
+            KzgVerifierKey { g1: -vk.g1, ..vk }.poseidon_digest()
+        );
+    }
+}
@@ -0,0 +1,260 @@
+//! Writing ptau files from arkworks points, for SRS material generated in
+//! Rust that is to be used with snarkjs.
+
+use crate::ptau::{encode_g1, encode_g2, io_error, Error, FQ_MODULUS};
+use ark_bn254::{FrParameters, G1Affine, G2Affine};
+use ark_ff::FftParameters;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+/// The points of a ptau file, to be written with `write` or `write_file`.
+///
+/// Files always get the header, the tau powers and an empty contributions
+/// section. The alpha and beta sections are only written if set with
+/// `alpha_beta`, and snarkjs needs them for phase 2.
+#[derive(Debug, Clone, Copy)]
+pub struct PtauWriter<'a> {
+    power: u32,
+    ceremony_power: u32,
+    tau_g1: &'a [G1Affine],
+    tau_g2: &'a [G2Affine],
+    alpha_beta: Option<AlphaBeta<'a>>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct AlphaBeta<'a> {
+    alpha_tau_g1: &'a [G1Affine],
+    beta_tau_g1: &'a [G1Affine],
+    beta_g2: G2Affine,
+}
+
+impl<'a> PtauWriter<'a> {
+    /// A file of power `power`, made of the `2^(power + 1) - 1` G1 and
+    /// `2^power` G2 tau powers given.
+    pub fn new(power: u32, tau_g1: &'a [G1Affine], tau_g2: &'a [G2Affine]) -> Self {
+        Self {
+            power,
+            ceremony_power: power,
+            tau_g1,
+            tau_g2,
+            alpha_beta: None,
+        }
+    }
+
+    /// The power of the ceremony the points come from, recorded in the
+    /// header. Defaults to the power of the file.
+    pub fn ceremony_power(mut self, ceremony_power: u32) -> Self {
+        self.ceremony_power = ceremony_power;
+        self
+    }
+
+    /// Write sections 4 to 6: the first `2^power` powers of tau times alpha
+    /// and beta in G1, and beta in G2.
+    pub fn alpha_beta(
+        mut self,
+        alpha_tau_g1: &'a [G1Affine],
+        beta_tau_g1: &'a [G1Affine],
+        beta_g2: G2Affine,
+    ) -> Self {
+        self.alpha_beta = Some(AlphaBeta {
+            alpha_tau_g1,
+            beta_tau_g1,
+            beta_g2,
+        });
+        self
+    }
+
+    /// Write the file to `out`, after checking the number of points against
+    /// the power.
+    pub fn write<W: Write>(&self, out: &mut W) -> Result<(), Error> {
+        self.check()?;
+        self.write_sections(out).map_err(io_error)
+    }
+
+    /// Like `write`, to a new file at `ptau_file`.
+    pub fn write_file(&self, ptau_file: &str) -> Result<(), Error> {
+        self.check()?;
+        let mut out = BufWriter::new(File::create(ptau_file).map_err(io_error)?);
+        self.write_sections(&mut out).map_err(io_error)?;
+        out.flush().map_err(io_error)
+    }
+
+    fn check(&self) -> Result<(), Error> {
+        let two_adicity = <FrParameters as FftParameters>::TWO_ADICITY;
+        if self.power > two_adicity {
+            return Err(Error::PowerExceedsTwoAdicity {
+                power: self.power,
+                two_adicity,
+            });
+        }
+        if self.power > self.ceremony_power {
+            return Err(Error::PowerExceedsCeremonyPower {
+                power: self.power,
+                ceremony_power: self.ceremony_power,
+            });
+        }
+        let num_g2_points = 1usize << self.power;
+        if self.tau_g1.len() != 2 * num_g2_points - 1 {
+            return Err(Error::InvalidNumG1Points);
+        }
+        if self.tau_g2.len() != num_g2_points {
+            return Err(Error::InvalidNumG2Points);
+        }
+        if let Some(alpha_beta) = &self.alpha_beta {
+            if alpha_beta.alpha_tau_g1.len() != num_g2_points
+                || alpha_beta.beta_tau_g1.len() != num_g2_points
+            {
+                return Err(Error::InvalidNumG1Points);
+            }
+        }
+        Ok(())
+    }
+
+    fn write_sections<W: Write>(&self, out: &mut W) -> std::io::Result<()> {
+        let num_sections = if self.alpha_beta.is_some() { 7u32 } else { 4 };
+        out.write_all(b"ptau")?;
+        out.write_all(&1u32.to_le_bytes())?;
+        out.write_all(&num_sections.to_le_bytes())?;
+
+        let mut header = Vec::new();
+        header.extend_from_slice(&32u32.to_le_bytes());
+        for limb in FQ_MODULUS.0.iter() {
+            header.extend_from_slice(&limb.to_le_bytes());
+        }
+        header.extend_from_slice(&self.power.to_le_bytes());
+        header.extend_from_slice(&self.ceremony_power.to_le_bytes());
+        write_section(out, 1, &header)?;
+
+        write_points(out, 2, self.tau_g1, 64, encode_g1)?;
+        write_points(out, 3, self.tau_g2, 128, encode_g2)?;
+        if let Some(alpha_beta) = &self.alpha_beta {
+            write_points(out, 4, alpha_beta.alpha_tau_g1, 64, encode_g1)?;
+            write_points(out, 5, alpha_beta.beta_tau_g1, 64, encode_g1)?;
+            write_points(out, 6, &[alpha_beta.beta_g2], 128, encode_g2)?;
+        }
+        // No contributions
+        write_section(out, 7, &0u32.to_le_bytes())
+    }
+}
+
+// Write a file to `output` through a temporary file next to it, renamed over
+// `output` once complete. `write` gets the path of the temporary file, for
+// reading back what it flushed. A failed write leaves `output` as it was,
+// and `output` may be the file the contents are read from.
+pub(crate) fn write_output<T, F>(output: &str, write: F) -> Result<T, Error>
+where
+    F: FnOnce(&mut BufWriter<File>, &str) -> Result<T, Error>,
+{
+    let temp = format!("{}.{}.tmp", output, std::process::id());
+    let result = File::create(&temp).map_err(io_error).and_then(|f| {
+        let mut out = BufWriter::new(f);
+        let value = write(&mut out, &temp)?;
+        out.flush().map_err(io_error)?;
+        std::fs::rename(&temp, output).map_err(io_error)?;
+        Ok(value)
+    });
+    if result.is_err() {
+        let _ = std::fs::remove_file(&temp);
+    }
+    result
+}
+
+fn write_section<W: Write>(out: &mut W, id: u32, contents: &[u8]) -> std::io::Result<()> {
+    out.write_all(&id.to_le_bytes())?;
+    out.write_all(&(contents.len() as u64).to_le_bytes())?;
+    out.write_all(contents)
+}
+
+fn write_points<W: Write, T>(
+    out: &mut W,
+    id: u32,
+    points: &[T],
+    point_size: usize,
+    encode: fn(&T, &mut Vec<u8>),
+) -> std::io::Result<()> {
+    out.write_all(&id.to_le_bytes())?;
+    out.write_all(&((points.len() * point_size) as u64).to_le_bytes())?;
+    let mut buf = Vec::with_capacity(point_size);
+    for point in points {
+        buf.clear();
+        encode(point, &mut buf);
+        out.write_all(&buf)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PtauWriter;
+    use crate::contributions::read_contributions;
+    use crate::ptau::{
+        beta_g2, parse, read, read_alpha_tau_g1, read_beta_tau_g1, read_header, Error, ReadOptions,
+    };
+    use crate::verify::verify_file;
+
+    #[test]
+    pub fn test_write() {
+        let options = ReadOptions::default();
+        let (tau_g1, tau_g2) = read("8.ptau", 63, 32).unwrap();
+        let alpha_tau_g1 = read_alpha_tau_g1("8.ptau", 32, &options).unwrap();
+        let beta_tau_g1 = read_beta_tau_g1("8.ptau", 32, &options).unwrap();
+        let beta = beta_g2("8.ptau", &options).unwrap();
+
+        let output = std::env::temp_dir().join("ppot-rs-written.ptau");
+        let output = output.to_str().unwrap();
+        PtauWriter::new(5, &tau_g1, &tau_g2)
+            .ceremony_power(28)
+            .alpha_beta(&alpha_tau_g1, &beta_tau_g1, beta)
+            .write_file(output)
+            .unwrap();
+        assert_eq!(verify_file(output, &options), Ok(()));
+        let header = read_header(output).unwrap();
+        assert_eq!((header.power, header.ceremony_power), (5, 28));
+        assert_eq!(
+            read(output, 63, 32).unwrap(),
+            (tau_g1.clone(), tau_g2.clone())
+        );
+        assert_eq!(
+            read_alpha_tau_g1(output, 32, &options).unwrap(),
+            alpha_tau_g1
+        );
+        assert_eq!(read_beta_tau_g1(output, 32, &options).unwrap(), beta_tau_g1);
+        assert_eq!(beta_g2(output, &options).unwrap(), beta);
+        assert!(read_contributions(output, &options).unwrap().is_empty());
+
+        // Without alpha and beta
+        let mut data = Vec::new();
+        PtauWriter::new(5, &tau_g1, &tau_g2)
+            .write(&mut data)
+            .unwrap();
+        let ptau = parse(&data).unwrap();
+        assert_eq!(
+            (ptau.g1_points, ptau.g2_points),
+            (tau_g1.clone(), tau_g2.clone())
+        );
+
+        assert_eq!(
+            PtauWriter::new(5, &tau_g1[..62], &tau_g2).write(&mut Vec::new()),
+            Err(Error::InvalidNumG1Points)
+        );
+        assert_eq!(
+            PtauWriter::new(4, &tau_g1[..31], &tau_g2).write(&mut Vec::new()),
+            Err(Error::InvalidNumG2Points)
+        );
+        assert_eq!(
+            PtauWriter::new(5, &tau_g1, &tau_g2)
+                .alpha_beta(&alpha_tau_g1[..31], &beta_tau_g1, beta)
+                .write(&mut Vec::new()),
+            Err(Error::InvalidNumG1Points)
+        );
+        assert_eq!(
+            PtauWriter::new(5, &tau_g1, &tau_g2)
+                .ceremony_power(4)
+                .write(&mut Vec::new()),
+            Err(Error::PowerExceedsCeremonyPower {
+                power: 5,
+                ceremony_power: 4
+            })
+        );
+    }
+}